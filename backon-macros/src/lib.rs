@@ -9,6 +9,8 @@
 //!
 //! The macro inspects the target signature to decide whether to call
 //! [`Retryable`](backon::Retryable) or [`BlockingRetryable`](backon::BlockingRetryable).
+//! The functions can return a `Result` or any other
+//! [`RetryOutcome`](backon::RetryOutcome), such as an `Option`.
 //! When `context = true` is supplied, it switches to the corresponding `*_WithContext`
 //! traits so the arguments are preserved across retries.
 //!
@@ -58,7 +60,7 @@
 //! * `sleep = path` – Sleeper function used for async or blocking retries.
 //! * `when = path` – Predicate that filters retryable errors.
 //! * `notify = path` – Callback invoked before each sleep.
-//! * `adjust = path` – Async-only hook that can override the delay.
+//! * `adjust = path` – Hook that can override the delay before each sleep.
 //! * `context = true` – Capture inputs into a context tuple and use the
 //!   `RetryableWithContext` traits.
 //!
@@ -307,13 +309,6 @@ fn build_function_body(
         adjust: args.adjust.clone(),
     };
 
-    if chain_config.adjust.is_some() && !is_async {
-        return Err(Error::new(
-            sig.ident.span(),
-            "`adjust` is only available for async functions",
-        ));
-    }
-
     let context_data = if let Some(context) = precomputed_context {
         Some(context)
     } else if force_context || args.context {
@@ -0,0 +1,26 @@
+use core::time::Duration;
+
+use backon_macros::backon;
+
+fn adjuster(_: &&'static str, dur: Option<Duration>) -> Option<Duration> {
+    dur.map(|_| Duration::from_millis(1))
+}
+
+#[backon(adjust = adjuster)]
+fn attempt_blocking() -> Result<(), &'static str> {
+    Err("temporary")
+}
+
+#[backon(context = true, adjust = adjuster)]
+fn attempt_blocking_with_context(value: String) -> Result<String, &'static str> {
+    if value.is_empty() {
+        Err("empty")
+    } else {
+        Ok(value.clone())
+    }
+}
+
+fn main() {
+    let _ = attempt_blocking();
+    let _ = attempt_blocking_with_context("data".to_string());
+}
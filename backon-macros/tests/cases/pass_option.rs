@@ -0,0 +1,16 @@
+use backon_macros::backon;
+
+#[backon(backoff = backon::ConstantBuilder::default)]
+fn lookup_sync() -> Option<i32> {
+    Some(7)
+}
+
+#[backon]
+async fn lookup_async() -> Option<i32> {
+    Some(7)
+}
+
+fn main() {
+    let _ = lookup_sync();
+    let _ = lookup_async();
+}
@@ -7,6 +7,38 @@ use core::time::Duration;
 pub trait Backoff: Iterator<Item = Duration> + Send + Sync + Unpin {}
 impl<T> Backoff for T where T: Iterator<Item = Duration> + Send + Sync + Unpin {}
 
+/// ResettableBackoff is a [`Backoff`] that can be rewound to its initial state.
+///
+/// This is useful for long-lived loops, like reconnecting to a server: once the connection is
+/// healthy again, the next failure should start over from the first delay instead of the last one.
+///
+/// All built-in backoffs implement this trait. Any other backoff can be made resettable with
+/// [`RebuildBackoff`](crate::RebuildBackoff), which builds it again from its builder.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use backon::BackoffBuilder;
+/// use backon::ExponentialBuilder;
+/// use backon::ResettableBackoff;
+///
+/// let mut backoff = ExponentialBuilder::default().with_max_times(2).build();
+/// assert_eq!(backoff.next(), Some(Duration::from_secs(1)));
+/// assert_eq!(backoff.next(), Some(Duration::from_secs(2)));
+/// assert_eq!(backoff.next(), None);
+///
+/// backoff.reset();
+/// assert_eq!(backoff.next(), Some(Duration::from_secs(1)));
+/// ```
+pub trait ResettableBackoff: Backoff {
+    /// Rewind the backoff to the state it had right after being built.
+    ///
+    /// The random number generator used for jitter keeps its state and is not reseeded.
+    fn reset(&mut self);
+}
+
 /// BackoffBuilder is utilized to construct a new backoff.
 pub trait BackoffBuilder: Send + Sync + Unpin {
     /// The associated backoff returned by this builder.
@@ -14,6 +46,15 @@ pub trait BackoffBuilder: Send + Sync + Unpin {
 
     /// Construct a new backoff using the builder.
     fn build(self) -> Self::Backoff;
+
+    /// The maximum delay configured for the backoff, `None` if it's not bounded or not known.
+    ///
+    /// The retries honoring the [`RetryAfterHint`](crate::RetryAfterHint) of the errors clamp
+    /// the hinted delays to it. The hints pass through unclamped when it's `None`, which is the
+    /// case of the [`Backoff`]s used as their own builder, like the hand-written ones.
+    fn max_delay(&self) -> Option<Duration> {
+        None
+    }
 }
 
 impl<B: Backoff> BackoffBuilder for B {
@@ -28,6 +69,7 @@ impl<B: Backoff> BackoffBuilder for B {
 mod tests {
     use super::*;
     use crate::ConstantBuilder;
+    use crate::DecorrelatedJitterBuilder;
     use crate::ExponentialBuilder;
     use crate::FibonacciBuilder;
 
@@ -45,6 +87,7 @@ mod tests {
             test_fn_builder(&ConstantBuilder::default());
             test_fn_builder(&FibonacciBuilder::default());
             test_fn_builder(&ExponentialBuilder::default());
+            test_fn_builder(&DecorrelatedJitterBuilder::default());
         }
     }
 }
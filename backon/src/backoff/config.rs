@@ -0,0 +1,390 @@
+use core::fmt;
+use core::time::Duration;
+
+use crate::backoff::BackoffBuilder;
+use crate::backoff::ConstantBackoff;
+use crate::backoff::ConstantBuilder;
+use crate::backoff::DecorrelatedJitterBackoff;
+use crate::backoff::DecorrelatedJitterBuilder;
+use crate::backoff::ExponentialBackoff;
+use crate::backoff::ExponentialBuilder;
+use crate::backoff::FibonacciBackoff;
+use crate::backoff::FibonacciBuilder;
+use crate::backoff::ResettableBackoff;
+use crate::backoff::Schedule;
+
+/// BackoffConfig is one of the built-in backoff builders, picked at runtime.
+///
+/// It's useful when the retry policy comes from configuration instead of code. With the
+/// `serde` feature enabled, it can be (de)serialized as a map tagged by `kind`, in which
+/// durations are written as human friendly strings like `"100ms"` or `"1m30s"`. Missing
+/// fields take the default value of the corresponding builder.
+///
+/// # String representation
+///
+/// A `BackoffConfig` can also be parsed from a compact policy string like
+/// `exponential(min=100ms, max=10s, factor=2, times=5, jitter)`, which is handy for CLI flags
+/// or environment variables. Omitted arguments take their default value, and the parentheses
+/// can be left out entirely. The builders implement [`Display`](fmt::Display) with the same
+/// syntax, so a policy can be printed and parsed back unchanged.
+///
+/// | kind                  | arguments                                          |
+/// |-----------------------|----------------------------------------------------|
+/// | `exponential`         | `min`, `max`, `factor`, `times`, `total`, `jitter`, `seed` |
+/// | `fibonacci`           | `min`, `max`, `times`, `jitter`, `seed`            |
+/// | `constant`            | `delay`, `times`, `jitter`, `seed`                 |
+/// | `decorrelated_jitter` | `min`, `max`, `times`, `total`, `seed`             |
+///
+/// - `min`, `max`, `delay` and `total` are durations like `100ms`, `1.5s` or `1m30s`.
+/// - `max`, `times` and `total` accept `none` to remove the limit.
+/// - `jitter` alone is the same as `with_jitter()`, while `jitter=<strategy>` picks a
+///   [`Jitter`](crate::Jitter) among `none`, `full`, `equal`, `additive` and `symmetric(ratio)`.
+///
+/// Parsing doesn't allocate and is available under `no_std`. Errors are reported as a
+/// [`ParseBackoffError`](crate::ParseBackoffError) pointing to the invalid part of the input.
+///
+/// ```
+/// use backon::BackoffConfig;
+/// use backon::ExponentialBuilder;
+/// use core::time::Duration;
+///
+/// let policy = "exponential(min=100ms, max=10s, factor=2, times=5, jitter)";
+/// let config: BackoffConfig = policy.parse().unwrap();
+///
+/// assert_eq!(
+///     config,
+///     BackoffConfig::Exponential(
+///         ExponentialBuilder::default()
+///             .with_min_delay(Duration::from_millis(100))
+///             .with_max_delay(Duration::from_secs(10))
+///             .with_max_times(5)
+///             .with_jitter()
+///     )
+/// );
+/// assert_eq!(config.to_string(), policy);
+/// ```
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "serde")]
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// use backon::BackoffConfig;
+/// use backon::ExponentialBuilder;
+/// use core::time::Duration;
+///
+/// let config: BackoffConfig = serde_json::from_str(
+///     r#"{"kind": "exponential", "min_delay": "100ms", "max_delay": "10s", "max_times": 5}"#,
+/// )?;
+///
+/// assert_eq!(
+///     config,
+///     BackoffConfig::Exponential(
+///         ExponentialBuilder::default()
+///             .with_min_delay(Duration::from_millis(100))
+///             .with_max_delay(Duration::from_secs(10))
+///             .with_max_times(5)
+///     )
+/// );
+/// # Ok(())
+/// # }
+/// # #[cfg(not(feature = "serde"))]
+/// # fn main() {}
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(tag = "kind", rename_all = "snake_case")
+)]
+pub enum BackoffConfig {
+    /// Use an [`ExponentialBuilder`].
+    Exponential(ExponentialBuilder),
+    /// Use a [`FibonacciBuilder`].
+    Fibonacci(FibonacciBuilder),
+    /// Use a [`ConstantBuilder`].
+    Constant(ConstantBuilder),
+    /// Use a [`DecorrelatedJitterBuilder`].
+    DecorrelatedJitter(DecorrelatedJitterBuilder),
+}
+
+impl Default for BackoffConfig {
+    fn default() -> Self {
+        BackoffConfig::Exponential(ExponentialBuilder::default())
+    }
+}
+
+impl BackoffConfig {
+    /// Preview the delays planned by the selected builder.
+    ///
+    /// See [`Schedule`] for details.
+    pub fn schedule(&self) -> Schedule {
+        match self {
+            BackoffConfig::Exponential(b) => b.schedule(),
+            BackoffConfig::Fibonacci(b) => b.schedule(),
+            BackoffConfig::Constant(b) => b.schedule(),
+            BackoffConfig::DecorrelatedJitter(b) => b.schedule(),
+        }
+    }
+}
+
+impl fmt::Display for BackoffConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BackoffConfig::Exponential(b) => b.fmt(f),
+            BackoffConfig::Fibonacci(b) => b.fmt(f),
+            BackoffConfig::Constant(b) => b.fmt(f),
+            BackoffConfig::DecorrelatedJitter(b) => b.fmt(f),
+        }
+    }
+}
+
+impl From<ExponentialBuilder> for BackoffConfig {
+    fn from(builder: ExponentialBuilder) -> Self {
+        BackoffConfig::Exponential(builder)
+    }
+}
+
+impl From<FibonacciBuilder> for BackoffConfig {
+    fn from(builder: FibonacciBuilder) -> Self {
+        BackoffConfig::Fibonacci(builder)
+    }
+}
+
+impl From<ConstantBuilder> for BackoffConfig {
+    fn from(builder: ConstantBuilder) -> Self {
+        BackoffConfig::Constant(builder)
+    }
+}
+
+impl From<DecorrelatedJitterBuilder> for BackoffConfig {
+    fn from(builder: DecorrelatedJitterBuilder) -> Self {
+        BackoffConfig::DecorrelatedJitter(builder)
+    }
+}
+
+impl BackoffBuilder for BackoffConfig {
+    type Backoff = ConfiguredBackoff;
+
+    fn build(self) -> Self::Backoff {
+        match self {
+            BackoffConfig::Exponential(b) => ConfiguredBackoff::Exponential(b.build()),
+            BackoffConfig::Fibonacci(b) => ConfiguredBackoff::Fibonacci(b.build()),
+            BackoffConfig::Constant(b) => ConfiguredBackoff::Constant(b.build()),
+            BackoffConfig::DecorrelatedJitter(b) => {
+                ConfiguredBackoff::DecorrelatedJitter(b.build())
+            }
+        }
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        match self {
+            BackoffConfig::Exponential(b) => b.max_delay(),
+            BackoffConfig::Fibonacci(b) => b.max_delay(),
+            BackoffConfig::Constant(b) => b.max_delay(),
+            BackoffConfig::DecorrelatedJitter(b) => b.max_delay(),
+        }
+    }
+}
+
+impl BackoffBuilder for &BackoffConfig {
+    type Backoff = ConfiguredBackoff;
+
+    fn build(self) -> Self::Backoff {
+        (*self).build()
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        (*self).max_delay()
+    }
+}
+
+/// ConfiguredBackoff dispatches to the backoff selected by a [`BackoffConfig`].
+///
+/// This backoff strategy is constructed by [`BackoffConfig`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub enum ConfiguredBackoff {
+    /// Backoff built by [`ExponentialBuilder`].
+    Exponential(ExponentialBackoff),
+    /// Backoff built by [`FibonacciBuilder`].
+    Fibonacci(FibonacciBackoff),
+    /// Backoff built by [`ConstantBuilder`].
+    Constant(ConstantBackoff),
+    /// Backoff built by [`DecorrelatedJitterBuilder`].
+    DecorrelatedJitter(DecorrelatedJitterBackoff),
+}
+
+impl Iterator for ConfiguredBackoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            ConfiguredBackoff::Exponential(b) => b.next(),
+            ConfiguredBackoff::Fibonacci(b) => b.next(),
+            ConfiguredBackoff::Constant(b) => b.next(),
+            ConfiguredBackoff::DecorrelatedJitter(b) => b.next(),
+        }
+    }
+}
+
+impl ResettableBackoff for ConfiguredBackoff {
+    fn reset(&mut self) {
+        match self {
+            ConfiguredBackoff::Exponential(b) => b.reset(),
+            ConfiguredBackoff::Fibonacci(b) => b.reset(),
+            ConfiguredBackoff::Constant(b) => b.reset(),
+            ConfiguredBackoff::DecorrelatedJitter(b) => b.reset(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::*;
+
+    #[test]
+    fn test_backoff_config_build() {
+        let mut it = BackoffConfig::from(ConstantBuilder::default().with_max_times(1)).build();
+
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(None, it.next());
+
+        let mut it = BackoffConfig::default().build();
+
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(Some(Duration::from_secs(2)), it.next());
+        assert_eq!(Some(Duration::from_secs(4)), it.next());
+        assert_eq!(None, it.next());
+    }
+}
+
+#[cfg(all(test, feature = "serde"))]
+mod serde_tests {
+    extern crate alloc;
+
+    use alloc::string::ToString;
+    use core::time::Duration;
+
+    use super::*;
+    use crate::Jitter;
+
+    #[test]
+    fn test_deserialize_json() {
+        let config: BackoffConfig = serde_json::from_str(
+            r#"{
+                "kind": "exponential",
+                "jitter": "full",
+                "factor": 3.0,
+                "min_delay": "100ms",
+                "max_delay": null,
+                "max_times": 5,
+                "total_delay": "1m30s"
+            }"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config,
+            BackoffConfig::Exponential(
+                ExponentialBuilder::default()
+                    .with_jitter_strategy(Jitter::Full)
+                    .with_factor(3.0)
+                    .with_min_delay(Duration::from_millis(100))
+                    .without_max_delay()
+                    .with_max_times(5)
+                    .with_total_delay(Some(Duration::from_secs(90)))
+            )
+        );
+    }
+
+    #[test]
+    fn test_deserialize_toml() {
+        #[derive(serde::Deserialize)]
+        struct ServiceConfig {
+            retry: BackoffConfig,
+        }
+
+        let config: ServiceConfig = toml::from_str(
+            r#"
+            [retry]
+            kind = "fibonacci"
+            jitter = { symmetric = 0.25 }
+            min_delay = "1.5s"
+            max_times = 10
+            "#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            config.retry,
+            BackoffConfig::Fibonacci(
+                FibonacciBuilder::default()
+                    .with_jitter_strategy(Jitter::Symmetric(0.25))
+                    .with_min_delay(Duration::from_millis(1500))
+                    .with_max_times(10)
+            )
+        );
+    }
+
+    #[test]
+    fn test_deserialize_defaults() {
+        let config: BackoffConfig = serde_json::from_str(r#"{"kind": "constant"}"#).unwrap();
+        assert_eq!(config, BackoffConfig::Constant(ConstantBuilder::default()));
+
+        let config: BackoffConfig =
+            serde_json::from_str(r#"{"kind": "decorrelated_jitter", "min_delay": "10ms"}"#)
+                .unwrap();
+        assert_eq!(
+            config,
+            BackoffConfig::DecorrelatedJitter(
+                DecorrelatedJitterBuilder::default().with_min_delay(Duration::from_millis(10))
+            )
+        );
+    }
+
+    #[test]
+    fn test_deserialize_error() {
+        let err = serde_json::from_str::<BackoffConfig>(r#"{"kind": "constant", "delay": "1y"}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("invalid duration"), "{err}");
+
+        let err = serde_json::from_str::<BackoffConfig>(r#"{"kind": "constant", "dealy": "1s"}"#)
+            .unwrap_err();
+        assert!(err.to_string().contains("unknown field"), "{err}");
+
+        let err = serde_json::from_str::<BackoffConfig>(r#"{"kind": "linear"}"#).unwrap_err();
+        assert!(err.to_string().contains("unknown variant"), "{err}");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        for config in [
+            BackoffConfig::Exponential(
+                ExponentialBuilder::default()
+                    .with_jitter()
+                    .with_max_delay(Duration::from_millis(2500)),
+            ),
+            BackoffConfig::Fibonacci(FibonacciBuilder::default().without_max_times()),
+            BackoffConfig::Constant(ConstantBuilder::default().with_jitter_seed(42)),
+            BackoffConfig::DecorrelatedJitter(DecorrelatedJitterBuilder::default()),
+        ] {
+            let json = serde_json::to_string(&config).unwrap();
+            assert_eq!(config, serde_json::from_str(&json).unwrap(), "{json}");
+        }
+
+        let json = serde_json::to_string(&BackoffConfig::Constant(
+            ConstantBuilder::default().with_delay(Duration::from_millis(100)),
+        ))
+        .unwrap();
+        assert_eq!(
+            json,
+            r#"{"kind":"constant","delay":"100ms","max_times":3,"jitter":"none","seed":null}"#
+        );
+    }
+}
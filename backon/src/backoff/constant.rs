@@ -1,6 +1,13 @@
+use core::fmt;
 use core::time::Duration;
 
+use super::duration::DisplayDuration;
+use super::policy;
 use crate::backoff::BackoffBuilder;
+use crate::backoff::ConfiguredBackoff;
+use crate::backoff::Jitter;
+use crate::backoff::ResettableBackoff;
+use crate::backoff::Schedule;
 
 /// ConstantBuilder is used to create a [`ConstantBackoff`], providing a steady delay with a fixed number of retries.
 ///
@@ -8,6 +15,7 @@ use crate::backoff::BackoffBuilder;
 ///
 /// - delay: 1s
 /// - max_times: 3
+/// - jitter: [`Jitter::None`]
 ///
 /// # Examples
 ///
@@ -31,11 +39,17 @@ use crate::backoff::BackoffBuilder;
 ///     Ok(())
 /// }
 /// ```
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct ConstantBuilder {
+    #[cfg_attr(feature = "serde", serde(with = "super::duration::serde_duration"))]
     delay: Duration,
     max_times: Option<usize>,
-    jitter: bool,
+    jitter: Jitter,
     seed: Option<u64>,
 }
 
@@ -51,7 +65,7 @@ impl ConstantBuilder {
         Self {
             delay: Duration::from_secs(1),
             max_times: Some(3),
-            jitter: false,
+            jitter: Jitter::None,
             seed: None,
         }
     }
@@ -71,8 +85,18 @@ impl ConstantBuilder {
     /// Enable jitter for the backoff.
     ///
     /// Jitter is a random value added to the delay to prevent a thundering herd problem.
+    ///
+    /// This is the same as `with_jitter_strategy(Jitter::Additive)`.
     pub const fn with_jitter(mut self) -> Self {
-        self.jitter = true;
+        self.jitter = Jitter::Additive;
+        self
+    }
+
+    /// Set the jitter strategy for the backoff.
+    ///
+    /// See [`Jitter`] for the available strategies. [`Jitter::None`] disables jitter.
+    pub const fn with_jitter_strategy(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
         self
     }
 
@@ -91,6 +115,21 @@ impl ConstantBuilder {
         self.max_times = None;
         self
     }
+
+    /// Preview the delays planned by this builder.
+    ///
+    /// See [`Schedule`] for details.
+    pub fn schedule(&self) -> Schedule {
+        let nominal = ConstantBuilder {
+            jitter: Jitter::None,
+            ..*self
+        };
+        Schedule::jittered(
+            ConfiguredBackoff::Constant(nominal.build()),
+            self.jitter,
+            None,
+        )
+    }
 }
 
 impl BackoffBuilder for ConstantBuilder {
@@ -103,18 +142,23 @@ impl BackoffBuilder for ConstantBuilder {
 
             attempts: 0,
             jitter: self.jitter,
-            rng: if let Some(seed) = self.seed {
-                fastrand::Rng::with_seed(seed)
-            } else {
-                #[cfg(feature = "std")]
-                let rng = fastrand::Rng::new();
+            rng: super::new_rng(self.seed),
+        }
+    }
 
-                #[cfg(not(feature = "std"))]
-                let rng = fastrand::Rng::with_seed(super::RANDOM_SEED);
+    /// The delay of the backoff, plus the most its jitter can add to it.
+    fn max_delay(&self) -> Option<Duration> {
+        Some(self.jitter.bounds(self.delay).1)
+    }
+}
 
-                rng
-            },
-        }
+/// Display the builder as a policy that can be parsed back into a [`BackoffConfig`](crate::BackoffConfig).
+impl fmt::Display for ConstantBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "constant(delay={}", DisplayDuration(self.delay))?;
+        policy::fmt_times(f, self.max_times)?;
+        policy::fmt_jitter(f, self.jitter, self.seed)?;
+        write!(f, ")")
     }
 }
 
@@ -124,6 +168,10 @@ impl BackoffBuilder for &ConstantBuilder {
     fn build(self) -> Self::Backoff {
         (*self).build()
     }
+
+    fn max_delay(&self) -> Option<Duration> {
+        (*self).max_delay()
+    }
 }
 
 /// ConstantBackoff offers a consistent delay with a limited number of retries.
@@ -136,7 +184,7 @@ pub struct ConstantBackoff {
     max_times: Option<usize>,
 
     attempts: usize,
-    jitter: bool,
+    jitter: Jitter,
     rng: fastrand::Rng,
 }
 
@@ -144,10 +192,7 @@ impl Iterator for ConstantBackoff {
     type Item = Duration;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let mut delay = || match self.jitter {
-            true => self.delay + self.delay.mul_f32(self.rng.f32()),
-            false => self.delay,
-        };
+        let mut delay = || self.jitter.apply(self.delay, &mut self.rng);
         match self.max_times {
             None => Some(delay()),
             Some(max_times) => {
@@ -162,6 +207,12 @@ impl Iterator for ConstantBackoff {
     }
 }
 
+impl ResettableBackoff for ConstantBackoff {
+    fn reset(&mut self) {
+        self.attempts = 0;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use core::time::Duration;
@@ -215,6 +266,19 @@ mod tests {
         assert!(dur > Duration::from_secs(1));
     }
 
+    #[test]
+    fn test_constant_with_jitter_strategy() {
+        let mut it = ConstantBuilder::default()
+            .with_jitter_strategy(Jitter::Full)
+            .build();
+
+        for _ in 0..3 {
+            let dur = it.next().unwrap();
+            assert!(dur <= Duration::from_secs(1), "current: {dur:?}");
+        }
+        assert_eq!(None, it.next());
+    }
+
     #[test]
     fn test_constant_without_max_times() {
         let mut it = ConstantBuilder::default().without_max_times().build();
@@ -230,6 +294,6 @@ mod tests {
     fn test_constant_const_builder() {
         assert_eq!(TEST_BUILDER.delay, Duration::from_secs(2));
         assert_eq!(TEST_BUILDER.max_times, Some(5));
-        assert!(TEST_BUILDER.jitter);
+        assert_eq!(TEST_BUILDER.jitter, Jitter::Additive);
     }
 }
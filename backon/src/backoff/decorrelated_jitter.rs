@@ -0,0 +1,361 @@
+use core::fmt;
+use core::time::Duration;
+
+use super::duration::DisplayDuration;
+use super::policy;
+use crate::backoff::BackoffBuilder;
+use crate::backoff::Jitter;
+use crate::backoff::ResettableBackoff;
+use crate::backoff::Schedule;
+
+/// DecorrelatedJitterBuilder is used to build a [`DecorrelatedJitterBackoff`] which offers delays
+/// following the "decorrelated jitter" algorithm described in the AWS Architecture Blog post
+/// [Exponential Backoff And Jitter](https://aws.amazon.com/blogs/architecture/exponential-backoff-and-jitter/).
+///
+/// Every delay is computed as `min(max_delay, random(min_delay, previous_delay * 3))`. Unlike
+/// [`ExponentialBuilder::with_jitter`](crate::ExponentialBuilder::with_jitter), the delay does
+/// not follow a deterministic curve, which spreads retries from many clients much better.
+///
+/// # Default
+///
+/// - min_delay: 1s
+/// - max_delay: 60s
+/// - max_times: 3
+///
+/// # Examples
+///
+/// ```no_run
+/// use anyhow::Result;
+/// use backon::DecorrelatedJitterBuilder;
+/// use backon::Retryable;
+///
+/// async fn fetch() -> Result<String> {
+///     Ok(reqwest::get("https://www.rust-lang.org")
+///         .await?
+///         .text()
+///         .await?)
+/// }
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() -> Result<()> {
+///     let content = fetch.retry(DecorrelatedJitterBuilder::default()).await?;
+///     println!("fetch succeeded: {}", content);
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
+pub struct DecorrelatedJitterBuilder {
+    #[cfg_attr(feature = "serde", serde(with = "super::duration::serde_duration"))]
+    min_delay: Duration,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "super::duration::serde_duration::option")
+    )]
+    max_delay: Option<Duration>,
+    max_times: Option<usize>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "super::duration::serde_duration::option")
+    )]
+    total_delay: Option<Duration>,
+    seed: Option<u64>,
+}
+
+impl Default for DecorrelatedJitterBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecorrelatedJitterBuilder {
+    /// Create a new `DecorrelatedJitterBuilder` with default values.
+    pub const fn new() -> Self {
+        Self {
+            min_delay: Duration::from_secs(1),
+            max_delay: Some(Duration::from_secs(60)),
+            max_times: Some(3),
+            total_delay: None,
+            seed: None,
+        }
+    }
+
+    /// Set the seed value for the random number generator. If no seed is given, a random seed is used in std and default seed is used in no_std.
+    pub fn with_jitter_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    /// Set the minimum delay for the backoff.
+    ///
+    /// This is the `base` of the algorithm: no delay will be shorter than it.
+    pub const fn with_min_delay(mut self, min_delay: Duration) -> Self {
+        self.min_delay = min_delay;
+        self
+    }
+
+    /// Set the maximum delay for the backoff.
+    ///
+    /// This is the `cap` of the algorithm: no delay will be longer than it.
+    pub const fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = Some(max_delay);
+        self
+    }
+
+    /// Set no maximum delay for the backoff.
+    ///
+    /// The delay will keep increasing.
+    ///
+    /// _The delay will saturate at `Duration::MAX` which is an **unrealistic** delay._
+    pub const fn without_max_delay(mut self) -> Self {
+        self.max_delay = None;
+        self
+    }
+
+    /// Set the maximum number of attempts for the current backoff.
+    ///
+    /// The backoff will stop if the maximum number of attempts is reached.
+    pub const fn with_max_times(mut self, max_times: usize) -> Self {
+        self.max_times = Some(max_times);
+        self
+    }
+
+    /// Set no maximum number of attempts for the current backoff.
+    ///
+    /// The backoff will not stop by itself.
+    ///
+    /// _The backoff could stop reaching `usize::MAX` attempts but this is **unrealistic**._
+    pub const fn without_max_times(mut self) -> Self {
+        self.max_times = None;
+        self
+    }
+
+    /// Set the total delay for the backoff.
+    ///
+    /// The backoff will stop yielding sleep durations once the cumulative sleep time
+    /// plus the next sleep duration would exceed `total_delay`.
+    pub const fn with_total_delay(mut self, total_delay: Option<Duration>) -> Self {
+        self.total_delay = total_delay;
+        self
+    }
+
+    /// Preview the delays planned by this builder.
+    ///
+    /// See [`Schedule`] for details.
+    pub fn schedule(&self) -> Schedule {
+        Schedule::decorrelated_jitter(
+            self.min_delay,
+            self.max_delay,
+            self.max_times,
+            self.total_delay,
+        )
+    }
+}
+
+impl BackoffBuilder for DecorrelatedJitterBuilder {
+    type Backoff = DecorrelatedJitterBackoff;
+
+    fn build(self) -> Self::Backoff {
+        DecorrelatedJitterBackoff {
+            rng: super::new_rng(self.seed),
+            min_delay: self.min_delay,
+            max_delay: self.max_delay,
+            max_times: self.max_times,
+            total_delay: self.total_delay,
+
+            previous_delay: None,
+            cumulative_delay: Duration::ZERO,
+            attempts: 0,
+        }
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        self.max_delay
+    }
+}
+
+/// Display the builder as a policy that can be parsed back into a [`BackoffConfig`](crate::BackoffConfig).
+impl fmt::Display for DecorrelatedJitterBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "decorrelated_jitter(min={}",
+            DisplayDuration(self.min_delay)
+        )?;
+        policy::fmt_delay(f, "max", self.max_delay)?;
+        policy::fmt_times(f, self.max_times)?;
+        if self.total_delay.is_some() {
+            policy::fmt_delay(f, "total", self.total_delay)?;
+        }
+        policy::fmt_jitter(f, Jitter::None, self.seed)?;
+        write!(f, ")")
+    }
+}
+
+impl BackoffBuilder for &DecorrelatedJitterBuilder {
+    type Backoff = DecorrelatedJitterBackoff;
+
+    fn build(self) -> Self::Backoff {
+        (*self).build()
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        (*self).max_delay()
+    }
+}
+
+/// DecorrelatedJitterBackoff offers delays following the decorrelated jitter algorithm.
+///
+/// This backoff strategy is constructed by [`DecorrelatedJitterBuilder`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct DecorrelatedJitterBackoff {
+    rng: fastrand::Rng,
+    min_delay: Duration,
+    max_delay: Option<Duration>,
+    max_times: Option<usize>,
+    total_delay: Option<Duration>,
+
+    previous_delay: Option<Duration>,
+    cumulative_delay: Duration,
+    attempts: usize,
+}
+
+impl Iterator for DecorrelatedJitterBackoff {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.attempts >= self.max_times.unwrap_or(usize::MAX) {
+            return None;
+        }
+
+        // The first delay is drawn from `(min_delay, min_delay * 3)` as if the previous
+        // delay was `min_delay`.
+        let previous = self.previous_delay.unwrap_or(self.min_delay);
+        let upper = super::exponential::saturating_mul(previous, 3.0);
+        let mut next = self
+            .min_delay
+            .saturating_add(upper.saturating_sub(self.min_delay).mul_f32(self.rng.f32()));
+        if let Some(max_delay) = self.max_delay {
+            next = next.min(max_delay);
+        }
+
+        // Check if adding the next delay would exceed the total delay limit.
+        if let Some(total_delay) = self.total_delay {
+            if self.cumulative_delay.saturating_add(next) > total_delay {
+                return None;
+            }
+            self.cumulative_delay = self.cumulative_delay.saturating_add(next);
+        }
+
+        self.attempts += 1;
+        self.previous_delay = Some(next);
+
+        Some(next)
+    }
+}
+
+impl ResettableBackoff for DecorrelatedJitterBackoff {
+    fn reset(&mut self) {
+        self.previous_delay = None;
+        self.cumulative_delay = Duration::ZERO;
+        self.attempts = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::*;
+
+    const TEST_BUILDER: DecorrelatedJitterBuilder = DecorrelatedJitterBuilder::new()
+        .with_min_delay(Duration::from_secs(2))
+        .with_max_delay(Duration::from_secs(30))
+        .with_max_times(5);
+
+    #[test]
+    fn test_decorrelated_jitter_default() {
+        let mut it = DecorrelatedJitterBuilder::default().build();
+
+        let mut previous = Duration::from_secs(1);
+        for _ in 0..3 {
+            let v = it.next().expect("value must valid");
+            assert!(v >= Duration::from_secs(1), "current: {v:?}");
+            assert!(v <= previous * 3, "current: {v:?}, previous: {previous:?}");
+            previous = v;
+        }
+        assert_eq!(None, it.next());
+    }
+
+    #[test]
+    fn test_decorrelated_jitter_max_delay() {
+        let mut it = DecorrelatedJitterBuilder::default()
+            .with_min_delay(Duration::from_secs(10))
+            .with_max_delay(Duration::from_secs(10))
+            .with_max_times(5)
+            .build();
+
+        for _ in 0..5 {
+            assert_eq!(Some(Duration::from_secs(10)), it.next());
+        }
+        assert_eq!(None, it.next());
+    }
+
+    #[test]
+    fn test_decorrelated_jitter_total_delay() {
+        let mut it = DecorrelatedJitterBuilder::default()
+            .with_min_delay(Duration::from_secs(1))
+            .with_max_delay(Duration::from_secs(1))
+            .with_total_delay(Some(Duration::from_secs(3)))
+            .with_max_times(5)
+            .build();
+
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(None, it.next());
+    }
+
+    #[test]
+    fn test_decorrelated_jitter_seed() {
+        let builder = DecorrelatedJitterBuilder::default()
+            .with_jitter_seed(0x2fdb0020ffc7722b)
+            .with_max_times(10);
+
+        assert_eq!(builder.build().count(), 10);
+        assert!(builder.build().eq(builder.build()));
+    }
+
+    #[test]
+    fn test_decorrelated_jitter_without_max_times() {
+        let mut it = DecorrelatedJitterBuilder::default()
+            .with_min_delay(Duration::from_millis(1))
+            .with_max_delay(Duration::from_secs(1))
+            .without_max_times()
+            .build();
+
+        for _ in 0..10_000 {
+            let v = it.next().expect("value must valid");
+            assert!(v >= Duration::from_millis(1), "current: {v:?}");
+            assert!(v <= Duration::from_secs(1), "current: {v:?}");
+        }
+    }
+
+    // allow assertions on constants because they are not optimized out by unit tests
+    #[allow(clippy::assertions_on_constants)]
+    #[test]
+    fn test_decorrelated_jitter_const_builder() {
+        assert_eq!(TEST_BUILDER.min_delay, Duration::from_secs(2));
+        assert_eq!(TEST_BUILDER.max_delay, Some(Duration::from_secs(30)));
+        assert_eq!(TEST_BUILDER.max_times, Some(5));
+    }
+}
@@ -0,0 +1,312 @@
+//! Human friendly representation of durations like `100ms`, `1.5s` or `1m30s`.
+
+use core::fmt;
+use core::time::Duration;
+
+const NANOS_PER_MICRO: u128 = 1_000;
+const NANOS_PER_MILLI: u128 = 1_000_000;
+const NANOS_PER_SEC: u128 = 1_000_000_000;
+const NANOS_PER_MINUTE: u128 = 60 * NANOS_PER_SEC;
+const NANOS_PER_HOUR: u128 = 60 * NANOS_PER_MINUTE;
+const NANOS_PER_DAY: u128 = 24 * NANOS_PER_HOUR;
+
+/// The error returned when parsing a duration failed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ParseDurationError {
+    /// The input is empty.
+    Empty,
+    /// A number was expected but not found.
+    InvalidNumber,
+    /// A number is not followed by a unit.
+    MissingUnit,
+    /// The unit is not one of `ns`, `us`, `µs`, `ms`, `s`, `m`, `h` or `d`.
+    UnknownUnit,
+    /// The duration doesn't fit in a [`Duration`].
+    Overflow,
+}
+
+impl fmt::Display for ParseDurationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseDurationError::Empty => write!(f, "duration is empty"),
+            ParseDurationError::InvalidNumber => write!(f, "expected a number"),
+            ParseDurationError::MissingUnit => {
+                write!(f, "missing unit, expected one of ns, us, ms, s, m, h or d")
+            }
+            ParseDurationError::UnknownUnit => {
+                write!(f, "unknown unit, expected one of ns, us, ms, s, m, h or d")
+            }
+            ParseDurationError::Overflow => write!(f, "duration is too large"),
+        }
+    }
+}
+
+/// Parse a duration like `100ms`, `1.5s` or `1m 30s`.
+///
+/// `0` is accepted without unit.
+pub(crate) fn parse_duration(input: &str) -> Result<Duration, ParseDurationError> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(ParseDurationError::Empty);
+    }
+    if input == "0" {
+        return Ok(Duration::ZERO);
+    }
+
+    let mut total: u128 = 0;
+    let mut rest = input;
+    while !rest.is_empty() {
+        let (number, tail) = split_while(rest, |c| c.is_ascii_digit() || c == '.');
+        let (unit, tail) = split_while(tail, |c| c.is_alphabetic());
+        if number.is_empty() {
+            return Err(ParseDurationError::InvalidNumber);
+        }
+        if unit.is_empty() {
+            return Err(ParseDurationError::MissingUnit);
+        }
+
+        let unit = match unit {
+            "ns" => 1,
+            "us" | "µs" => NANOS_PER_MICRO,
+            "ms" => NANOS_PER_MILLI,
+            "s" => NANOS_PER_SEC,
+            "m" => NANOS_PER_MINUTE,
+            "h" => NANOS_PER_HOUR,
+            "d" => NANOS_PER_DAY,
+            _ => return Err(ParseDurationError::UnknownUnit),
+        };
+
+        let (int, frac) = number.split_once('.').unwrap_or((number, ""));
+        if int.is_empty() && frac.is_empty() || frac.contains('.') {
+            return Err(ParseDurationError::InvalidNumber);
+        }
+        let mut nanos = parse_u128(int)?
+            .checked_mul(unit)
+            .ok_or(ParseDurationError::Overflow)?;
+        // Digits beyond nanosecond precision are ignored.
+        let mut scale = unit;
+        for digit in frac.bytes() {
+            scale /= 10;
+            nanos = nanos
+                .checked_add(u128::from(digit - b'0') * scale)
+                .ok_or(ParseDurationError::Overflow)?;
+        }
+
+        total = total
+            .checked_add(nanos)
+            .ok_or(ParseDurationError::Overflow)?;
+        rest = tail.trim_start();
+    }
+
+    let secs = u64::try_from(total / NANOS_PER_SEC).map_err(|_| ParseDurationError::Overflow)?;
+    Ok(Duration::new(secs, (total % NANOS_PER_SEC) as u32))
+}
+
+fn split_while(input: &str, f: impl Fn(char) -> bool) -> (&str, &str) {
+    let end = input
+        .char_indices()
+        .find(|(_, c)| !f(*c))
+        .map_or(input.len(), |(idx, _)| idx);
+    input.split_at(end)
+}
+
+fn parse_u128(digits: &str) -> Result<u128, ParseDurationError> {
+    if digits.is_empty() {
+        return Ok(0);
+    }
+    digits.parse().map_err(|_| ParseDurationError::Overflow)
+}
+
+/// Display a duration in the largest unit that represents it exactly, like `100ms` or `5m`.
+///
+/// The output can be parsed back by [`parse_duration`].
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct DisplayDuration(pub(crate) Duration);
+
+impl fmt::Display for DisplayDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let nanos = self.0.as_nanos();
+        if nanos == 0 {
+            return write!(f, "0s");
+        }
+
+        for (unit, name) in [
+            (NANOS_PER_HOUR, "h"),
+            (NANOS_PER_MINUTE, "m"),
+            (NANOS_PER_SEC, "s"),
+            (NANOS_PER_MILLI, "ms"),
+            (NANOS_PER_MICRO, "us"),
+        ] {
+            if nanos % unit == 0 {
+                return write!(f, "{}{}", nanos / unit, name);
+            }
+        }
+        write!(f, "{nanos}ns")
+    }
+}
+
+/// `serde(with)` helpers serializing durations as human friendly strings.
+#[cfg(feature = "serde")]
+pub(crate) mod serde_duration {
+    use core::fmt;
+    use core::time::Duration;
+
+    use serde::Deserializer;
+    use serde::Serializer;
+    use serde::de;
+
+    use super::DisplayDuration;
+    use super::parse_duration;
+
+    pub(crate) fn serialize<S: Serializer>(
+        dur: &Duration,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&DisplayDuration(*dur))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Duration, D::Error> {
+        deserializer.deserialize_str(DurationVisitor)
+    }
+
+    struct DurationVisitor;
+
+    impl de::Visitor<'_> for DurationVisitor {
+        type Value = Duration;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "a duration like \"100ms\" or \"1m30s\"")
+        }
+
+        fn visit_str<E: de::Error>(self, v: &str) -> Result<Self::Value, E> {
+            parse_duration(v)
+                .map_err(|err| E::custom(format_args!("invalid duration {v:?}: {err}")))
+        }
+    }
+
+    /// Same as the parent module but for `Option<Duration>`.
+    pub(crate) mod option {
+        use core::fmt;
+        use core::time::Duration;
+
+        use serde::Deserializer;
+        use serde::Serializer;
+        use serde::de;
+
+        pub(crate) fn serialize<S: Serializer>(
+            dur: &Option<Duration>,
+            serializer: S,
+        ) -> Result<S::Ok, S::Error> {
+            match dur {
+                Some(dur) => super::serialize(dur, serializer),
+                None => serializer.serialize_none(),
+            }
+        }
+
+        pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+            deserializer: D,
+        ) -> Result<Option<Duration>, D::Error> {
+            deserializer.deserialize_option(OptionVisitor)
+        }
+
+        struct OptionVisitor;
+
+        impl<'de> de::Visitor<'de> for OptionVisitor {
+            type Value = Option<Duration>;
+
+            fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                write!(f, "an optional duration like \"100ms\" or \"1m30s\"")
+            }
+
+            fn visit_none<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_unit<E: de::Error>(self) -> Result<Self::Value, E> {
+                Ok(None)
+            }
+
+            fn visit_some<D: Deserializer<'de>>(
+                self,
+                deserializer: D,
+            ) -> Result<Self::Value, D::Error> {
+                super::deserialize(deserializer).map(Some)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use alloc::string::ToString;
+    use core::time::Duration;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        let cases = [
+            ("0", Duration::ZERO),
+            ("0s", Duration::ZERO),
+            ("10ns", Duration::from_nanos(10)),
+            ("10us", Duration::from_micros(10)),
+            ("10µs", Duration::from_micros(10)),
+            ("100ms", Duration::from_millis(100)),
+            ("1.5s", Duration::from_millis(1500)),
+            (".5s", Duration::from_millis(500)),
+            ("2m", Duration::from_secs(120)),
+            ("1m30s", Duration::from_secs(90)),
+            ("1h 1m 1s", Duration::from_secs(3661)),
+            ("1d", Duration::from_secs(86400)),
+            (" 5s ", Duration::from_secs(5)),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_duration(input), Ok(expected), "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_parse_duration_error() {
+        let cases = [
+            ("", ParseDurationError::Empty),
+            ("s", ParseDurationError::InvalidNumber),
+            ("1", ParseDurationError::MissingUnit),
+            ("1.2.3s", ParseDurationError::InvalidNumber),
+            ("10 parsecs", ParseDurationError::MissingUnit),
+            ("10parsecs", ParseDurationError::UnknownUnit),
+            ("99999999999999999999999d", ParseDurationError::Overflow),
+            (
+                "340282366920938463463374607431768211.9us",
+                ParseDurationError::Overflow,
+            ),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_duration(input), Err(expected), "input: {input:?}");
+        }
+    }
+
+    #[test]
+    fn test_display_duration_round_trip() {
+        for (dur, expected) in [
+            (Duration::ZERO, "0s"),
+            (Duration::from_nanos(1), "1ns"),
+            (Duration::from_micros(7), "7us"),
+            (Duration::from_millis(1500), "1500ms"),
+            (Duration::from_secs(90), "90s"),
+            (Duration::from_secs(120), "2m"),
+            (Duration::from_secs(7200), "2h"),
+            (Duration::MAX, "18446744073709551615999999999ns"),
+        ] {
+            let s = DisplayDuration(dur).to_string();
+            assert_eq!(s, expected);
+            assert_eq!(parse_duration(&s), Ok(dur), "formatted: {s:?}");
+        }
+    }
+}
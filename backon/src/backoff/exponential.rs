@@ -1,12 +1,19 @@
+use core::fmt;
 use core::time::Duration;
 
+use super::duration::DisplayDuration;
+use super::policy;
 use crate::backoff::BackoffBuilder;
+use crate::backoff::ConfiguredBackoff;
+use crate::backoff::Jitter;
+use crate::backoff::ResettableBackoff;
+use crate::backoff::Schedule;
 
 /// ExponentialBuilder is used to construct an [`ExponentialBackoff`] that offers delays with exponential retries.
 ///
 /// # Default
 ///
-/// - jitter: false
+/// - jitter: [`Jitter::None`]
 /// - factor: 2
 /// - min_delay: 1s
 /// - max_delay: 60s
@@ -34,13 +41,27 @@ use crate::backoff::BackoffBuilder;
 ///     Ok(())
 /// }
 /// ```
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct ExponentialBuilder {
-    jitter: bool,
+    jitter: Jitter,
     factor: f32,
+    #[cfg_attr(feature = "serde", serde(with = "super::duration::serde_duration"))]
     min_delay: Duration,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "super::duration::serde_duration::option")
+    )]
     max_delay: Option<Duration>,
     max_times: Option<usize>,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "super::duration::serde_duration::option")
+    )]
     total_delay: Option<Duration>,
     seed: Option<u64>,
 }
@@ -55,7 +76,7 @@ impl ExponentialBuilder {
     /// Create a new `ExponentialBuilder` with default values.
     pub const fn new() -> Self {
         Self {
-            jitter: false,
+            jitter: Jitter::None,
             factor: 2.0,
             min_delay: Duration::from_secs(1),
             max_delay: Some(Duration::from_secs(60)),
@@ -69,8 +90,18 @@ impl ExponentialBuilder {
     ///
     /// When jitter is enabled, [`ExponentialBackoff`] will add a random jitter within `(0, current_delay)`
     /// to the current delay.
+    ///
+    /// This is the same as `with_jitter_strategy(Jitter::Additive)`.
     pub const fn with_jitter(mut self) -> Self {
-        self.jitter = true;
+        self.jitter = Jitter::Additive;
+        self
+    }
+
+    /// Set the jitter strategy for the backoff.
+    ///
+    /// See [`Jitter`] for the available strategies. [`Jitter::None`] disables jitter.
+    pub const fn with_jitter_strategy(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
         self
     }
 
@@ -139,6 +170,22 @@ impl ExponentialBuilder {
         self.total_delay = total_delay;
         self
     }
+
+    /// Preview the delays planned by this builder.
+    ///
+    /// See [`Schedule`] for details.
+    pub fn schedule(&self) -> Schedule {
+        let nominal = ExponentialBuilder {
+            jitter: Jitter::None,
+            total_delay: None,
+            ..*self
+        };
+        Schedule::jittered(
+            ConfiguredBackoff::Exponential(nominal.build()),
+            self.jitter,
+            self.total_delay,
+        )
+    }
 }
 
 impl BackoffBuilder for ExponentialBuilder {
@@ -147,17 +194,7 @@ impl BackoffBuilder for ExponentialBuilder {
     fn build(self) -> Self::Backoff {
         ExponentialBackoff {
             jitter: self.jitter,
-            rng: if let Some(seed) = self.seed {
-                fastrand::Rng::with_seed(seed)
-            } else {
-                #[cfg(feature = "std")]
-                let rng = fastrand::Rng::new();
-
-                #[cfg(not(feature = "std"))]
-                let rng = fastrand::Rng::with_seed(super::RANDOM_SEED);
-
-                rng
-            },
+            rng: super::new_rng(self.seed),
             factor: self.factor,
             min_delay: self.min_delay,
             max_delay: self.max_delay,
@@ -169,6 +206,25 @@ impl BackoffBuilder for ExponentialBuilder {
             total_delay: self.total_delay,
         }
     }
+
+    fn max_delay(&self) -> Option<Duration> {
+        self.max_delay
+    }
+}
+
+/// Display the builder as a policy that can be parsed back into a [`BackoffConfig`](crate::BackoffConfig).
+impl fmt::Display for ExponentialBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "exponential(min={}", DisplayDuration(self.min_delay))?;
+        policy::fmt_delay(f, "max", self.max_delay)?;
+        write!(f, ", factor={}", self.factor)?;
+        policy::fmt_times(f, self.max_times)?;
+        if self.total_delay.is_some() {
+            policy::fmt_delay(f, "total", self.total_delay)?;
+        }
+        policy::fmt_jitter(f, self.jitter, self.seed)?;
+        write!(f, ")")
+    }
 }
 
 impl BackoffBuilder for &ExponentialBuilder {
@@ -177,6 +233,10 @@ impl BackoffBuilder for &ExponentialBuilder {
     fn build(self) -> Self::Backoff {
         (*self).build()
     }
+
+    fn max_delay(&self) -> Option<Duration> {
+        (*self).max_delay()
+    }
 }
 
 /// ExponentialBackoff provides a delay with exponential retries.
@@ -185,7 +245,7 @@ impl BackoffBuilder for &ExponentialBuilder {
 #[doc(hidden)]
 #[derive(Debug)]
 pub struct ExponentialBackoff {
-    jitter: bool,
+    jitter: Jitter,
     rng: fastrand::Rng,
     factor: f32,
     min_delay: Duration,
@@ -229,10 +289,7 @@ impl Iterator for ExponentialBackoff {
         };
 
         let current_delay = tmp_cur;
-        // If jitter is enabled, add random jitter based on min delay.
-        if self.jitter {
-            tmp_cur = tmp_cur.saturating_add(tmp_cur.mul_f32(self.rng.f32()));
-        }
+        tmp_cur = self.jitter.apply(tmp_cur, &mut self.rng);
 
         // Check if adding the current delay would exceed the total delay limit.
         let total_delay_check = self
@@ -258,6 +315,14 @@ pub(crate) fn saturating_mul(d: Duration, rhs: f32) -> Duration {
     Duration::try_from_secs_f32(rhs * d.as_secs_f32()).unwrap_or(Duration::MAX)
 }
 
+impl ResettableBackoff for ExponentialBackoff {
+    fn reset(&mut self) {
+        self.current_delay = None;
+        self.cumulative_delay = Duration::ZERO;
+        self.attempts = 0;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use core::time::Duration;
@@ -267,6 +332,7 @@ mod tests {
 
     use crate::BackoffBuilder;
     use crate::ExponentialBuilder;
+    use crate::Jitter;
 
     const TEST_BUILDER: ExponentialBuilder = ExponentialBuilder::new()
         .with_jitter()
@@ -314,6 +380,27 @@ mod tests {
         assert_eq!(None, exp.next());
     }
 
+    #[test]
+    fn test_exponential_jitter_strategy() {
+        let mut exp = ExponentialBuilder::default()
+            .with_jitter_strategy(Jitter::Equal)
+            .build();
+
+        let v = exp.next().expect("value must valid");
+        assert!(v >= Duration::from_millis(500), "current: {v:?}");
+        assert!(v <= Duration::from_secs(1), "current: {v:?}");
+
+        let v = exp.next().expect("value must valid");
+        assert!(v >= Duration::from_secs(1), "current: {v:?}");
+        assert!(v <= Duration::from_secs(2), "current: {v:?}");
+
+        let v = exp.next().expect("value must valid");
+        assert!(v >= Duration::from_secs(2), "current: {v:?}");
+        assert!(v <= Duration::from_secs(4), "current: {v:?}");
+
+        assert_eq!(None, exp.next());
+    }
+
     #[test]
     fn test_exponential_min_delay() {
         let mut exp = ExponentialBuilder::default()
@@ -387,7 +474,7 @@ mod tests {
     #[test]
     fn test_exponential_max_delay_without_default_1() {
         let mut exp = ExponentialBuilder {
-            jitter: false,
+            jitter: Jitter::None,
             seed: Some(0x2fdb0020ffc7722b),
             factor: 10_000_000_000_f32,
             min_delay: Duration::from_secs(1),
@@ -406,7 +493,7 @@ mod tests {
     #[test]
     fn test_exponential_max_delay_without_default_2() {
         let mut exp = ExponentialBuilder {
-            jitter: true,
+            jitter: Jitter::Additive,
             seed: Some(0x2fdb0020ffc7722b),
             factor: 10_000_000_000_f32,
             min_delay: Duration::from_secs(10_000_000_000),
@@ -425,7 +512,7 @@ mod tests {
     #[test]
     fn test_exponential_max_delay_without_default_3() {
         let mut exp = ExponentialBuilder {
-            jitter: false,
+            jitter: Jitter::None,
             seed: Some(0x2fdb0020ffc7722b),
             factor: 10_000_000_000_f32,
             min_delay: Duration::from_secs(10_000_000_000),
@@ -452,7 +539,7 @@ mod tests {
     #[allow(clippy::assertions_on_constants)]
     #[test]
     fn test_exponential_const_builder() {
-        assert!(TEST_BUILDER.jitter);
+        assert_eq!(TEST_BUILDER.jitter, Jitter::Additive);
         assert_eq!(TEST_BUILDER.factor, 1.5);
         assert_eq!(TEST_BUILDER.min_delay, Duration::from_secs(2));
         assert_eq!(TEST_BUILDER.max_delay, Some(Duration::from_secs(30)));
@@ -0,0 +1,585 @@
+use core::time::Duration;
+
+use crate::backoff::Backoff;
+use crate::backoff::BackoffBuilder;
+use crate::backoff::Jitter;
+use crate::backoff::ResettableBackoff;
+
+/// BackoffExt provides combinators to compose backoff strategies.
+///
+/// It is implemented for every [`BackoffBuilder`], which includes every [`Backoff`]. The
+/// returned adapters implement [`BackoffBuilder`] too, so they can be passed to `retry`
+/// directly or combined further.
+///
+/// The adapters are named so that they don't clash with the methods of [`Iterator`] or
+/// with the inherent methods of the built-in builders.
+///
+/// # Examples
+///
+/// Retry 3 times quickly, then keep retrying with a slow exponential tail, and never sleep
+/// longer than 10s:
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use backon::BackoffBuilder;
+/// use backon::BackoffExt;
+/// use backon::ConstantBuilder;
+/// use backon::ExponentialBuilder;
+///
+/// let backoff = ConstantBuilder::default()
+///     .with_delay(Duration::from_millis(100))
+///     .with_max_times(3)
+///     .then(ExponentialBuilder::default().with_min_delay(Duration::from_secs(5)))
+///     .cap(Duration::from_secs(10))
+///     .build();
+///
+/// let delays: Vec<_> = backoff.collect();
+/// assert_eq!(
+///     delays,
+///     [
+///         Duration::from_millis(100),
+///         Duration::from_millis(100),
+///         Duration::from_millis(100),
+///         Duration::from_secs(5),
+///         Duration::from_secs(10),
+///         Duration::from_secs(10),
+///     ]
+/// );
+/// ```
+pub trait BackoffExt: BackoffBuilder + Sized {
+    /// Limit every delay to at most `max`.
+    fn cap(self, max: Duration) -> CapBuilder<Self> {
+        CapBuilder { inner: self, max }
+    }
+
+    /// Stop the backoff after `max_times` attempts.
+    ///
+    /// The backoff may still stop earlier if the inner backoff is exhausted.
+    fn max_times(self, max_times: usize) -> MaxTimesBuilder<Self> {
+        MaxTimesBuilder {
+            inner: self,
+            max_times,
+        }
+    }
+
+    /// Stop the backoff once the cumulative sleep time plus the next sleep duration would
+    /// exceed `total_delay`.
+    fn max_total_delay(self, total_delay: Duration) -> TotalDelayBuilder<Self> {
+        TotalDelayBuilder {
+            inner: self,
+            total_delay,
+        }
+    }
+
+    /// Continue with `next` once this backoff is exhausted.
+    fn then<N: BackoffBuilder>(self, next: N) -> ThenBuilder<Self, N> {
+        ThenBuilder { first: self, next }
+    }
+
+    /// Transform every delay with the given function.
+    ///
+    /// The [`max_delay`](BackoffBuilder::max_delay) of the backoff is transformed as well, so
+    /// `f` is expected to keep the order of the delays.
+    fn map_delay<F>(self, f: F) -> MapDelayBuilder<Self, F>
+    where
+        F: Fn(Duration) -> Duration + Send + Sync + Unpin,
+    {
+        MapDelayBuilder { inner: self, f }
+    }
+
+    /// Apply the given [`Jitter`] strategy to every delay.
+    fn jitter(self, jitter: Jitter) -> JitteredBuilder<Self> {
+        JitteredBuilder {
+            inner: self,
+            jitter,
+            seed: None,
+        }
+    }
+}
+
+impl<B: BackoffBuilder> BackoffExt for B {}
+
+/// Builder returned by [`BackoffExt::cap`].
+#[derive(Debug, Clone, Copy)]
+pub struct CapBuilder<B> {
+    inner: B,
+    max: Duration,
+}
+
+impl<B: BackoffBuilder> BackoffBuilder for CapBuilder<B> {
+    type Backoff = CapBackoff<B::Backoff>;
+
+    fn build(self) -> Self::Backoff {
+        CapBackoff {
+            inner: self.inner.build(),
+            max: self.max,
+        }
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        match self.inner.max_delay() {
+            Some(max_delay) => Some(max_delay.min(self.max)),
+            None => Some(self.max),
+        }
+    }
+}
+
+/// Backoff built by [`CapBuilder`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct CapBackoff<B> {
+    inner: B,
+    max: Duration,
+}
+
+impl<B: Backoff> Iterator for CapBackoff<B> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(|dur| dur.min(self.max))
+    }
+}
+
+impl<B: ResettableBackoff> ResettableBackoff for CapBackoff<B> {
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+/// Builder returned by [`BackoffExt::max_times`].
+#[derive(Debug, Clone, Copy)]
+pub struct MaxTimesBuilder<B> {
+    inner: B,
+    max_times: usize,
+}
+
+impl<B: BackoffBuilder> BackoffBuilder for MaxTimesBuilder<B> {
+    type Backoff = MaxTimesBackoff<B::Backoff>;
+
+    fn build(self) -> Self::Backoff {
+        MaxTimesBackoff {
+            inner: self.inner.build(),
+            max_times: self.max_times,
+            attempts: 0,
+        }
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        self.inner.max_delay()
+    }
+}
+
+/// Backoff built by [`MaxTimesBuilder`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct MaxTimesBackoff<B> {
+    inner: B,
+    max_times: usize,
+    attempts: usize,
+}
+
+impl<B: Backoff> Iterator for MaxTimesBackoff<B> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.attempts >= self.max_times {
+            return None;
+        }
+        self.attempts += 1;
+        self.inner.next()
+    }
+}
+
+impl<B: ResettableBackoff> ResettableBackoff for MaxTimesBackoff<B> {
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.attempts = 0;
+    }
+}
+
+/// Builder returned by [`BackoffExt::max_total_delay`].
+#[derive(Debug, Clone, Copy)]
+pub struct TotalDelayBuilder<B> {
+    inner: B,
+    total_delay: Duration,
+}
+
+impl<B: BackoffBuilder> BackoffBuilder for TotalDelayBuilder<B> {
+    type Backoff = TotalDelayBackoff<B::Backoff>;
+
+    fn build(self) -> Self::Backoff {
+        TotalDelayBackoff {
+            inner: self.inner.build(),
+            total_delay: self.total_delay,
+            cumulative_delay: Duration::ZERO,
+            exhausted: false,
+        }
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        self.inner.max_delay()
+    }
+}
+
+/// Backoff built by [`TotalDelayBuilder`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct TotalDelayBackoff<B> {
+    inner: B,
+    total_delay: Duration,
+    cumulative_delay: Duration,
+    exhausted: bool,
+}
+
+impl<B: Backoff> Iterator for TotalDelayBackoff<B> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.exhausted {
+            return None;
+        }
+
+        match self.inner.next() {
+            Some(dur) if self.cumulative_delay.saturating_add(dur) <= self.total_delay => {
+                self.cumulative_delay = self.cumulative_delay.saturating_add(dur);
+                Some(dur)
+            }
+            _ => {
+                self.exhausted = true;
+                None
+            }
+        }
+    }
+}
+
+impl<B: ResettableBackoff> ResettableBackoff for TotalDelayBackoff<B> {
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.cumulative_delay = Duration::ZERO;
+        self.exhausted = false;
+    }
+}
+
+/// Builder returned by [`BackoffExt::then`].
+#[derive(Debug, Clone, Copy)]
+pub struct ThenBuilder<A, B> {
+    first: A,
+    next: B,
+}
+
+impl<A: BackoffBuilder, B: BackoffBuilder> BackoffBuilder for ThenBuilder<A, B> {
+    type Backoff = ThenBackoff<A::Backoff, B::Backoff>;
+
+    fn build(self) -> Self::Backoff {
+        ThenBackoff {
+            first: self.first.build(),
+            next: self.next.build(),
+            first_exhausted: false,
+        }
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        Some(self.first.max_delay()?.max(self.next.max_delay()?))
+    }
+}
+
+/// Backoff built by [`ThenBuilder`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct ThenBackoff<A, B> {
+    first: A,
+    next: B,
+    first_exhausted: bool,
+}
+
+impl<A: Backoff, B: Backoff> Iterator for ThenBackoff<A, B> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if !self.first_exhausted {
+            match self.first.next() {
+                Some(dur) => return Some(dur),
+                None => self.first_exhausted = true,
+            }
+        }
+        self.next.next()
+    }
+}
+
+impl<A: ResettableBackoff, B: ResettableBackoff> ResettableBackoff for ThenBackoff<A, B> {
+    fn reset(&mut self) {
+        self.first.reset();
+        self.next.reset();
+        self.first_exhausted = false;
+    }
+}
+
+/// Builder returned by [`BackoffExt::map_delay`].
+#[derive(Clone, Copy)]
+pub struct MapDelayBuilder<B, F> {
+    inner: B,
+    f: F,
+}
+
+impl<B, F> BackoffBuilder for MapDelayBuilder<B, F>
+where
+    B: BackoffBuilder,
+    F: Fn(Duration) -> Duration + Send + Sync + Unpin,
+{
+    type Backoff = MapDelayBackoff<B::Backoff, F>;
+
+    fn build(self) -> Self::Backoff {
+        MapDelayBackoff {
+            inner: self.inner.build(),
+            f: self.f,
+        }
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        self.inner.max_delay().map(&self.f)
+    }
+}
+
+/// Backoff built by [`MapDelayBuilder`].
+#[doc(hidden)]
+pub struct MapDelayBackoff<B, F> {
+    inner: B,
+    f: F,
+}
+
+impl<B, F> Iterator for MapDelayBackoff<B, F>
+where
+    B: Backoff,
+    F: Fn(Duration) -> Duration + Send + Sync + Unpin,
+{
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner.next().map(&self.f)
+    }
+}
+
+impl<B, F> ResettableBackoff for MapDelayBackoff<B, F>
+where
+    B: ResettableBackoff,
+    F: Fn(Duration) -> Duration + Send + Sync + Unpin,
+{
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+/// Builder returned by [`BackoffExt::jitter`].
+#[derive(Debug, Clone, Copy)]
+pub struct JitteredBuilder<B> {
+    inner: B,
+    jitter: Jitter,
+    seed: Option<u64>,
+}
+
+impl<B> JitteredBuilder<B> {
+    /// Set the seed value for the jitter random number generator. If no seed is given, a random seed is used in std and default seed is used in no_std.
+    pub fn with_jitter_seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+impl<B: BackoffBuilder> BackoffBuilder for JitteredBuilder<B> {
+    type Backoff = JitteredBackoff<B::Backoff>;
+
+    fn build(self) -> Self::Backoff {
+        JitteredBackoff {
+            inner: self.inner.build(),
+            jitter: self.jitter,
+            rng: super::new_rng(self.seed),
+        }
+    }
+
+    fn max_delay(&self) -> Option<Duration> {
+        self.inner.max_delay()
+    }
+}
+
+/// Backoff built by [`JitteredBuilder`].
+#[doc(hidden)]
+#[derive(Debug)]
+pub struct JitteredBackoff<B> {
+    inner: B,
+    jitter: Jitter,
+    rng: fastrand::Rng,
+}
+
+impl<B: Backoff> Iterator for JitteredBackoff<B> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.inner
+            .next()
+            .map(|dur| self.jitter.apply(dur, &mut self.rng))
+    }
+}
+
+impl<B: ResettableBackoff> ResettableBackoff for JitteredBackoff<B> {
+    fn reset(&mut self) {
+        self.inner.reset();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::*;
+    use crate::ConstantBuilder;
+    use crate::ExponentialBuilder;
+
+    #[test]
+    fn test_cap() {
+        let mut it = ExponentialBuilder::default()
+            .with_max_times(4)
+            .cap(Duration::from_secs(3))
+            .build();
+
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(Some(Duration::from_secs(2)), it.next());
+        assert_eq!(Some(Duration::from_secs(3)), it.next());
+        assert_eq!(Some(Duration::from_secs(3)), it.next());
+        assert_eq!(None, it.next());
+    }
+
+    #[test]
+    fn test_max_times() {
+        let mut it = ConstantBuilder::default()
+            .without_max_times()
+            .max_times(2)
+            .build();
+
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(None, it.next());
+
+        // The inner backoff still stops the adapter.
+        let mut it = ConstantBuilder::default()
+            .with_max_times(1)
+            .max_times(2)
+            .build();
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(None, it.next());
+    }
+
+    #[test]
+    fn test_max_total_delay() {
+        let mut it = ConstantBuilder::default()
+            .without_max_times()
+            .max_total_delay(Duration::from_millis(2500))
+            .build();
+
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(None, it.next());
+        assert_eq!(None, it.next());
+    }
+
+    #[test]
+    fn test_then() {
+        let mut it = ConstantBuilder::default()
+            .with_delay(Duration::from_millis(100))
+            .with_max_times(2)
+            .then(ExponentialBuilder::default().with_max_times(2))
+            .build();
+
+        assert_eq!(Some(Duration::from_millis(100)), it.next());
+        assert_eq!(Some(Duration::from_millis(100)), it.next());
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(Some(Duration::from_secs(2)), it.next());
+        assert_eq!(None, it.next());
+    }
+
+    #[test]
+    fn test_max_delay() {
+        let exponential = ExponentialBuilder::default().with_max_delay(Duration::from_secs(10));
+
+        assert_eq!(
+            exponential.cap(Duration::from_secs(3)).max_delay(),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            ConstantBuilder::default()
+                .with_delay(Duration::from_secs(5))
+                .cap(Duration::from_secs(3))
+                .max_delay(),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            exponential
+                .then(ExponentialBuilder::default())
+                .max_times(5)
+                .max_delay(),
+            Some(Duration::from_secs(60))
+        );
+        assert_eq!(
+            ConstantBuilder::default().then(exponential).max_delay(),
+            Some(Duration::from_secs(10))
+        );
+        // A hand-written backoff has no known maximum delay.
+        assert_eq!(
+            core::iter::repeat(Duration::from_secs(1))
+                .then(exponential)
+                .max_delay(),
+            None
+        );
+        assert_eq!(
+            exponential.map_delay(|d| d * 2).max_delay(),
+            Some(Duration::from_secs(20))
+        );
+    }
+
+    #[test]
+    fn test_map_delay() {
+        let mut it = ConstantBuilder::default().map_delay(|d| d * 2).build();
+
+        assert_eq!(Some(Duration::from_secs(2)), it.next());
+        assert_eq!(Some(Duration::from_secs(2)), it.next());
+        assert_eq!(Some(Duration::from_secs(2)), it.next());
+        assert_eq!(None, it.next());
+    }
+
+    #[test]
+    fn test_jitter() {
+        let mut it = ConstantBuilder::default().jitter(Jitter::Equal).build();
+
+        for _ in 0..3 {
+            let v = it.next().expect("value must valid");
+            assert!(v >= Duration::from_millis(500), "current: {v:?}");
+            assert!(v <= Duration::from_secs(1), "current: {v:?}");
+        }
+        assert_eq!(None, it.next());
+    }
+
+    #[test]
+    fn test_jitter_seed() {
+        let builder = ConstantBuilder::default()
+            .jitter(Jitter::Full)
+            .with_jitter_seed(0x2fdb0020ffc7722b);
+
+        assert!(builder.build().eq(builder.build()));
+    }
+
+    #[test]
+    fn test_on_backoff() {
+        // Adapters work on plain iterators as well.
+        let mut it = [Duration::from_secs(1), Duration::from_secs(5)]
+            .into_iter()
+            .cap(Duration::from_secs(2))
+            .build();
+
+        assert_eq!(Some(Duration::from_secs(1)), it.next());
+        assert_eq!(Some(Duration::from_secs(2)), it.next());
+        assert_eq!(None, it.next());
+    }
+}
@@ -1,12 +1,19 @@
+use core::fmt;
 use core::time::Duration;
 
+use super::duration::DisplayDuration;
+use super::policy;
 use crate::backoff::BackoffBuilder;
+use crate::backoff::ConfiguredBackoff;
+use crate::backoff::Jitter;
+use crate::backoff::ResettableBackoff;
+use crate::backoff::Schedule;
 
 /// FibonacciBuilder is used to build a [`FibonacciBackoff`] which offers a delay with Fibonacci-based retries.
 ///
 /// # Default
 ///
-/// - jitter: false
+/// - jitter: [`Jitter::None`]
 /// - min_delay: 1s
 /// - max_delay: 60s
 /// - max_times: 3
@@ -33,11 +40,21 @@ use crate::backoff::BackoffBuilder;
 ///     Ok(())
 /// }
 /// ```
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(default, deny_unknown_fields)
+)]
 pub struct FibonacciBuilder {
-    jitter: bool,
+    jitter: Jitter,
     seed: Option<u64>,
+    #[cfg_attr(feature = "serde", serde(with = "super::duration::serde_duration"))]
     min_delay: Duration,
+    #[cfg_attr(
+        feature = "serde",
+        serde(with = "super::duration::serde_duration::option")
+    )]
     max_delay: Option<Duration>,
     max_times: Option<usize>,
 }
@@ -52,7 +69,7 @@ impl FibonacciBuilder {
     /// Create a new `FibonacciBuilder` with default values.
     pub const fn new() -> Self {
         Self {
-            jitter: false,
+            jitter: Jitter::None,
             seed: None,
             min_delay: Duration::from_secs(1),
             max_delay: Some(Duration::from_secs(60)),
@@ -62,9 +79,20 @@ impl FibonacciBuilder {
 
     /// Set the jitter for the backoff.
     ///
-    /// When jitter is enabled, FibonacciBackoff will add a random jitter between `(0, current_delay)` to the delay.
+    /// When jitter is enabled, FibonacciBackoff will add a random jitter between `(0, min_delay)` to the delay.
+    ///
+    /// This is the same as `with_jitter_strategy(Jitter::Additive)`.
     pub const fn with_jitter(mut self) -> Self {
-        self.jitter = true;
+        self.jitter = Jitter::Additive;
+        self
+    }
+
+    /// Set the jitter strategy for the backoff.
+    ///
+    /// See [`Jitter`] for the available strategies. [`Jitter::None`] disables jitter, while
+    /// [`Jitter::Additive`] is based on the minimum delay, see [`FibonacciBuilder::with_jitter`].
+    pub const fn with_jitter_strategy(mut self, jitter: Jitter) -> Self {
+        self.jitter = jitter;
         self
     }
 
@@ -115,6 +143,22 @@ impl FibonacciBuilder {
         self.max_times = None;
         self
     }
+
+    /// Preview the delays planned by this builder.
+    ///
+    /// See [`Schedule`] for details.
+    pub fn schedule(&self) -> Schedule {
+        let nominal = FibonacciBuilder {
+            jitter: Jitter::None,
+            ..*self
+        };
+        Schedule::jittered_from(
+            ConfiguredBackoff::Fibonacci(nominal.build()),
+            self.jitter,
+            Some(self.min_delay),
+            None,
+        )
+    }
 }
 
 impl BackoffBuilder for FibonacciBuilder {
@@ -123,17 +167,7 @@ impl BackoffBuilder for FibonacciBuilder {
     fn build(self) -> Self::Backoff {
         FibonacciBackoff {
             jitter: self.jitter,
-            rng: if let Some(seed) = self.seed {
-                fastrand::Rng::with_seed(seed)
-            } else {
-                #[cfg(feature = "std")]
-                let rng = fastrand::Rng::new();
-
-                #[cfg(not(feature = "std"))]
-                let rng = fastrand::Rng::with_seed(super::RANDOM_SEED);
-
-                rng
-            },
+            rng: super::new_rng(self.seed),
             min_delay: self.min_delay,
             max_delay: self.max_delay,
             max_times: self.max_times,
@@ -143,6 +177,21 @@ impl BackoffBuilder for FibonacciBuilder {
             attempts: 0,
         }
     }
+
+    fn max_delay(&self) -> Option<Duration> {
+        self.max_delay
+    }
+}
+
+/// Display the builder as a policy that can be parsed back into a [`BackoffConfig`](crate::BackoffConfig).
+impl fmt::Display for FibonacciBuilder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "fibonacci(min={}", DisplayDuration(self.min_delay))?;
+        policy::fmt_delay(f, "max", self.max_delay)?;
+        policy::fmt_times(f, self.max_times)?;
+        policy::fmt_jitter(f, self.jitter, self.seed)?;
+        write!(f, ")")
+    }
 }
 
 impl BackoffBuilder for &FibonacciBuilder {
@@ -151,6 +200,10 @@ impl BackoffBuilder for &FibonacciBuilder {
     fn build(self) -> Self::Backoff {
         (*self).build()
     }
+
+    fn max_delay(&self) -> Option<Duration> {
+        (*self).max_delay()
+    }
 }
 
 /// FibonacciBackoff offers a delay with Fibonacci-based retries.
@@ -159,7 +212,7 @@ impl BackoffBuilder for &FibonacciBuilder {
 #[doc(hidden)]
 #[derive(Debug)]
 pub struct FibonacciBackoff {
-    jitter: bool,
+    jitter: Jitter,
     rng: fastrand::Rng,
     min_delay: Duration,
     max_delay: Option<Duration>,
@@ -182,15 +235,10 @@ impl Iterator for FibonacciBackoff {
         match self.current_delay {
             None => {
                 // If current_delay is None, it's must be the first time to retry.
-                let mut next = self.min_delay;
+                let next = self.min_delay;
                 self.current_delay = Some(next);
 
-                // If jitter is enabled, add random jitter based on min delay.
-                if self.jitter {
-                    next += next.mul_f32(self.rng.f32());
-                }
-
-                Some(next)
+                Some(self.jitter.apply_from(next, self.min_delay, &mut self.rng))
             }
             Some(cur) => {
                 let mut next = cur;
@@ -204,17 +252,20 @@ impl Iterator for FibonacciBackoff {
                     self.previous_delay = Some(cur);
                 }
 
-                // If jitter is enabled, add random jitter based on min delay.
-                if self.jitter {
-                    next += self.min_delay.mul_f32(self.rng.f32());
-                }
-
-                Some(next)
+                Some(self.jitter.apply_from(next, self.min_delay, &mut self.rng))
             }
         }
     }
 }
 
+impl ResettableBackoff for FibonacciBackoff {
+    fn reset(&mut self) {
+        self.previous_delay = None;
+        self.current_delay = None;
+        self.attempts = 0;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use core::time::Duration;
@@ -259,6 +310,35 @@ mod tests {
         assert_eq!(None, fib.next());
     }
 
+    #[test]
+    fn test_fibonacci_jitter_schedule() {
+        let builder = FibonacciBuilder::default().with_jitter();
+
+        // The jitter is based on the minimum delay rather than the current one.
+        let last = builder
+            .schedule()
+            .last()
+            .expect("schedule must not be empty");
+        assert_eq!(last.delay, Duration::from_secs(2)..=Duration::from_secs(3));
+    }
+
+    #[test]
+    fn test_fibonacci_jitter_strategy() {
+        let mut fib = FibonacciBuilder::default()
+            .with_jitter_strategy(Jitter::Symmetric(0.5))
+            .with_max_times(4)
+            .build();
+
+        for expected in [1, 1, 2, 3] {
+            let expected = Duration::from_secs(expected);
+            let v = fib.next().expect("value must valid");
+            assert!(v >= expected / 2, "current: {v:?}");
+            assert!(v <= expected + expected / 2, "current: {v:?}");
+        }
+
+        assert_eq!(None, fib.next());
+    }
+
     #[test]
     fn test_fibonacci_min_delay() {
         let mut fib = FibonacciBuilder::default()
@@ -337,7 +417,7 @@ mod tests {
     #[allow(clippy::assertions_on_constants)]
     #[test]
     fn test_fibonacci_const_builder() {
-        assert!(TEST_BUILDER.jitter);
+        assert_eq!(TEST_BUILDER.jitter, Jitter::Additive);
         assert_eq!(TEST_BUILDER.min_delay, Duration::from_secs(2));
         assert_eq!(TEST_BUILDER.max_delay, Some(Duration::from_secs(30)));
         assert_eq!(TEST_BUILDER.max_times, Some(5));
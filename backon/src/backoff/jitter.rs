@@ -0,0 +1,240 @@
+use core::fmt;
+use core::time::Duration;
+
+use super::exponential::saturating_mul;
+
+/// Jitter is the strategy used to randomize the delays produced by a backoff.
+///
+/// Jitter spreads the retries of many clients over time to prevent a thundering herd problem.
+/// It is accepted by [`ExponentialBuilder`](crate::ExponentialBuilder),
+/// [`FibonacciBuilder`](crate::FibonacciBuilder) and [`ConstantBuilder`](crate::ConstantBuilder)
+/// through `with_jitter_strategy`.
+///
+/// In the descriptions below, `delay` is the delay computed by the backoff before jitter is applied.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use backon::BackoffBuilder;
+/// use backon::ExponentialBuilder;
+/// use backon::Jitter;
+///
+/// let mut backoff = ExponentialBuilder::default()
+///     .with_jitter_strategy(Jitter::Full)
+///     .build();
+///
+/// let delay = backoff.next().unwrap();
+/// assert!(delay <= Duration::from_secs(1));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(rename_all = "snake_case")
+)]
+pub enum Jitter {
+    /// Don't apply any jitter: use `delay` as is.
+    #[default]
+    None,
+    /// Pick a random delay within `[0, delay]`.
+    Full,
+    /// Keep half of the delay and randomize the other half: `delay / 2 + random(0, delay / 2)`.
+    Equal,
+    /// Pick a random delay within `[delay * (1 - ratio), delay * (1 + ratio)]`.
+    ///
+    /// The ratio is clamped to `[0.0, 1.0]`, and a ratio that isn't finite, like `NaN`, is taken
+    /// as `0.0`.
+    Symmetric(f32),
+    /// Add a random value within `[0, delay]` to the delay: `delay + random(0, delay)`.
+    ///
+    /// This is the strategy used by `with_jitter()`. [`FibonacciBuilder`](crate::FibonacciBuilder)
+    /// adds a random value within `[0, min_delay]` instead.
+    Additive,
+}
+
+impl Jitter {
+    /// The ratio of [`Jitter::Symmetric`] actually used, see its docs.
+    fn symmetric_ratio(ratio: f32) -> f32 {
+        if ratio.is_finite() {
+            ratio.clamp(0.0, 1.0)
+        } else {
+            0.0
+        }
+    }
+
+    /// Apply the jitter to the given delay.
+    pub(crate) fn apply(self, delay: Duration, rng: &mut fastrand::Rng) -> Duration {
+        self.apply_from(delay, delay, rng)
+    }
+
+    /// Apply the jitter to the given delay, [`Jitter::Additive`] adding a random value within
+    /// `[0, base]` rather than `[0, delay]`.
+    pub(crate) fn apply_from(
+        self,
+        delay: Duration,
+        base: Duration,
+        rng: &mut fastrand::Rng,
+    ) -> Duration {
+        match self {
+            Jitter::None => delay,
+            Jitter::Full => saturating_mul(delay, rng.f32()).min(delay),
+            Jitter::Equal => {
+                let half = delay / 2;
+                half.saturating_add(saturating_mul(delay - half, rng.f32()))
+                    .min(delay)
+            }
+            Jitter::Symmetric(ratio) => {
+                let ratio = Self::symmetric_ratio(ratio);
+                let lower = delay.saturating_sub(saturating_mul(delay, ratio));
+                lower.saturating_add(saturating_mul(delay, 2.0 * ratio * rng.f32()))
+            }
+            Jitter::Additive => delay.saturating_add(saturating_mul(base, rng.f32())),
+        }
+    }
+
+    /// The range of the delays [`Jitter::apply`] can return for the given delay.
+    pub(crate) fn bounds(self, delay: Duration) -> (Duration, Duration) {
+        self.bounds_from(delay, delay)
+    }
+
+    /// The range of the delays [`Jitter::apply_from`] can return for the given delay and base.
+    pub(crate) fn bounds_from(self, delay: Duration, base: Duration) -> (Duration, Duration) {
+        match self {
+            Jitter::None => (delay, delay),
+            Jitter::Full => (Duration::ZERO, delay),
+            Jitter::Equal => (delay / 2, delay),
+            Jitter::Symmetric(ratio) => {
+                let ratio = Self::symmetric_ratio(ratio);
+                let lower = delay.saturating_sub(saturating_mul(delay, ratio));
+                (
+                    lower,
+                    lower.saturating_add(saturating_mul(delay, 2.0 * ratio)),
+                )
+            }
+            Jitter::Additive => (delay, delay.saturating_add(base)),
+        }
+    }
+}
+
+impl fmt::Display for Jitter {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Jitter::None => write!(f, "none"),
+            Jitter::Full => write!(f, "full"),
+            Jitter::Equal => write!(f, "equal"),
+            Jitter::Symmetric(ratio) => {
+                write!(f, "symmetric({})", Jitter::symmetric_ratio(*ratio))
+            }
+            Jitter::Additive => write!(f, "additive"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::*;
+
+    fn assert_within(jitter: Jitter, min: Duration, max: Duration) {
+        // Allow some tolerance for the `f32` arithmetic.
+        let (min, max) = (
+            min.saturating_sub(Duration::from_millis(1)),
+            max + Duration::from_millis(1),
+        );
+        let (lower, upper) = jitter.bounds(Duration::from_secs(10));
+        assert!(
+            lower >= min && upper <= max,
+            "{jitter:?} bounds: {lower:?}..={upper:?}"
+        );
+
+        let mut rng = fastrand::Rng::with_seed(0x2fdb0020ffc7722b);
+        for _ in 0..1_000 {
+            let v = jitter.apply(Duration::from_secs(10), &mut rng);
+            assert!(v >= min, "{jitter:?} current: {v:?}");
+            assert!(v <= max, "{jitter:?} current: {v:?}");
+            assert!(
+                v >= lower.saturating_sub(Duration::from_millis(1)),
+                "{jitter:?} current: {v:?}"
+            );
+            assert!(
+                v <= upper + Duration::from_millis(1),
+                "{jitter:?} current: {v:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_jitter_none() {
+        let mut rng = fastrand::Rng::with_seed(0x2fdb0020ffc7722b);
+        assert_eq!(
+            Duration::from_secs(10),
+            Jitter::None.apply(Duration::from_secs(10), &mut rng)
+        );
+    }
+
+    #[test]
+    fn test_jitter_full() {
+        assert_within(Jitter::Full, Duration::ZERO, Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_jitter_equal() {
+        assert_within(
+            Jitter::Equal,
+            Duration::from_secs(5),
+            Duration::from_secs(10),
+        );
+    }
+
+    #[test]
+    fn test_jitter_symmetric() {
+        assert_within(
+            Jitter::Symmetric(0.2),
+            Duration::from_secs(8),
+            Duration::from_secs(12),
+        );
+        // The ratio is clamped to `[0.0, 1.0]`.
+        assert_within(
+            Jitter::Symmetric(5.0),
+            Duration::ZERO,
+            Duration::from_secs(20),
+        );
+        // The ratios that aren't finite are taken as `0.0`.
+        for ratio in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            assert_within(
+                Jitter::Symmetric(ratio),
+                Duration::from_secs(10),
+                Duration::from_secs(10),
+            );
+        }
+    }
+
+    #[test]
+    fn test_jitter_additive() {
+        assert_within(
+            Jitter::Additive,
+            Duration::from_secs(10),
+            Duration::from_secs(20),
+        );
+    }
+
+    #[test]
+    fn test_jitter_saturate() {
+        let mut rng = fastrand::Rng::with_seed(0x2fdb0020ffc7722b);
+        for jitter in [
+            Jitter::Full,
+            Jitter::Equal,
+            Jitter::Symmetric(1.0),
+            Jitter::Additive,
+        ] {
+            // Must not panic on overflow.
+            let _ = jitter.apply(Duration::MAX, &mut rng);
+        }
+    }
+}
@@ -1,6 +1,11 @@
 mod api;
 pub use api::*;
 
+mod duration;
+
+mod jitter;
+pub use jitter::Jitter;
+
 mod constant;
 pub use constant::ConstantBackoff;
 pub use constant::ConstantBuilder;
@@ -13,6 +18,58 @@ mod exponential;
 pub use exponential::ExponentialBackoff;
 pub use exponential::ExponentialBuilder;
 
+mod decorrelated_jitter;
+pub use decorrelated_jitter::DecorrelatedJitterBackoff;
+pub use decorrelated_jitter::DecorrelatedJitterBuilder;
+
+mod config;
+pub use config::BackoffConfig;
+pub use config::ConfiguredBackoff;
+
+mod policy;
+pub use policy::ParseBackoffError;
+
+mod schedule;
+pub use schedule::Schedule;
+pub use schedule::ScheduleEntry;
+
+mod reset;
+#[cfg(feature = "std")]
+pub use reset::AutoResetBackoff;
+pub use reset::RebuildBackoff;
+
+mod ext;
+pub use ext::BackoffExt;
+pub use ext::CapBackoff;
+pub use ext::CapBuilder;
+pub use ext::JitteredBackoff;
+pub use ext::JitteredBuilder;
+pub use ext::MapDelayBackoff;
+pub use ext::MapDelayBuilder;
+pub use ext::MaxTimesBackoff;
+pub use ext::MaxTimesBuilder;
+pub use ext::ThenBackoff;
+pub use ext::ThenBuilder;
+pub use ext::TotalDelayBackoff;
+pub use ext::TotalDelayBuilder;
+
 // Random seed value for no_std (the value is "backon" in hex)
 #[cfg(not(feature = "std"))]
 const RANDOM_SEED: u64 = 0x6261636b6f6e;
+
+/// Create the random number generator used for jitter.
+///
+/// If no seed is given, a random seed is used in std and default seed is used in no_std.
+fn new_rng(seed: Option<u64>) -> fastrand::Rng {
+    if let Some(seed) = seed {
+        return fastrand::Rng::with_seed(seed);
+    }
+
+    #[cfg(feature = "std")]
+    let rng = fastrand::Rng::new();
+
+    #[cfg(not(feature = "std"))]
+    let rng = fastrand::Rng::with_seed(RANDOM_SEED);
+
+    rng
+}
@@ -0,0 +1,626 @@
+//! String representation of backoff policies like `exponential(min=100ms, max=10s, jitter)`.
+
+use core::fmt;
+use core::ops::Range;
+use core::str::FromStr;
+use core::time::Duration;
+
+use super::duration::DisplayDuration;
+use super::duration::ParseDurationError;
+use super::duration::parse_duration;
+use crate::backoff::BackoffConfig;
+use crate::backoff::ConstantBuilder;
+use crate::backoff::DecorrelatedJitterBuilder;
+use crate::backoff::ExponentialBuilder;
+use crate::backoff::FibonacciBuilder;
+use crate::backoff::Jitter;
+
+const EXPONENTIAL_KEYS: &[&str] = &["min", "max", "factor", "times", "total", "jitter", "seed"];
+const FIBONACCI_KEYS: &[&str] = &["min", "max", "times", "jitter", "seed"];
+const CONSTANT_KEYS: &[&str] = &["delay", "times", "jitter", "seed"];
+const DECORRELATED_JITTER_KEYS: &[&str] = &["min", "max", "times", "total", "seed"];
+
+/// The error returned when parsing a [`BackoffConfig`] from a string failed.
+///
+/// The [`span`](ParseBackoffError::span) points to the part of the input that caused the error.
+///
+/// # Examples
+///
+/// ```
+/// use backon::BackoffConfig;
+///
+/// let err = "constant(delay=1y)".parse::<BackoffConfig>().unwrap_err();
+/// assert_eq!(err.span(), 15..17);
+/// assert_eq!(
+///     err.to_string(),
+///     "invalid duration: unknown unit, expected one of ns, us, ms, s, m, h or d at 15..17"
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseBackoffError {
+    kind: ErrorKind,
+    span: Range<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ErrorKind {
+    MissingKind,
+    UnknownKind,
+    UnclosedParen,
+    ExpectedArgument,
+    ExpectedKey,
+    UnknownKey(&'static [&'static str]),
+    DuplicateKey,
+    MissingValue,
+    InvalidDuration(ParseDurationError),
+    InvalidNumber,
+    InvalidJitter,
+}
+
+impl ParseBackoffError {
+    fn new(kind: ErrorKind, span: Range<usize>) -> Self {
+        Self { kind, span }
+    }
+
+    /// The byte range of the input that caused the error.
+    pub fn span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+}
+
+impl fmt::Display for ParseBackoffError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ErrorKind::MissingKind => write!(f, "expected a backoff kind")?,
+            ErrorKind::UnknownKind => write!(
+                f,
+                "unknown backoff kind, expected one of exponential, fibonacci, constant or decorrelated_jitter"
+            )?,
+            ErrorKind::UnclosedParen => write!(f, "expected `)`")?,
+            ErrorKind::ExpectedArgument => write!(f, "expected an argument")?,
+            ErrorKind::ExpectedKey => write!(f, "expected an argument name before `=`")?,
+            ErrorKind::UnknownKey(keys) => {
+                write!(f, "unknown argument, expected one of ")?;
+                for (idx, key) in keys.iter().enumerate() {
+                    if idx > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "{key}")?;
+                }
+            }
+            ErrorKind::DuplicateKey => write!(f, "argument is specified more than once")?,
+            ErrorKind::MissingValue => write!(f, "missing value")?,
+            ErrorKind::InvalidDuration(err) => write!(f, "invalid duration: {err}")?,
+            ErrorKind::InvalidNumber => write!(f, "invalid number")?,
+            ErrorKind::InvalidJitter => write!(
+                f,
+                "invalid jitter, expected one of none, full, equal, additive or symmetric(ratio)"
+            )?,
+        }
+        write!(f, " at {}..{}", self.span.start, self.span.end)
+    }
+}
+
+impl core::error::Error for ParseBackoffError {}
+
+impl FromStr for BackoffConfig {
+    type Err = ParseBackoffError;
+
+    /// Parse a policy like `exponential(min=100ms, max=10s, factor=2, times=5, jitter)`.
+    ///
+    /// See [`BackoffConfig`] for the accepted arguments.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (kind, args) = split_policy(s)?;
+        let args = Args::new(s, args);
+        match kind.text {
+            "exponential" => {
+                let mut builder = ExponentialBuilder::new();
+                parse_args(args, EXPONENTIAL_KEYS, |key, arg| {
+                    builder = match key {
+                        "min" => builder.with_min_delay(arg.duration()?),
+                        "max" => match arg.optional_duration()? {
+                            Some(max_delay) => builder.with_max_delay(max_delay),
+                            None => builder.without_max_delay(),
+                        },
+                        "factor" => builder.with_factor(arg.factor()?),
+                        "times" => match arg.times()? {
+                            Some(max_times) => builder.with_max_times(max_times),
+                            None => builder.without_max_times(),
+                        },
+                        "total" => builder.with_total_delay(arg.optional_duration()?),
+                        "jitter" => builder.with_jitter_strategy(arg.jitter()?),
+                        "seed" => builder.with_jitter_seed(arg.seed()?),
+                        _ => unreachable!("key must be one of the exponential keys"),
+                    };
+                    Ok(())
+                })?;
+                Ok(BackoffConfig::Exponential(builder))
+            }
+            "fibonacci" => {
+                let mut builder = FibonacciBuilder::new();
+                parse_args(args, FIBONACCI_KEYS, |key, arg| {
+                    builder = match key {
+                        "min" => builder.with_min_delay(arg.duration()?),
+                        "max" => match arg.optional_duration()? {
+                            Some(max_delay) => builder.with_max_delay(max_delay),
+                            None => builder.without_max_delay(),
+                        },
+                        "times" => match arg.times()? {
+                            Some(max_times) => builder.with_max_times(max_times),
+                            None => builder.without_max_times(),
+                        },
+                        "jitter" => builder.with_jitter_strategy(arg.jitter()?),
+                        "seed" => builder.with_jitter_seed(arg.seed()?),
+                        _ => unreachable!("key must be one of the fibonacci keys"),
+                    };
+                    Ok(())
+                })?;
+                Ok(BackoffConfig::Fibonacci(builder))
+            }
+            "constant" => {
+                let mut builder = ConstantBuilder::new();
+                parse_args(args, CONSTANT_KEYS, |key, arg| {
+                    builder = match key {
+                        "delay" => builder.with_delay(arg.duration()?),
+                        "times" => match arg.times()? {
+                            Some(max_times) => builder.with_max_times(max_times),
+                            None => builder.without_max_times(),
+                        },
+                        "jitter" => builder.with_jitter_strategy(arg.jitter()?),
+                        "seed" => builder.with_jitter_seed(arg.seed()?),
+                        _ => unreachable!("key must be one of the constant keys"),
+                    };
+                    Ok(())
+                })?;
+                Ok(BackoffConfig::Constant(builder))
+            }
+            "decorrelated_jitter" => {
+                let mut builder = DecorrelatedJitterBuilder::new();
+                parse_args(args, DECORRELATED_JITTER_KEYS, |key, arg| {
+                    builder = match key {
+                        "min" => builder.with_min_delay(arg.duration()?),
+                        "max" => match arg.optional_duration()? {
+                            Some(max_delay) => builder.with_max_delay(max_delay),
+                            None => builder.without_max_delay(),
+                        },
+                        "times" => match arg.times()? {
+                            Some(max_times) => builder.with_max_times(max_times),
+                            None => builder.without_max_times(),
+                        },
+                        "total" => builder.with_total_delay(arg.optional_duration()?),
+                        "seed" => builder.with_jitter_seed(arg.seed()?),
+                        _ => unreachable!("key must be one of the decorrelated_jitter keys"),
+                    };
+                    Ok(())
+                })?;
+                Ok(BackoffConfig::DecorrelatedJitter(builder))
+            }
+            _ => Err(ParseBackoffError::new(ErrorKind::UnknownKind, kind.span)),
+        }
+    }
+}
+
+/// A trimmed part of the input along with its position.
+struct Token<'a> {
+    text: &'a str,
+    span: Range<usize>,
+}
+
+impl<'a> Token<'a> {
+    fn new(input: &'a str, range: Range<usize>) -> Self {
+        let raw = &input[range.clone()];
+        let start = range.start + (raw.len() - raw.trim_start().len());
+        let text = raw.trim();
+        Token {
+            text,
+            span: start..start + text.len(),
+        }
+    }
+}
+
+/// Split `kind(args)` into the kind and the range of the arguments.
+///
+/// The parentheses can be omitted to use the default values.
+fn split_policy(input: &str) -> Result<(Token<'_>, Range<usize>), ParseBackoffError> {
+    let end = input.trim_end().len();
+    let (kind, args) = match input.find('(') {
+        Some(open) => {
+            if end <= open + 1 || !input[..end].ends_with(')') {
+                return Err(ParseBackoffError::new(ErrorKind::UnclosedParen, end..end));
+            }
+            (Token::new(input, 0..open), open + 1..end - 1)
+        }
+        None => (Token::new(input, 0..end), end..end),
+    };
+    if kind.text.is_empty() {
+        return Err(ParseBackoffError::new(ErrorKind::MissingKind, kind.span));
+    }
+    Ok((kind, args))
+}
+
+/// Parse the arguments, rejecting unknown and duplicated keys.
+fn parse_args<'a>(
+    args: Args<'a>,
+    keys: &'static [&'static str],
+    mut f: impl FnMut(&'static str, Arg<'a>) -> Result<(), ParseBackoffError>,
+) -> Result<(), ParseBackoffError> {
+    let mut seen = 0u32;
+    for arg in args {
+        let arg = arg?;
+        let Some(idx) = keys.iter().position(|key| *key == arg.key.text) else {
+            return Err(ParseBackoffError::new(
+                ErrorKind::UnknownKey(keys),
+                arg.key.span,
+            ));
+        };
+        if seen & (1 << idx) != 0 {
+            return Err(ParseBackoffError::new(
+                ErrorKind::DuplicateKey,
+                arg.key.span,
+            ));
+        }
+        seen |= 1 << idx;
+        f(keys[idx], arg)?;
+    }
+    Ok(())
+}
+
+/// Iterator over the comma separated arguments. A trailing comma is allowed.
+struct Args<'a> {
+    input: &'a str,
+    pos: usize,
+    end: usize,
+    done: bool,
+}
+
+impl<'a> Args<'a> {
+    fn new(input: &'a str, range: Range<usize>) -> Self {
+        Args {
+            input,
+            pos: range.start,
+            end: range.end,
+            done: false,
+        }
+    }
+}
+
+impl<'a> Iterator for Args<'a> {
+    type Item = Result<Arg<'a>, ParseBackoffError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+
+        let segment_end = match self.input[self.pos..self.end].find(',') {
+            Some(idx) => self.pos + idx,
+            None => {
+                self.done = true;
+                self.end
+            }
+        };
+        let token = Token::new(self.input, self.pos..segment_end);
+        self.pos = segment_end + 1;
+        if token.text.is_empty() {
+            if self.done {
+                return None;
+            }
+            return Some(Err(ParseBackoffError::new(
+                ErrorKind::ExpectedArgument,
+                token.span,
+            )));
+        }
+
+        let Some(eq) = token.text.find('=') else {
+            return Some(Ok(Arg {
+                key: token,
+                value: None,
+            }));
+        };
+        let eq = token.span.start + eq;
+        let key = Token::new(self.input, token.span.start..eq);
+        let value = Token::new(self.input, eq + 1..token.span.end);
+        if key.text.is_empty() {
+            return Some(Err(ParseBackoffError::new(
+                ErrorKind::ExpectedKey,
+                eq..eq + 1,
+            )));
+        }
+        if value.text.is_empty() {
+            return Some(Err(ParseBackoffError::new(
+                ErrorKind::MissingValue,
+                value.span,
+            )));
+        }
+        Some(Ok(Arg {
+            key,
+            value: Some(value),
+        }))
+    }
+}
+
+/// An argument like `min=100ms` or `jitter`.
+struct Arg<'a> {
+    key: Token<'a>,
+    value: Option<Token<'a>>,
+}
+
+impl Arg<'_> {
+    fn value(&self) -> Result<&Token<'_>, ParseBackoffError> {
+        self.value.as_ref().ok_or_else(|| {
+            ParseBackoffError::new(
+                ErrorKind::MissingValue,
+                self.key.span.end..self.key.span.end,
+            )
+        })
+    }
+
+    fn duration(&self) -> Result<Duration, ParseBackoffError> {
+        let value = self.value()?;
+        parse_duration(value.text).map_err(|err| {
+            ParseBackoffError::new(ErrorKind::InvalidDuration(err), value.span.clone())
+        })
+    }
+
+    /// Parse a duration, `none` stands for no limit.
+    fn optional_duration(&self) -> Result<Option<Duration>, ParseBackoffError> {
+        if self.value()?.text == "none" {
+            return Ok(None);
+        }
+        self.duration().map(Some)
+    }
+
+    /// Parse the max times, `none` stands for no limit.
+    fn times(&self) -> Result<Option<usize>, ParseBackoffError> {
+        let value = self.value()?;
+        if value.text == "none" {
+            return Ok(None);
+        }
+        value
+            .text
+            .parse()
+            .map(Some)
+            .map_err(|_| ParseBackoffError::new(ErrorKind::InvalidNumber, value.span.clone()))
+    }
+
+    fn factor(&self) -> Result<f32, ParseBackoffError> {
+        parse_f32(self.value()?)
+    }
+
+    fn seed(&self) -> Result<u64, ParseBackoffError> {
+        let value = self.value()?;
+        value
+            .text
+            .parse()
+            .map_err(|_| ParseBackoffError::new(ErrorKind::InvalidNumber, value.span.clone()))
+    }
+
+    /// Parse the jitter strategy, a bare `jitter` stands for [`Jitter::Additive`] like `with_jitter()`.
+    fn jitter(&self) -> Result<Jitter, ParseBackoffError> {
+        let Some(value) = &self.value else {
+            return Ok(Jitter::Additive);
+        };
+        match value.text {
+            "none" => Ok(Jitter::None),
+            "full" => Ok(Jitter::Full),
+            "equal" => Ok(Jitter::Equal),
+            "additive" => Ok(Jitter::Additive),
+            text => {
+                let Some(ratio) = text
+                    .strip_prefix("symmetric(")
+                    .and_then(|s| s.strip_suffix(')'))
+                else {
+                    return Err(ParseBackoffError::new(
+                        ErrorKind::InvalidJitter,
+                        value.span.clone(),
+                    ));
+                };
+                let start = value.span.start
+                    + "symmetric(".len()
+                    + (ratio.len() - ratio.trim_start().len());
+                let ratio = ratio.trim();
+                parse_f32(&Token {
+                    text: ratio,
+                    span: start..start + ratio.len(),
+                })
+                .map(Jitter::Symmetric)
+            }
+        }
+    }
+}
+
+fn parse_f32(value: &Token<'_>) -> Result<f32, ParseBackoffError> {
+    match value.text.parse::<f32>() {
+        Ok(v) if v.is_finite() => Ok(v),
+        _ => Err(ParseBackoffError::new(
+            ErrorKind::InvalidNumber,
+            value.span.clone(),
+        )),
+    }
+}
+
+/// Write the `max` or `total` argument, `none` standing for no limit.
+pub(super) fn fmt_delay(
+    f: &mut fmt::Formatter<'_>,
+    key: &str,
+    delay: Option<Duration>,
+) -> fmt::Result {
+    match delay {
+        Some(delay) => write!(f, ", {key}={}", DisplayDuration(delay)),
+        None => write!(f, ", {key}=none"),
+    }
+}
+
+/// Write the `times` argument, `none` standing for no limit.
+pub(super) fn fmt_times(f: &mut fmt::Formatter<'_>, times: Option<usize>) -> fmt::Result {
+    match times {
+        Some(times) => write!(f, ", times={times}"),
+        None => write!(f, ", times=none"),
+    }
+}
+
+/// Write the `jitter` and `seed` arguments, omitted when unset.
+pub(super) fn fmt_jitter(
+    f: &mut fmt::Formatter<'_>,
+    jitter: Jitter,
+    seed: Option<u64>,
+) -> fmt::Result {
+    match jitter {
+        Jitter::None => {}
+        Jitter::Additive => write!(f, ", jitter")?,
+        jitter => write!(f, ", jitter={jitter}")?,
+    }
+    if let Some(seed) = seed {
+        write!(f, ", seed={seed}")?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate alloc;
+
+    use alloc::string::ToString;
+    use core::time::Duration;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::*;
+
+    fn parse(s: &str) -> BackoffConfig {
+        s.parse().unwrap_or_else(|err| panic!("{s:?}: {err}"))
+    }
+
+    #[test]
+    fn test_parse_policy() {
+        assert_eq!(
+            parse(
+                "exponential(min=100ms, max=10s, factor=3, times=5, total=1m, jitter=full, seed=42)"
+            ),
+            BackoffConfig::Exponential(
+                ExponentialBuilder::new()
+                    .with_min_delay(Duration::from_millis(100))
+                    .with_max_delay(Duration::from_secs(10))
+                    .with_factor(3.0)
+                    .with_max_times(5)
+                    .with_total_delay(Some(Duration::from_secs(60)))
+                    .with_jitter_strategy(Jitter::Full)
+                    .with_jitter_seed(42)
+            )
+        );
+        assert_eq!(
+            parse("fibonacci(max=none, times=none, jitter)"),
+            BackoffConfig::Fibonacci(
+                FibonacciBuilder::new()
+                    .without_max_delay()
+                    .without_max_times()
+                    .with_jitter()
+            )
+        );
+        assert_eq!(
+            parse(" constant ( delay = 1.5s , jitter = symmetric( 0.25 ) , ) "),
+            BackoffConfig::Constant(
+                ConstantBuilder::new()
+                    .with_delay(Duration::from_millis(1500))
+                    .with_jitter_strategy(Jitter::Symmetric(0.25))
+            )
+        );
+        assert_eq!(
+            parse("decorrelated_jitter(min=10ms, total=none)"),
+            BackoffConfig::DecorrelatedJitter(
+                DecorrelatedJitterBuilder::new().with_min_delay(Duration::from_millis(10))
+            )
+        );
+    }
+
+    #[test]
+    fn test_parse_policy_defaults() {
+        assert_eq!(parse("exponential"), BackoffConfig::default());
+        assert_eq!(parse("exponential()"), BackoffConfig::default());
+        assert_eq!(
+            parse("constant()"),
+            BackoffConfig::Constant(ConstantBuilder::default())
+        );
+    }
+
+    #[test]
+    fn test_parse_policy_error() {
+        let cases = [
+            ("", "expected a backoff kind", 0..0),
+            ("(min=1s)", "expected a backoff kind", 0..0),
+            ("linear(delay=1s)", "unknown backoff kind", 0..6),
+            ("constant(delay=1s", "expected `)`", 17..17),
+            ("constant(,)", "expected an argument", 9..9),
+            ("constant(=1s)", "expected an argument name", 9..10),
+            (
+                "constant(min=1s)",
+                "unknown argument, expected one of delay, times, jitter, seed",
+                9..12,
+            ),
+            (
+                "decorrelated_jitter(jitter)",
+                "unknown argument, expected one of min, max, times, total, seed",
+                20..26,
+            ),
+            (
+                "constant(delay=1s, delay=2s)",
+                "argument is specified more than once",
+                19..24,
+            ),
+            ("constant(delay)", "missing value", 14..14),
+            ("constant(delay=)", "missing value", 15..15),
+            ("constant(delay=1y)", "invalid duration", 15..17),
+            ("constant(times=-1)", "invalid number", 15..17),
+            ("exponential(factor=NaN)", "invalid number", 19..22),
+            ("exponential(jitter=half)", "invalid jitter", 19..23),
+            ("exponential(jitter=symmetric(x))", "invalid number", 29..30),
+        ];
+        for (input, message, span) in cases {
+            let err = input.parse::<BackoffConfig>().unwrap_err();
+            assert!(
+                err.to_string().starts_with(message),
+                "{input:?}: unexpected error {err}"
+            );
+            assert_eq!(err.span(), span, "{input:?}: {err}");
+        }
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let cases = [
+            "exponential(min=100ms, max=10s, factor=2, times=5, jitter)",
+            "exponential(min=1s, max=none, factor=1.5, times=none, total=2m, jitter=equal, seed=7)",
+            "fibonacci(min=1s, max=1m, times=3, jitter=symmetric(0.2))",
+            "constant(delay=250ms, times=3, jitter=full)",
+            "decorrelated_jitter(min=1s, max=1m, times=3, seed=42)",
+        ];
+        for policy in cases {
+            assert_eq!(parse(policy).to_string(), policy);
+        }
+
+        assert_eq!(
+            ExponentialBuilder::default().to_string(),
+            "exponential(min=1s, max=1m, factor=2, times=3)"
+        );
+        assert_eq!(
+            FibonacciBuilder::default().to_string(),
+            "fibonacci(min=1s, max=1m, times=3)"
+        );
+        assert_eq!(
+            ConstantBuilder::default().to_string(),
+            "constant(delay=1s, times=3)"
+        );
+        assert_eq!(
+            DecorrelatedJitterBuilder::default().to_string(),
+            "decorrelated_jitter(min=1s, max=1m, times=3)"
+        );
+        // The ratios that can't be parsed back are written as the ratio actually used.
+        let nan = ConstantBuilder::default().with_jitter_strategy(Jitter::Symmetric(f32::NAN));
+        assert_eq!(
+            nan.to_string(),
+            "constant(delay=1s, times=3, jitter=symmetric(0))"
+        );
+        assert!(nan.to_string().parse::<BackoffConfig>().is_ok());
+    }
+}
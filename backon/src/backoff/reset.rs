@@ -0,0 +1,266 @@
+use core::time::Duration;
+
+#[cfg(feature = "std")]
+use crate::Clock;
+#[cfg(feature = "std")]
+use crate::StdClock;
+#[cfg(feature = "std")]
+use crate::backoff::Backoff;
+use crate::backoff::BackoffBuilder;
+use crate::backoff::ResettableBackoff;
+
+/// RebuildBackoff makes any backoff resettable by building it again from a copy of its builder.
+///
+/// Prefer calling [`ResettableBackoff::reset`] on the built-in backoffs directly, this wrapper is
+/// meant for custom backoffs that can't be rewound.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use backon::BackoffExt;
+/// use backon::ConstantBuilder;
+/// use backon::RebuildBackoff;
+/// use backon::ResettableBackoff;
+///
+/// let builder = ConstantBuilder::default().map_delay(|d| d * 2);
+/// let mut backoff = RebuildBackoff::new(builder);
+/// assert_eq!(backoff.next(), Some(Duration::from_secs(2)));
+///
+/// backoff.reset();
+/// assert_eq!(backoff.count(), 3);
+/// ```
+pub struct RebuildBackoff<B: BackoffBuilder> {
+    builder: B,
+    backoff: B::Backoff,
+}
+
+impl<B: BackoffBuilder + Clone> RebuildBackoff<B> {
+    /// Create a new `RebuildBackoff` from the given builder.
+    pub fn new(builder: B) -> Self {
+        let backoff = builder.clone().build();
+        Self { builder, backoff }
+    }
+}
+
+impl<B: BackoffBuilder> Iterator for RebuildBackoff<B> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.backoff.next()
+    }
+}
+
+impl<B: BackoffBuilder + Clone> ResettableBackoff for RebuildBackoff<B> {
+    fn reset(&mut self) {
+        self.backoff = self.builder.clone().build();
+    }
+}
+
+/// AutoResetBackoff resets the wrapped backoff after a quiet period without failures.
+///
+/// The backoff is reset when the time elapsed since the previous call to `next` is longer than
+/// the delay returned by that call plus `quiet`. In other words, as soon as the operation has been
+/// working for `quiet` after the last retry, the next failure starts over from the first delay.
+/// This also applies once the wrapped backoff has been exhausted.
+///
+/// The time is measured with [`StdClock`] by default, another [`Clock`] can be set with
+/// [`AutoResetBackoff::clock`]. This requires the `std` feature.
+///
+/// # Examples
+///
+/// ```no_run
+/// use core::time::Duration;
+///
+/// use backon::AutoResetBackoff;
+/// use backon::BackoffBuilder;
+/// use backon::ExponentialBuilder;
+///
+/// fn connect() -> std::io::Result<()> {
+///     Ok(())
+/// }
+///
+/// let mut backoff = AutoResetBackoff::new(
+///     ExponentialBuilder::default().without_max_times().build(),
+///     Duration::from_secs(60),
+/// );
+///
+/// loop {
+///     if let Err(err) = connect() {
+///         let delay = backoff.next().unwrap();
+///         println!("connection lost: {err}, reconnecting in {delay:?}");
+///         std::thread::sleep(delay);
+///     }
+/// }
+/// ```
+#[cfg(feature = "std")]
+#[derive(Debug)]
+pub struct AutoResetBackoff<B, C: Clock = StdClock> {
+    inner: B,
+    quiet: Duration,
+    clock: C,
+    last: Option<(C::Instant, Duration)>,
+}
+
+#[cfg(feature = "std")]
+impl<B: ResettableBackoff> AutoResetBackoff<B> {
+    /// Wrap the backoff to reset it after `quiet` without failures.
+    pub fn new(inner: B, quiet: Duration) -> Self {
+        Self {
+            inner,
+            quiet,
+            clock: StdClock,
+            last: None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<B: ResettableBackoff, C: Clock> AutoResetBackoff<B, C> {
+    /// Set the clock measuring the quiet period, like a [`ManualClock`](crate::ManualClock) in
+    /// the tests.
+    pub fn clock<CN: Clock>(self, clock: CN) -> AutoResetBackoff<B, CN> {
+        AutoResetBackoff {
+            inner: self.inner,
+            quiet: self.quiet,
+            clock,
+            last: None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl<B: ResettableBackoff, C: Clock> Iterator for AutoResetBackoff<B, C> {
+    type Item = Duration;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let now = self.clock.now();
+        if let Some((at, delay)) = self.last {
+            if self.clock.duration_between(at, now) >= delay.saturating_add(self.quiet) {
+                self.inner.reset();
+            }
+        }
+
+        let delay = self.inner.next();
+        self.last = Some((now, delay.unwrap_or_default()));
+        delay
+    }
+}
+
+#[cfg(feature = "std")]
+impl<B, C> ResettableBackoff for AutoResetBackoff<B, C>
+where
+    B: ResettableBackoff,
+    C: Clock,
+    Self: Backoff,
+{
+    fn reset(&mut self) {
+        self.inner.reset();
+        self.last = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::*;
+    use crate::BackoffExt;
+    use crate::ConstantBuilder;
+    use crate::DecorrelatedJitterBuilder;
+    use crate::ExponentialBuilder;
+    use crate::FibonacciBuilder;
+
+    fn assert_reset<B: ResettableBackoff>(mut backoff: B) {
+        let mut expected = [Duration::ZERO; 8];
+        for (idx, dur) in (&mut backoff).take(8).enumerate() {
+            expected[idx] = dur;
+        }
+        assert!(backoff.next().is_none());
+
+        backoff.reset();
+        for dur in expected {
+            assert_eq!(backoff.next(), Some(dur));
+        }
+        assert!(backoff.next().is_none());
+    }
+
+    #[test]
+    fn test_reset_builtin_backoffs() {
+        assert_reset(
+            ExponentialBuilder::default()
+                .with_max_times(8)
+                .with_max_delay(Duration::from_secs(10))
+                .build(),
+        );
+        assert_reset(FibonacciBuilder::default().with_max_times(8).build());
+        assert_reset(ConstantBuilder::default().with_max_times(8).build());
+        assert_reset(
+            DecorrelatedJitterBuilder::default()
+                .with_min_delay(Duration::from_secs(1))
+                .with_max_delay(Duration::from_secs(1))
+                .with_max_times(8)
+                .build(),
+        );
+    }
+
+    #[test]
+    fn test_reset_combinators() {
+        assert_reset(
+            ConstantBuilder::default()
+                .with_max_times(2)
+                .then(ExponentialBuilder::default().without_max_times())
+                .cap(Duration::from_secs(5))
+                .max_times(8)
+                .build(),
+        );
+        assert_reset(
+            ExponentialBuilder::default()
+                .without_max_delay()
+                .without_max_times()
+                .max_total_delay(Duration::from_secs(255))
+                .map_delay(|d| d + Duration::from_secs(1))
+                .build(),
+        );
+    }
+
+    #[test]
+    fn test_rebuild_backoff() {
+        assert_reset(RebuildBackoff::new(
+            [Duration::from_secs(1), Duration::from_secs(2)]
+                .into_iter()
+                .cycle()
+                .take(8),
+        ));
+    }
+
+    #[cfg(all(feature = "std", target_has_atomic = "64"))]
+    #[test]
+    fn test_auto_reset_backoff() {
+        static CLOCK: crate::ManualClock = crate::ManualClock::new();
+
+        let mut it = AutoResetBackoff::new(
+            ExponentialBuilder::default()
+                .with_min_delay(Duration::from_secs(1))
+                .with_max_times(3)
+                .build(),
+            Duration::from_secs(10),
+        )
+        .clock(&CLOCK);
+
+        assert_eq!(it.next(), Some(Duration::from_secs(1)));
+        // Failing again before the quiet period keeps going.
+        CLOCK.advance(Duration::from_secs(10));
+        assert_eq!(it.next(), Some(Duration::from_secs(2)));
+        assert_eq!(it.next(), Some(Duration::from_secs(4)));
+        assert_eq!(it.next(), None);
+
+        // The quiet period starts after the delay returned by the last call.
+        CLOCK.advance(Duration::from_secs(10));
+        assert_eq!(it.next(), Some(Duration::from_secs(1)));
+    }
+}
@@ -0,0 +1,348 @@
+use core::ops::RangeInclusive;
+use core::time::Duration;
+
+use super::exponential::saturating_mul;
+use crate::backoff::ConfiguredBackoff;
+use crate::backoff::Jitter;
+
+/// Schedule previews the delays planned by a backoff builder, without sleeping nor randomness.
+///
+/// It's an iterator of [`ScheduleEntry`] returned by the `schedule` method of the built-in
+/// builders and [`BackoffConfig`](crate::BackoffConfig). Every entry reports the range the
+/// delay will fall within once jitter is applied, so the same builder always produces the same
+/// schedule. Without jitter, both ends of the ranges are equal.
+///
+/// The schedule ends when the backoff gives up. It never ends if the builder has neither
+/// `max_times` nor `total_delay`, use [`Iterator::take`] in that case.
+///
+/// With both jitter and `total_delay`, whether the backoff gives up depends on the actual
+/// delays: the schedule keeps entries that can happen, and the cumulative delay never exceeds
+/// `total_delay`.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use backon::ExponentialBuilder;
+///
+/// let builder = ExponentialBuilder::default().with_max_times(3).with_jitter();
+/// let last = builder.schedule().last().unwrap();
+///
+/// assert_eq!(last.attempt, 3);
+/// assert_eq!(last.delay, Duration::from_secs(4)..=Duration::from_secs(8));
+/// // The backoff gives up after sleeping between 7s and 14s in total.
+/// assert_eq!(last.cumulative, Duration::from_secs(7)..=Duration::from_secs(14));
+/// ```
+#[derive(Debug)]
+pub struct Schedule {
+    delays: Delays,
+    total_delay: Option<Duration>,
+
+    attempt: usize,
+    cumulative_min: Duration,
+    cumulative_max: Duration,
+}
+
+/// ScheduleEntry is one of the retries planned by a [`Schedule`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScheduleEntry {
+    /// The number of the retry, starting at 1.
+    pub attempt: usize,
+    /// The range of the delay before this retry.
+    pub delay: RangeInclusive<Duration>,
+    /// The range of the total delay slept until this retry, included.
+    pub cumulative: RangeInclusive<Duration>,
+}
+
+#[derive(Debug)]
+enum Delays {
+    /// Delays computed by a backoff without jitter and total delay, on which jitter is applied.
+    ///
+    /// The additive jitter adds up to `additive_base` if set, rather than up to the delay.
+    Jittered {
+        nominal: ConfiguredBackoff,
+        jitter: Jitter,
+        additive_base: Option<Duration>,
+    },
+    /// Delays of a [`DecorrelatedJitterBackoff`](crate::DecorrelatedJitterBackoff) which are
+    /// random within `[min_delay, previous_max * 3]`.
+    DecorrelatedJitter {
+        min_delay: Duration,
+        max_delay: Option<Duration>,
+        max_times: Option<usize>,
+        upper: Duration,
+    },
+}
+
+impl Schedule {
+    /// Preview a backoff applying `jitter` on the delays of `nominal`.
+    pub(crate) fn jittered(
+        nominal: ConfiguredBackoff,
+        jitter: Jitter,
+        total_delay: Option<Duration>,
+    ) -> Self {
+        Self::jittered_from(nominal, jitter, None, total_delay)
+    }
+
+    /// Preview a backoff applying `jitter` on the delays of `nominal`, with the additive jitter
+    /// adding up to `additive_base` rather than up to the delay.
+    pub(crate) fn jittered_from(
+        nominal: ConfiguredBackoff,
+        jitter: Jitter,
+        additive_base: Option<Duration>,
+        total_delay: Option<Duration>,
+    ) -> Self {
+        Self::new(
+            Delays::Jittered {
+                nominal,
+                jitter,
+                additive_base,
+            },
+            total_delay,
+        )
+    }
+
+    /// Preview a [`DecorrelatedJitterBackoff`](crate::DecorrelatedJitterBackoff).
+    pub(crate) fn decorrelated_jitter(
+        min_delay: Duration,
+        max_delay: Option<Duration>,
+        max_times: Option<usize>,
+        total_delay: Option<Duration>,
+    ) -> Self {
+        Self::new(
+            Delays::DecorrelatedJitter {
+                min_delay,
+                max_delay,
+                max_times,
+                upper: min_delay,
+            },
+            total_delay,
+        )
+    }
+
+    fn new(delays: Delays, total_delay: Option<Duration>) -> Self {
+        Self {
+            delays,
+            total_delay,
+
+            attempt: 0,
+            cumulative_min: Duration::ZERO,
+            cumulative_max: Duration::ZERO,
+        }
+    }
+}
+
+impl Delays {
+    fn next(&mut self, attempt: usize) -> Option<(Duration, Duration)> {
+        match self {
+            Delays::Jittered {
+                nominal,
+                jitter,
+                additive_base,
+            } => nominal
+                .next()
+                .map(|dur| jitter.bounds_from(dur, additive_base.unwrap_or(dur))),
+            Delays::DecorrelatedJitter {
+                min_delay,
+                max_delay,
+                max_times,
+                upper,
+            } => {
+                if attempt >= max_times.unwrap_or(usize::MAX) {
+                    return None;
+                }
+                let max_delay = max_delay.unwrap_or(Duration::MAX);
+                *upper = saturating_mul(*upper, 3.0).min(max_delay);
+                Some(((*min_delay).min(max_delay), *upper))
+            }
+        }
+    }
+}
+
+impl Iterator for Schedule {
+    type Item = ScheduleEntry;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (min, max) = self.delays.next(self.attempt)?;
+        let mut cumulative_min = self.cumulative_min.saturating_add(min);
+        let mut cumulative_max = self.cumulative_max.saturating_add(max);
+        if let Some(total_delay) = self.total_delay {
+            if cumulative_min > total_delay {
+                return None;
+            }
+            cumulative_max = cumulative_max.min(total_delay);
+            cumulative_min = cumulative_min.min(cumulative_max);
+        }
+
+        self.attempt += 1;
+        self.cumulative_min = cumulative_min;
+        self.cumulative_max = cumulative_max;
+        Some(ScheduleEntry {
+            attempt: self.attempt,
+            delay: min..=max,
+            cumulative: cumulative_min..=cumulative_max,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+
+    use super::*;
+    use crate::BackoffBuilder;
+    use crate::BackoffConfig;
+    use crate::ConstantBuilder;
+    use crate::DecorrelatedJitterBuilder;
+    use crate::ExponentialBuilder;
+    use crate::FibonacciBuilder;
+
+    fn secs(secs: u64) -> Duration {
+        Duration::from_secs(secs)
+    }
+
+    fn entry(attempt: usize, delay: (u64, u64), cumulative: (u64, u64)) -> ScheduleEntry {
+        ScheduleEntry {
+            attempt,
+            delay: secs(delay.0)..=secs(delay.1),
+            cumulative: secs(cumulative.0)..=secs(cumulative.1),
+        }
+    }
+
+    #[test]
+    fn test_schedule_exponential() {
+        let mut it = ExponentialBuilder::default().with_max_times(4).schedule();
+
+        assert_eq!(it.next(), Some(entry(1, (1, 1), (1, 1))));
+        assert_eq!(it.next(), Some(entry(2, (2, 2), (3, 3))));
+        assert_eq!(it.next(), Some(entry(3, (4, 4), (7, 7))));
+        assert_eq!(it.next(), Some(entry(4, (8, 8), (15, 15))));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_schedule_matches_backoff() {
+        let builders = [
+            BackoffConfig::from(
+                ExponentialBuilder::default()
+                    .with_max_times(10)
+                    .with_max_delay(secs(20))
+                    .with_total_delay(Some(secs(100))),
+            ),
+            BackoffConfig::from(FibonacciBuilder::default().with_max_times(10)),
+            BackoffConfig::from(ConstantBuilder::default().with_max_times(10)),
+        ];
+        for builder in builders {
+            let mut cumulative = Duration::ZERO;
+            let mut schedule = builder.schedule();
+            for (attempt, dur) in builder.build().enumerate() {
+                cumulative += dur;
+                assert_eq!(
+                    schedule.next(),
+                    Some(ScheduleEntry {
+                        attempt: attempt + 1,
+                        delay: dur..=dur,
+                        cumulative: cumulative..=cumulative,
+                    }),
+                    "{builder}"
+                );
+            }
+            assert_eq!(schedule.next(), None, "{builder}");
+        }
+    }
+
+    #[test]
+    fn test_schedule_bounds_actual_delays() {
+        let builders = [
+            BackoffConfig::from(
+                ExponentialBuilder::default()
+                    .with_max_times(10)
+                    .with_jitter_strategy(Jitter::Symmetric(0.5)),
+            ),
+            BackoffConfig::from(
+                FibonacciBuilder::default()
+                    .with_max_times(10)
+                    .with_jitter_strategy(Jitter::Equal),
+            ),
+            BackoffConfig::from(FibonacciBuilder::default().with_max_times(10).with_jitter()),
+            BackoffConfig::from(
+                ConstantBuilder::default()
+                    .with_max_times(10)
+                    .with_jitter_strategy(Jitter::Full),
+            ),
+            BackoffConfig::from(
+                DecorrelatedJitterBuilder::default()
+                    .with_max_delay(secs(30))
+                    .with_max_times(10),
+            ),
+        ];
+        // Allow some tolerance for the `f32` arithmetic.
+        let tolerance = Duration::from_millis(1);
+        for builder in builders {
+            for _ in 0..100 {
+                let mut cumulative = Duration::ZERO;
+                let mut schedule = builder.schedule();
+                for dur in builder.build() {
+                    cumulative += dur;
+                    let entry = schedule
+                        .next()
+                        .expect("schedule must not end before backoff");
+                    assert!(
+                        *entry.delay.start() <= dur + tolerance,
+                        "{builder}: {entry:?}"
+                    );
+                    assert!(
+                        dur <= *entry.delay.end() + tolerance,
+                        "{builder}: {entry:?}"
+                    );
+                    assert!(
+                        *entry.cumulative.start() <= cumulative + tolerance,
+                        "{builder}: {entry:?}"
+                    );
+                    assert!(
+                        cumulative <= *entry.cumulative.end() + tolerance * 10,
+                        "{builder}: {entry:?}"
+                    );
+                }
+                assert_eq!(schedule.next(), None, "{builder}");
+            }
+        }
+    }
+
+    #[test]
+    fn test_schedule_total_delay_with_jitter() {
+        let mut it = ExponentialBuilder::default()
+            .with_factor(1.0)
+            .with_min_delay(secs(4))
+            .without_max_times()
+            .with_total_delay(Some(secs(10)))
+            .with_jitter_strategy(Jitter::Equal)
+            .schedule();
+
+        assert_eq!(it.next(), Some(entry(1, (2, 4), (2, 4))));
+        assert_eq!(it.next(), Some(entry(2, (2, 4), (4, 8))));
+        // Can only happen if the previous delays were short enough.
+        assert_eq!(it.next(), Some(entry(3, (2, 4), (6, 10))));
+        assert_eq!(it.next(), Some(entry(4, (2, 4), (8, 10))));
+        assert_eq!(it.next(), Some(entry(5, (2, 4), (10, 10))));
+        assert_eq!(it.next(), None);
+    }
+
+    #[test]
+    fn test_schedule_decorrelated_jitter() {
+        let mut it = DecorrelatedJitterBuilder::default()
+            .with_max_delay(secs(20))
+            .with_max_times(4)
+            .schedule();
+
+        assert_eq!(it.next(), Some(entry(1, (1, 3), (1, 3))));
+        assert_eq!(it.next(), Some(entry(2, (1, 9), (2, 12))));
+        assert_eq!(it.next(), Some(entry(3, (1, 20), (3, 32))));
+        assert_eq!(it.next(), Some(entry(4, (1, 20), (4, 52))));
+        assert_eq!(it.next(), None);
+    }
+}
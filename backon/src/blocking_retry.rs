@@ -3,16 +3,41 @@ use core::time::Duration;
 
 use crate::Backoff;
 use crate::BlockingSleeper;
+use crate::Clock;
+#[cfg(feature = "alloc")]
+use crate::CollectErrors;
+use crate::DeadlineExceeded;
 use crate::DefaultBlockingSleeper;
+use crate::DefaultClock;
+use crate::GiveUpReason;
+use crate::RetryAfterHint;
+use crate::RetryDecision;
+use crate::RetryOutcome;
+use crate::RetryState;
+#[cfg(feature = "alloc")]
+use crate::RetryStats;
+use crate::Until;
 use crate::backoff::BackoffBuilder;
 use crate::blocking_sleep::MaybeBlockingSleeper;
+use crate::clock::MaybeClock;
+use crate::clock::maybe_instant;
+use crate::outcome::OutcomeFn;
+use crate::retry_core::AdjustHook;
+use crate::retry_core::Classify;
+use crate::retry_core::NotifyHook;
 use crate::retry_core::RetryConfig;
+use crate::retry_core::RetryObserver;
+use crate::retry_core::WhenHook;
+use crate::retry_core::WithState;
 use crate::retry_core::always_retry;
 use crate::retry_core::identity_adjust;
 use crate::retry_core::noop_notify;
 
 /// BlockingRetryable adds retry support for blocking functions.
 ///
+/// The functions return a `Result<T, E>` or any other [`RetryOutcome`], for example an
+/// `Option<T>` retried on `None`.
+///
 /// For example:
 ///
 /// - Functions without extra args:
@@ -49,7 +74,13 @@ use crate::retry_core::noop_notify;
 ///     Ok(())
 /// }
 /// ```
-pub trait BlockingRetryable<B: BackoffBuilder, T, E, F: FnMut() -> Result<T, E>> {
+pub trait BlockingRetryable<
+    B: BackoffBuilder,
+    T,
+    E,
+    F: OutcomeFn<Outcome: RetryOutcome<Ok = T, Err = E>>,
+>
+{
     /// Generate a new retry.
     fn retry(self, builder: B) -> BlockingRetry<B::Backoff, T, E, F>;
 }
@@ -57,10 +88,11 @@ pub trait BlockingRetryable<B: BackoffBuilder, T, E, F: FnMut() -> Result<T, E>>
 impl<B, T, E, F> BlockingRetryable<B, T, E, F> for F
 where
     B: BackoffBuilder,
-    F: FnMut() -> Result<T, E>,
+    F: OutcomeFn<Outcome: RetryOutcome<Ok = T, Err = E>>,
 {
     fn retry(self, builder: B) -> BlockingRetry<B::Backoff, T, E, F> {
-        BlockingRetry::new(self, builder.build())
+        let max_delay = builder.max_delay();
+        BlockingRetry::new(self, builder.build(), max_delay)
     }
 }
 
@@ -69,44 +101,52 @@ pub struct BlockingRetry<
     B: Backoff,
     T,
     E,
-    F: FnMut() -> Result<T, E>,
+    F: OutcomeFn<Outcome: RetryOutcome<Ok = T, Err = E>>,
     SF: MaybeBlockingSleeper = DefaultBlockingSleeper,
     RF = fn(&E) -> bool,
     NF = fn(&E, Duration),
     AF = fn(&E, Option<Duration>) -> Option<Duration>,
+    CF: MaybeClock = DefaultClock,
 > {
-    config: RetryConfig<B, SF, RF, NF, AF>,
+    config: RetryConfig<B, SF, RF, NF, AF, CF>,
     f: F,
+    deadline_exceeded: Option<fn(DeadlineExceeded) -> E>,
+    hint: Option<fn(&E) -> Option<Duration>>,
 }
 
 impl<B, T, E, F> BlockingRetry<B, T, E, F>
 where
     B: Backoff,
-    F: FnMut() -> Result<T, E>,
+    F: OutcomeFn<Outcome: RetryOutcome<Ok = T, Err = E>>,
 {
     /// Create a new retry.
-    fn new(f: F, backoff: B) -> Self {
+    fn new(f: F, backoff: B, max_delay: Option<Duration>) -> Self {
         BlockingRetry {
             config: RetryConfig::new(
                 backoff,
+                max_delay,
                 DefaultBlockingSleeper::default(),
                 always_retry::<E>,
                 noop_notify::<E>,
                 identity_adjust::<E>,
+                DefaultClock::default(),
             ),
             f,
+            deadline_exceeded: None,
+            hint: None,
         }
     }
 }
 
-impl<B, T, E, F, SF, RF, NF, AF> BlockingRetry<B, T, E, F, SF, RF, NF, AF>
+impl<B, T, E, F, SF, RF, NF, AF, CF> BlockingRetry<B, T, E, F, SF, RF, NF, AF, CF>
 where
     B: Backoff,
-    F: FnMut() -> Result<T, E>,
+    F: OutcomeFn<Outcome: RetryOutcome<Ok = T, Err = E>>,
     SF: MaybeBlockingSleeper,
-    RF: FnMut(&E) -> bool,
-    NF: FnMut(&E, Duration),
-    AF: FnMut(&E, Option<Duration>) -> Option<Duration>,
+    RF: WhenHook<E>,
+    NF: NotifyHook<E>,
+    AF: AdjustHook<E>,
+    CF: MaybeClock,
 {
     /// Set the sleeper for retrying.
     ///
@@ -138,10 +178,12 @@ where
     pub fn sleep<SN: BlockingSleeper>(
         self,
         sleep_fn: SN,
-    ) -> BlockingRetry<B, T, E, F, SN, RF, NF, AF> {
+    ) -> BlockingRetry<B, T, E, F, SN, RF, NF, AF, CF> {
         BlockingRetry {
             config: self.config.with_sleep(sleep_fn),
             f: self.f,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
         }
     }
 
@@ -173,10 +215,68 @@ where
     pub fn when<RN: FnMut(&E) -> bool>(
         self,
         retryable: RN,
-    ) -> BlockingRetry<B, T, E, F, SF, RN, NF, AF> {
+    ) -> BlockingRetry<B, T, E, F, SF, RN, NF, AF, CF> {
         BlockingRetry {
             config: self.config.with_retryable(retryable),
             f: self.f,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Set the conditions for retrying, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`BlockingRetry::when`], and the function also receives the progress of the retry,
+    /// for example to only retry some errors on the first attempts.
+    pub fn when_with_state<RN: FnMut(&E, &RetryState) -> bool>(
+        self,
+        retryable: RN,
+    ) -> BlockingRetry<B, T, E, F, SF, WithState<RN>, NF, AF, CF> {
+        BlockingRetry {
+            config: self.config.timed().with_retryable(WithState(retryable)),
+            f: self.f,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Set how to handle the errors, with a function classifying them into a [`RetryDecision`].
+    ///
+    /// This replaces [`BlockingRetry::when`], and can also override the delay of the backoff without
+    /// matching the error again in [`BlockingRetry::adjust`]. The delay is still passed to `adjust`
+    /// afterwards, and the retry gives up once the backoff is exhausted whatever the decision.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backon::BlockingRetryable;
+    /// use backon::ConstantBuilder;
+    /// use backon::RetryDecision;
+    ///
+    /// let mut attempts = 0;
+    /// let result = (|| {
+    ///     attempts += 1;
+    ///     Err::<(), _>(if attempts < 3 { "conflict" } else { "invalid" })
+    /// })
+    /// .retry(ConstantBuilder::default())
+    /// .classify(|e| match *e {
+    ///     "conflict" => RetryDecision::RetryNow,
+    ///     _ => RetryDecision::Abort,
+    /// })
+    /// .call();
+    ///
+    /// assert_eq!(result, Err("invalid"));
+    /// assert_eq!(attempts, 3);
+    /// ```
+    pub fn classify<RN: FnMut(&E) -> RetryDecision>(
+        self,
+        classify: RN,
+    ) -> BlockingRetry<B, T, E, F, SF, Classify<RN>, NF, AF, CF> {
+        BlockingRetry {
+            config: self.config.with_retryable(Classify(classify)),
+            f: self.f,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
         }
     }
 
@@ -214,38 +314,402 @@ where
     pub fn notify<NN: FnMut(&E, Duration)>(
         self,
         notify: NN,
-    ) -> BlockingRetry<B, T, E, F, SF, RF, NN, AF> {
+    ) -> BlockingRetry<B, T, E, F, SF, RF, NN, AF, CF> {
         BlockingRetry {
             config: self.config.with_notify(notify),
             f: self.f,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Set to notify for all retry attempts, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`BlockingRetry::notify`], and the function also receives the progress of the retry,
+    /// for example to log the attempt number alongside the error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use anyhow::Result;
+    /// use backon::BlockingRetryable;
+    /// use backon::ExponentialBuilder;
+    /// use backon::RetryState;
+    ///
+    /// fn fetch() -> Result<String> {
+    ///     Ok("hello, world!".to_string())
+    /// }
+    ///
+    /// fn main() -> Result<()> {
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .notify_with_state(|err: &anyhow::Error, dur: Duration, state: &RetryState| {
+    ///             println!(
+    ///                 "attempt {} failed with {:?}, retrying after {:?}",
+    ///                 state.attempt, err, dur
+    ///             );
+    ///         })
+    ///         .call()?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn notify_with_state<NN: FnMut(&E, Duration, &RetryState)>(
+        self,
+        notify: NN,
+    ) -> BlockingRetry<B, T, E, F, SF, RF, WithState<NN>, AF, CF> {
+        BlockingRetry {
+            config: self.config.timed().with_notify(WithState(notify)),
+            f: self.f,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
         }
     }
+
+    /// Sets the function to adjust the backoff duration for retry attempts.
+    ///
+    /// This is the blocking counterpart of [`Retry::adjust`](crate::Retry::adjust). The function is
+    /// called with the error and the delay proposed by the backoff before each sleep, and returns the
+    /// delay to sleep instead. Returning `None` gives up and returns the error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use anyhow::Result;
+    /// use backon::BlockingRetryable;
+    /// use backon::ExponentialBuilder;
+    ///
+    /// fn fetch() -> Result<String> {
+    ///     Ok("hello, world!".to_string())
+    /// }
+    ///
+    /// fn main() -> Result<()> {
+    ///     let retry = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .adjust(|err, dur| {
+    ///             if err.to_string() == "rate limited" {
+    ///                 Some(Duration::from_secs(5))
+    ///             } else {
+    ///                 dur
+    ///             }
+    ///         });
+    ///     let content = retry.call()?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn adjust<NAF: FnMut(&E, Option<Duration>) -> Option<Duration>>(
+        self,
+        adjust: NAF,
+    ) -> BlockingRetry<B, T, E, F, SF, RF, NF, NAF, CF> {
+        BlockingRetry {
+            config: self.config.with_adjust(adjust),
+            f: self.f,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Sets the function to adjust the backoff duration, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`BlockingRetry::adjust`], and the function also receives the progress of the retry,
+    /// for example to shorten the delays once the retry took too long.
+    pub fn adjust_with_state<NAF: FnMut(&E, Option<Duration>, &RetryState) -> Option<Duration>>(
+        self,
+        adjust: NAF,
+    ) -> BlockingRetry<B, T, E, F, SF, RF, NF, WithState<NAF>, CF> {
+        BlockingRetry {
+            config: self.config.timed().with_adjust(WithState(adjust)),
+            f: self.f,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Set the clock used to measure the time limits of the retry.
+    ///
+    /// The clock should implement the [`Clock`] trait, for example [`ManualClock`](crate::ManualClock) in tests.
+    ///
+    /// If not specified, we use the [`DefaultClock`].
+    ///
+    /// The clock must be set before [`BlockingRetry::deadline`], which is an instant of the clock: a
+    /// deadline set before the clock is dropped along with the previous clock.
+    pub fn clock<CN: Clock>(self, clock: CN) -> BlockingRetry<B, T, E, F, SF, RF, NF, AF, CN> {
+        BlockingRetry {
+            config: self.config.with_clock(clock),
+            f: self.f,
+            deadline_exceeded: None,
+            hint: self.hint,
+        }
+    }
+
+    /// Set the maximum time the whole retry can take.
+    ///
+    /// The time is measured by the clock from the start of the first attempt, so it includes the
+    /// time spent in the attempts as well as in the sleeps. No retry will be made if the next sleep
+    /// would end after `max_elapsed`, the last error is returned instead.
+    ///
+    /// Unlike `with_total_delay` on the backoff builders, which only sums the sleeps, this limit
+    /// follows the clock.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use anyhow::Result;
+    /// use backon::BlockingRetryable;
+    /// use backon::ExponentialBuilder;
+    ///
+    /// fn fetch() -> Result<String> {
+    ///     Ok("hello, world!".to_string())
+    /// }
+    ///
+    /// fn main() -> Result<()> {
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default().without_max_times())
+    ///         // Give up if the content can't be fetched within 30s.
+    ///         .max_elapsed(Duration::from_secs(30))
+    ///         .call()?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self
+    where
+        CF: Clock,
+    {
+        self.config.max_elapsed = Some(max_elapsed);
+        self.config.timed = true;
+        self
+    }
+
+    /// Set the deadline of the retry.
+    ///
+    /// The deadline is an instant of the clock, so a custom clock must be set with
+    /// [`BlockingRetry::clock`] beforehand. Sleeps are shortened to end at the deadline, and
+    /// no attempt starts after it: the retry returns a [`DeadlineExceeded`] converted into `E`
+    /// instead of the last error, which tells it apart from a retry ended by the backoff.
+    ///
+    /// Use [`BlockingRetry::deadline_with`] if `E` doesn't implement `From<DeadlineExceeded>`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use std::time::Instant;
+    ///
+    /// use anyhow::Result;
+    /// use backon::BlockingRetryable;
+    /// use backon::ExponentialBuilder;
+    ///
+    /// fn fetch() -> Result<String> {
+    ///     Ok("hello, world!".to_string())
+    /// }
+    ///
+    /// fn main() -> Result<()> {
+    ///     // The deadline received from upstream.
+    ///     let deadline = Instant::now() + Duration::from_secs(10);
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .deadline(deadline)
+    ///         .call()?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn deadline(self, deadline: <CF as Clock>::Instant) -> Self
+    where
+        CF: Clock,
+        E: From<DeadlineExceeded>,
+    {
+        self.deadline_with(deadline, E::from)
+    }
+
+    /// Set the deadline of the retry, with a function building the error returned once it's reached.
+    ///
+    /// This is the same as [`BlockingRetry::deadline`], for error types that don't implement
+    /// `From<DeadlineExceeded>`.
+    pub fn deadline_with(
+        mut self,
+        deadline: <CF as Clock>::Instant,
+        map: fn(DeadlineExceeded) -> E,
+    ) -> Self
+    where
+        CF: Clock,
+    {
+        self.config.deadline = Some(maybe_instant::<CF>(deadline));
+        self.deadline_exceeded = Some(map);
+        self
+    }
+
+    /// Wait for the delay hinted by the errors implementing [`RetryAfterHint`] before retrying.
+    ///
+    /// The hint replaces the delay of the backoff, clamped to the maximum delay of the backoff
+    /// builder if it has one, and is passed to [`BlockingRetry::adjust`] like the delay it replaces. Errors
+    /// without a hint are retried after the delay of the backoff, which still limits the number of
+    /// retries. The delays chosen with [`BlockingRetry::classify`] take precedence over the hints.
+    pub fn honor_hints(mut self) -> Self
+    where
+        E: RetryAfterHint,
+    {
+        self.hint = Some(E::retry_after);
+        self
+    }
+
+    /// Collect the errors of all attempts, returning a [`RetryError`](crate::RetryError) once the retry gives up.
+    ///
+    /// The error holds the error and the duration of every attempt, along with the delay
+    /// slept after it. This requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn collect_errors(mut self) -> CollectErrors<Self, E> {
+        self.config.timed = true;
+        CollectErrors::new(self)
+    }
+
+    /// Also retry the values that are not `ready`, for example to poll a job until it's done.
+    ///
+    /// A value for which `ready` returns `false` is retried with the same backoff and time
+    /// limits as an error, but without going through the `when`, `notify` and `adjust` hooks.
+    /// Once the retry gives up, including at the deadline, the last value is returned as an
+    /// [`UntilError::NotReady`](crate::UntilError::NotReady).
+    pub fn until<P: FnMut(&T) -> bool>(self, ready: P) -> Until<Self, P> {
+        Until::new(self, ready)
+    }
 }
 
-impl<B, T, E, F, SF, RF, NF, AF> BlockingRetry<B, T, E, F, SF, RF, NF, AF>
+impl<B, T, E, F, SF, RF, NF, AF, CF> BlockingRetry<B, T, E, F, SF, RF, NF, AF, CF>
 where
     B: Backoff,
-    F: FnMut() -> Result<T, E>,
+    F: OutcomeFn<Outcome: RetryOutcome<Ok = T, Err = E>>,
     SF: BlockingSleeper,
-    RF: FnMut(&E) -> bool,
-    NF: FnMut(&E, Duration),
-    AF: FnMut(&E, Option<Duration>) -> Option<Duration>,
+    RF: WhenHook<E>,
+    NF: NotifyHook<E>,
+    AF: AdjustHook<E>,
+    CF: MaybeClock,
 {
     /// Call the retried function.
     ///
     /// TODO: implement [`FnOnce`] after it stable.
-    pub fn call(mut self) -> Result<T, E> {
+    pub fn call(mut self) -> F::Outcome {
+        RetryOutcome::from_result(self.call_with(&mut ()))
+    }
+
+    /// Call the retried function, returning the reason it gave up along with the error.
+    ///
+    /// The [`GiveUpReason`] tells apart an error that is not retryable from a retry that ran out
+    /// of retries or time.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use backon::BlockingRetryable;
+    /// use backon::ConstantBuilder;
+    /// use backon::GiveUpReason;
+    ///
+    /// fn fetch() -> Result<String, String> {
+    ///     Err("unavailable".to_string())
+    /// }
+    ///
+    /// let (err, reason) = fetch
+    ///     .retry(ConstantBuilder::default().with_max_times(2))
+    ///     .sleep(|_| {})
+    ///     .call_with_reason()
+    ///     .unwrap_err();
+    ///
+    /// assert_eq!(err, "unavailable");
+    /// assert_eq!(reason, GiveUpReason::BackoffExhausted);
+    /// ```
+    pub fn call_with_reason(mut self) -> Result<T, (E, GiveUpReason)> {
+        let mut reason = None;
+        let res = self.call_with(&mut reason);
+        res.map_err(|err| (err, reason.expect("retry must give up with a reason")))
+    }
+
+    /// Call the retried function, returning the [`RetryStats`](crate::RetryStats) along with the result.
+    ///
+    /// This requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn call_with_stats(mut self) -> (Result<T, E>, RetryStats) {
+        self.config.timed = true;
+        let mut stats = RetryStats::default();
+        let res = self.call_with(&mut stats);
+        stats.attempts = self.config.attempts;
+        stats.slept = self.config.slept;
+        stats.elapsed = self.config.elapsed();
+        (res, stats)
+    }
+
+    /// Call the retried function, reporting the failed attempts to `observer`.
+    pub(crate) fn call_with(&mut self, observer: &mut impl RetryObserver<E>) -> Result<T, E> {
+        self.call_until(observer, &mut |_: &T| true)
+    }
+
+    /// Call the retried function, also retrying the values that are not `ready`.
+    ///
+    /// The last value is returned if the retry gives up before one is ready, `observer` is then
+    /// told why it gave up.
+    pub(crate) fn call_until(
+        &mut self,
+        observer: &mut impl RetryObserver<E>,
+        ready: &mut impl FnMut(&T) -> bool,
+    ) -> Result<T, E> {
         loop {
-            let result = (self.f)();
+            if let Some(map) = self.deadline_exceeded {
+                if self.config.deadline_passed() {
+                    observer.gave_up(GiveUpReason::DeadlineExceeded, Duration::ZERO);
+                    return Err(map(DeadlineExceeded));
+                }
+            }
+            self.config.start();
+            let result = self.f.attempt().into_result();
 
             match result {
-                Ok(v) => return Ok(v),
-                Err(err) => match self.config.decide(&err) {
-                    ControlFlow::Continue(dur) => {
-                        self.config.sleep.sleep(dur);
+                Ok(v) if ready(&v) => return Ok(v),
+                Ok(v) => {
+                    let duration = self.config.attempt_duration();
+                    let candidate = self.config.backoff.next();
+                    match self.config.settle(candidate, candidate) {
+                        ControlFlow::Continue(dur) => {
+                            observer.not_ready(duration, dur);
+                            self.config.sleep.sleep(dur);
+                            self.config.record_sleep(dur);
+                            observer.slept(dur);
+                        }
+                        ControlFlow::Break(reason) => {
+                            observer.gave_up(reason, duration);
+                            return Ok(v);
+                        }
+                    }
+                    // Keep the last value rather than an error once the deadline is reached.
+                    if self.config.deadline_passed() {
+                        observer.gave_up(GiveUpReason::DeadlineExceeded, Duration::ZERO);
+                        return Ok(v);
                     }
-                    ControlFlow::Break(_) => return Err(err),
-                },
+                }
+                Err(err) => {
+                    let duration = self.config.attempt_duration();
+                    match self.config.decide(&err, self.hint) {
+                        ControlFlow::Continue(dur) => {
+                            observer.retried(err, duration, dur);
+                            self.config.sleep.sleep(dur);
+                            self.config.record_sleep(dur);
+                            observer.slept(dur);
+                        }
+                        ControlFlow::Break(reason) => {
+                            observer.gave_up(reason, duration);
+                            return Err(err);
+                        }
+                    }
+                }
             }
         }
     }
@@ -262,7 +726,9 @@ mod tests {
     use spin::Mutex;
 
     use super::*;
+    use crate::ConstantBuilder;
     use crate::ExponentialBuilder;
+    use crate::ManualClock;
 
     fn always_error() -> anyhow::Result<()> {
         Err(anyhow::anyhow!("test_query meets error"))
@@ -330,6 +796,365 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_retry_with_adjust() -> anyhow::Result<()> {
+        let error_times = Mutex::new(0);
+
+        let f = || Err::<(), anyhow::Error>(anyhow::anyhow!("retryable"));
+
+        let backoff = ExponentialBuilder::default().with_min_delay(Duration::from_millis(1));
+        let result = f
+            .retry(backoff)
+            // Only retry If error message is `retryable`
+            .when(|e| e.to_string() == "retryable")
+            .sleep(|_| {})
+            .adjust(|_, dur| {
+                let mut x = error_times.lock();
+                *x += 1;
+                dur
+            })
+            .call();
+
+        assert!(result.is_err());
+        assert_eq!("retryable", result.unwrap_err().to_string());
+        // `f` always returns error "retryable", so it should be executed
+        // 4 times (retry 3 times).
+        assert_eq!(*error_times.lock(), 4);
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_with_adjust_stop() -> anyhow::Result<()> {
+        let error_times = Mutex::new(0);
+
+        let f = || {
+            let mut x = error_times.lock();
+            *x += 1;
+            Err::<(), anyhow::Error>(anyhow::anyhow!("retryable"))
+        };
+
+        let backoff = ExponentialBuilder::default().with_min_delay(Duration::from_millis(1));
+        let result = f
+            .retry(backoff)
+            .sleep(|_| {})
+            // Stop retrying as soon as the backoff is consulted.
+            .adjust(|_, _| None)
+            .call();
+
+        assert!(result.is_err());
+        assert_eq!(*error_times.lock(), 1);
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_with_max_elapsed() -> anyhow::Result<()> {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut attempts = 0;
+        let f = || {
+            attempts += 1;
+            // Every attempt takes 5s.
+            CLOCK.advance(Duration::from_secs(5));
+            Err::<(), anyhow::Error>(anyhow::anyhow!("retryable"))
+        };
+
+        let result = f
+            .retry(
+                ConstantBuilder::default()
+                    .with_delay(Duration::from_secs(10))
+                    .without_max_times(),
+            )
+            .sleep(|dur| CLOCK.advance(dur))
+            .clock(&CLOCK)
+            .max_elapsed(Duration::from_secs(40))
+            .call();
+
+        assert!(result.is_err());
+        // The third retry would end at 45s.
+        assert_eq!(attempts, 3);
+        assert_eq!(CLOCK.now(), Duration::from_secs(35));
+        Ok(())
+    }
+
+    #[test]
+    fn test_retry_with_reason() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let f = || Err::<(), &str>("error");
+        let backoff = ConstantBuilder::default().with_max_times(1);
+
+        let reason = |res: Result<(), (&str, GiveUpReason)>| res.unwrap_err().1;
+        assert_eq!(
+            reason(
+                f.retry(backoff)
+                    .sleep(|_| {})
+                    .when(|_| false)
+                    .call_with_reason()
+            ),
+            GiveUpReason::NotRetryable
+        );
+        assert_eq!(
+            reason(f.retry(backoff).sleep(|_| {}).call_with_reason()),
+            GiveUpReason::BackoffExhausted
+        );
+        assert_eq!(
+            reason(
+                f.retry(backoff)
+                    .sleep(|_| {})
+                    .adjust(|_, _| None)
+                    .call_with_reason()
+            ),
+            GiveUpReason::AdjustReturnedNone
+        );
+        assert_eq!(
+            reason(
+                f.retry(backoff)
+                    .sleep(|_| {})
+                    .clock(&CLOCK)
+                    .max_elapsed(Duration::from_millis(500))
+                    .call_with_reason()
+            ),
+            GiveUpReason::MaxElapsedExceeded
+        );
+        assert_eq!(
+            reason(
+                (|| Err::<(), _>(DeadlineExceeded))
+                    .retry(backoff)
+                    .sleep(|dur| CLOCK.advance(dur))
+                    .clock(&CLOCK)
+                    .deadline(CLOCK.now())
+                    .call_with_reason()
+                    .map_err(|(_, reason)| ("error", reason))
+            ),
+            GiveUpReason::DeadlineExceeded
+        );
+    }
+
+    #[test]
+    fn test_retry_with_state() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let f = || {
+            // Every attempt takes 5s.
+            CLOCK.advance(Duration::from_secs(5));
+            Err::<(), &str>("error")
+        };
+
+        let mut checked = vec![];
+        let mut notified = vec![];
+        let result = f
+            .retry(
+                ConstantBuilder::default()
+                    .with_delay(Duration::from_secs(10))
+                    .without_max_times(),
+            )
+            .sleep(|dur| CLOCK.advance(dur))
+            .clock(&CLOCK)
+            .when_with_state(|_, state| {
+                checked.push(state.elapsed);
+                state.attempt < 3
+            })
+            .adjust_with_state(|_, dur, state| dur.map(|dur| dur * state.attempt as u32))
+            .notify_with_state(|_, dur, state| notified.push((dur, *state)))
+            .call();
+
+        assert_eq!(result, Err("error"));
+        assert_eq!(
+            checked,
+            vec![
+                Duration::from_secs(5),
+                Duration::from_secs(20),
+                Duration::from_secs(45)
+            ]
+        );
+        assert_eq!(
+            notified,
+            vec![
+                (
+                    Duration::from_secs(10),
+                    RetryState {
+                        attempt: 1,
+                        elapsed: Duration::from_secs(5),
+                        slept: Duration::ZERO,
+                        last_attempt_duration: Duration::from_secs(5),
+                    }
+                ),
+                (
+                    Duration::from_secs(20),
+                    RetryState {
+                        attempt: 2,
+                        elapsed: Duration::from_secs(20),
+                        slept: Duration::from_secs(10),
+                        last_attempt_duration: Duration::from_secs(5),
+                    }
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retry_with_classify() {
+        let mut attempts = 0;
+        let mut sleeps = vec![];
+        let result = (|| {
+            attempts += 1;
+            match attempts {
+                1 => Err::<(), _>("unavailable"),
+                2 => Err("rate limited"),
+                3 => Err("conflict"),
+                _ => Err("invalid"),
+            }
+        })
+        .retry(ConstantBuilder::default().with_delay(Duration::from_secs(1)))
+        .sleep(|_| {})
+        .classify(|e| match *e {
+            "unavailable" => RetryDecision::Retry,
+            "rate limited" => RetryDecision::RetryAfter(Duration::from_millis(500)),
+            "conflict" => RetryDecision::RetryNow,
+            _ => RetryDecision::Abort,
+        })
+        .notify(|_, dur| sleeps.push(dur))
+        .call();
+
+        assert_eq!(result, Err("invalid"));
+        assert_eq!(attempts, 4);
+        assert_eq!(
+            sleeps,
+            vec![
+                Duration::from_secs(1),
+                Duration::from_millis(500),
+                Duration::ZERO
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retry_with_classify_max_delay() {
+        let mut sleeps = vec![];
+        let result = (|| Err::<(), _>("rate limited"))
+            .retry(
+                ExponentialBuilder::default()
+                    .with_max_delay(Duration::from_secs(3))
+                    .with_max_times(1),
+            )
+            .sleep(|_| {})
+            .classify(|_| RetryDecision::RetryAfter(Duration::from_secs(10)))
+            .notify(|_, dur| sleeps.push(dur))
+            .call();
+
+        assert_eq!(result, Err("rate limited"));
+        // The delay is clamped to the maximum delay of the backoff, like the hints.
+        assert_eq!(sleeps, vec![Duration::from_secs(3)]);
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct HintedError(Option<Duration>);
+
+    impl RetryAfterHint for HintedError {
+        fn retry_after(&self) -> Option<Duration> {
+            self.0
+        }
+    }
+
+    #[test]
+    fn test_retry_with_hints() {
+        let hints = [
+            Some(Duration::from_secs(5)),
+            None,
+            Some(Duration::from_secs(100)),
+        ];
+        let mut attempts = 0;
+        let mut sleeps = vec![];
+        let result = (|| {
+            attempts += 1;
+            Err::<(), _>(HintedError(hints[(attempts - 1) % hints.len()]))
+        })
+        .retry(
+            ExponentialBuilder::default()
+                .with_max_delay(Duration::from_secs(30))
+                .with_max_times(3),
+        )
+        .sleep(|_| {})
+        .honor_hints()
+        .notify(|_, dur| sleeps.push(dur))
+        .call();
+
+        assert_eq!(result, Err(HintedError(Some(Duration::from_secs(5)))));
+        assert_eq!(attempts, 4);
+        // The hints replace the delays of the backoff, clamped to its maximum delay.
+        assert_eq!(
+            sleeps,
+            vec![
+                Duration::from_secs(5),
+                Duration::from_secs(2),
+                Duration::from_secs(30)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_retry_with_deadline() -> anyhow::Result<()> {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut attempts = 0;
+        let mut sleeps = Vec::new();
+        let f = || {
+            attempts += 1;
+            Err::<(), anyhow::Error>(anyhow::anyhow!("retryable"))
+        };
+
+        let result = f
+            .retry(
+                ConstantBuilder::default()
+                    .with_delay(Duration::from_secs(10))
+                    .without_max_times(),
+            )
+            .sleep(|dur| CLOCK.advance(dur))
+            .notify(|_, dur| sleeps.push(dur))
+            .clock(&CLOCK)
+            .deadline(Duration::from_secs(25))
+            .call();
+
+        assert!(result.unwrap_err().is::<DeadlineExceeded>());
+        assert_eq!(attempts, 3);
+        // The last sleep is shortened to end at the deadline.
+        assert_eq!(
+            sleeps,
+            [
+                Duration::from_secs(10),
+                Duration::from_secs(10),
+                Duration::from_secs(5)
+            ]
+        );
+        assert_eq!(CLOCK.now(), Duration::from_secs(25));
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn test_retry_with_deadline_before_clock() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut attempts = 0;
+        let f = || {
+            attempts += 1;
+            Err::<(), anyhow::Error>(anyhow::anyhow!("retryable"))
+        };
+
+        // The deadline of the previous clock is dropped along with it.
+        let result = f
+            .retry(ConstantBuilder::default().with_max_times(2))
+            .sleep(|dur| CLOCK.advance(dur))
+            .deadline(std::time::Instant::now())
+            .clock(&CLOCK)
+            .call_with_reason();
+
+        let (err, reason) = result.unwrap_err();
+        assert!(!err.is::<DeadlineExceeded>());
+        assert_eq!(reason, GiveUpReason::BackoffExhausted);
+        assert_eq!(attempts, 3);
+    }
+
     #[test]
     fn test_fn_mut_when_and_notify() -> anyhow::Result<()> {
         let mut calls_retryable: Vec<()> = vec![];
@@ -3,10 +3,27 @@ use core::time::Duration;
 
 use crate::Backoff;
 use crate::BlockingSleeper;
+use crate::Clock;
+#[cfg(feature = "alloc")]
+use crate::CollectErrors;
+use crate::DeadlineExceeded;
 use crate::DefaultBlockingSleeper;
+use crate::DefaultClock;
+use crate::GiveUpReason;
+use crate::RetryAfterHint;
+use crate::RetryDecision;
+use crate::RetryState;
 use crate::backoff::BackoffBuilder;
 use crate::blocking_sleep::MaybeBlockingSleeper;
+use crate::clock::MaybeClock;
+use crate::clock::maybe_instant;
+use crate::retry_core::AdjustHook;
+use crate::retry_core::Classify;
+use crate::retry_core::NotifyHook;
 use crate::retry_core::RetryConfig;
+use crate::retry_core::RetryObserver;
+use crate::retry_core::WhenHook;
+use crate::retry_core::WithState;
 use crate::retry_core::always_retry;
 use crate::retry_core::identity_adjust;
 use crate::retry_core::noop_notify;
@@ -30,7 +47,8 @@ where
     F: FnMut(Ctx) -> (Ctx, Result<T, E>),
 {
     fn retry(self, builder: B) -> BlockingRetryWithContext<B::Backoff, T, E, Ctx, F> {
-        BlockingRetryWithContext::new(self, builder.build())
+        let max_delay = builder.max_delay();
+        BlockingRetryWithContext::new(self, builder.build(), max_delay)
     }
 }
 
@@ -45,10 +63,13 @@ pub struct BlockingRetryWithContext<
     RF = fn(&E) -> bool,
     NF = fn(&E, Duration),
     AF = fn(&E, Option<Duration>) -> Option<Duration>,
+    CF: MaybeClock = DefaultClock,
 > {
-    config: RetryConfig<B, SF, RF, NF, AF>,
+    config: RetryConfig<B, SF, RF, NF, AF, CF>,
     f: F,
     ctx: Option<Ctx>,
+    deadline_exceeded: Option<fn(DeadlineExceeded) -> E>,
+    hint: Option<fn(&E) -> Option<Duration>>,
 }
 
 impl<B, T, E, Ctx, F> BlockingRetryWithContext<B, T, E, Ctx, F>
@@ -57,29 +78,35 @@ where
     F: FnMut(Ctx) -> (Ctx, Result<T, E>),
 {
     /// Create a new retry.
-    fn new(f: F, backoff: B) -> Self {
+    fn new(f: F, backoff: B, max_delay: Option<Duration>) -> Self {
         BlockingRetryWithContext {
             config: RetryConfig::new(
                 backoff,
+                max_delay,
                 DefaultBlockingSleeper::default(),
                 always_retry::<E>,
                 noop_notify::<E>,
                 identity_adjust::<E>,
+                DefaultClock::default(),
             ),
             f,
             ctx: None,
+            deadline_exceeded: None,
+            hint: None,
         }
     }
 }
 
-impl<B, T, E, Ctx, F, SF, RF, NF, AF> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, AF>
+impl<B, T, E, Ctx, F, SF, RF, NF, AF, CF>
+    BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, AF, CF>
 where
     B: Backoff,
     F: FnMut(Ctx) -> (Ctx, Result<T, E>),
     SF: MaybeBlockingSleeper,
-    RF: FnMut(&E) -> bool,
-    NF: FnMut(&E, Duration),
-    AF: FnMut(&E, Option<Duration>) -> Option<Duration>,
+    RF: WhenHook<E>,
+    NF: NotifyHook<E>,
+    AF: AdjustHook<E>,
+    CF: MaybeClock,
 {
     /// Set the context for retrying.
     ///
@@ -87,11 +114,13 @@ where
     pub fn context(
         self,
         context: Ctx,
-    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, AF> {
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, AF, CF> {
         BlockingRetryWithContext {
             config: self.config,
             f: self.f,
             ctx: Some(context),
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
         }
     }
 
@@ -103,11 +132,13 @@ where
     pub fn sleep<SN: BlockingSleeper>(
         self,
         sleep_fn: SN,
-    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SN, RF, NF, AF> {
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SN, RF, NF, AF, CF> {
         BlockingRetryWithContext {
             config: self.config.with_sleep(sleep_fn),
             f: self.f,
             ctx: self.ctx,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
         }
     }
 
@@ -117,11 +148,48 @@ where
     pub fn when<RN: FnMut(&E) -> bool>(
         self,
         retryable: RN,
-    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RN, NF, AF> {
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RN, NF, AF, CF> {
         BlockingRetryWithContext {
             config: self.config.with_retryable(retryable),
             f: self.f,
             ctx: self.ctx,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Set the conditions for retrying, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`BlockingRetryWithContext::when`], and the function also receives the progress of the retry,
+    /// for example to only retry some errors on the first attempts.
+    pub fn when_with_state<RN: FnMut(&E, &RetryState) -> bool>(
+        self,
+        retryable: RN,
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, WithState<RN>, NF, AF, CF> {
+        BlockingRetryWithContext {
+            config: self.config.timed().with_retryable(WithState(retryable)),
+            f: self.f,
+            ctx: self.ctx,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Set how to handle the errors, with a function classifying them into a [`RetryDecision`].
+    ///
+    /// This replaces [`BlockingRetryWithContext::when`], and can also override the delay of the backoff without
+    /// matching the error again in [`BlockingRetryWithContext::adjust`]. The delay is still passed to `adjust`
+    /// afterwards, and the retry gives up once the backoff is exhausted whatever the decision.
+    pub fn classify<RN: FnMut(&E) -> RetryDecision>(
+        self,
+        classify: RN,
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, Classify<RN>, NF, AF, CF> {
+        BlockingRetryWithContext {
+            config: self.config.with_retryable(Classify(classify)),
+            f: self.f,
+            ctx: self.ctx,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
         }
     }
 
@@ -133,42 +201,271 @@ where
     pub fn notify<NN: FnMut(&E, Duration)>(
         self,
         notify: NN,
-    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NN, AF> {
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NN, AF, CF> {
         BlockingRetryWithContext {
             config: self.config.with_notify(notify),
             f: self.f,
             ctx: self.ctx,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Set to notify for all retry attempts, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`BlockingRetryWithContext::notify`], and the function also receives the progress of the retry,
+    /// for example to log the attempt number alongside the error.
+    pub fn notify_with_state<NN: FnMut(&E, Duration, &RetryState)>(
+        self,
+        notify: NN,
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, WithState<NN>, AF, CF> {
+        BlockingRetryWithContext {
+            config: self.config.timed().with_notify(WithState(notify)),
+            f: self.f,
+            ctx: self.ctx,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Sets the function to adjust the backoff duration for retry attempts.
+    ///
+    /// The function is called with the error and the delay proposed by the backoff, see
+    /// [`BlockingRetry::adjust`](crate::BlockingRetry::adjust). Returning `None` stops the retry
+    /// with the error, while the context is returned along with it as usual.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use anyhow::anyhow;
+    /// use anyhow::Result;
+    /// use backon::BlockingRetryableWithContext;
+    /// use backon::ExponentialBuilder;
+    ///
+    /// struct Test;
+    ///
+    /// impl Test {
+    ///     fn hello(&mut self) -> Result<usize> {
+    ///         Err(anyhow!("rate limited"))
+    ///     }
+    /// }
+    ///
+    /// fn main() -> Result<()> {
+    ///     let (_, result) = {
+    ///         |mut v: Test| {
+    ///             let res = v.hello();
+    ///             (v, res)
+    ///         }
+    ///     }
+    ///     .retry(ExponentialBuilder::default())
+    ///     .context(Test)
+    ///     .adjust(|err, dur| {
+    ///         if err.to_string() == "rate limited" {
+    ///             Some(Duration::from_secs(5))
+    ///         } else {
+    ///             dur
+    ///         }
+    ///     })
+    ///     .call();
+    ///     println!("fetch result: {:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn adjust<NAF: FnMut(&E, Option<Duration>) -> Option<Duration>>(
+        self,
+        adjust: NAF,
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, NAF, CF> {
+        BlockingRetryWithContext {
+            config: self.config.with_adjust(adjust),
+            f: self.f,
+            ctx: self.ctx,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Sets the function to adjust the backoff duration, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`BlockingRetryWithContext::adjust`], and the function also receives the progress of the retry,
+    /// for example to shorten the delays once the retry took too long.
+    pub fn adjust_with_state<NAF: FnMut(&E, Option<Duration>, &RetryState) -> Option<Duration>>(
+        self,
+        adjust: NAF,
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, WithState<NAF>, CF> {
+        BlockingRetryWithContext {
+            config: self.config.timed().with_adjust(WithState(adjust)),
+            f: self.f,
+            ctx: self.ctx,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+        }
+    }
+
+    /// Set the clock used to measure the time limits of the retry.
+    ///
+    /// The clock should implement the [`Clock`] trait, for example [`ManualClock`](crate::ManualClock) in tests.
+    ///
+    /// If not specified, we use the [`DefaultClock`].
+    ///
+    /// The clock must be set before [`BlockingRetryWithContext::deadline`], which is an instant of the clock: a
+    /// deadline set before the clock is dropped along with the previous clock.
+    pub fn clock<CN: Clock>(
+        self,
+        clock: CN,
+    ) -> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, AF, CN> {
+        BlockingRetryWithContext {
+            config: self.config.with_clock(clock),
+            f: self.f,
+            ctx: self.ctx,
+            deadline_exceeded: None,
+            hint: self.hint,
         }
     }
+
+    /// Set the maximum time the whole retry can take.
+    ///
+    /// The time is measured by the clock from the start of the first attempt, so it includes the
+    /// time spent in the attempts as well as in the sleeps. No retry will be made if the next sleep
+    /// would end after `max_elapsed`, the last error is returned instead.
+    ///
+    /// Unlike `with_total_delay` on the backoff builders, which only sums the sleeps, this limit
+    /// follows the clock.
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self
+    where
+        CF: Clock,
+    {
+        self.config.max_elapsed = Some(max_elapsed);
+        self.config.timed = true;
+        self
+    }
+
+    /// Set the deadline of the retry.
+    ///
+    /// The deadline is an instant of the clock, so a custom clock must be set with
+    /// [`BlockingRetryWithContext::clock`] beforehand. Sleeps are shortened to end at the deadline, and
+    /// no attempt starts after it: the retry returns a [`DeadlineExceeded`] converted into `E`
+    /// instead of the last error, which tells it apart from a retry ended by the backoff.
+    ///
+    /// Use [`BlockingRetryWithContext::deadline_with`] if `E` doesn't implement `From<DeadlineExceeded>`.
+    pub fn deadline(self, deadline: <CF as Clock>::Instant) -> Self
+    where
+        CF: Clock,
+        E: From<DeadlineExceeded>,
+    {
+        self.deadline_with(deadline, E::from)
+    }
+
+    /// Set the deadline of the retry, with a function building the error returned once it's reached.
+    ///
+    /// This is the same as [`BlockingRetryWithContext::deadline`], for error types that don't implement
+    /// `From<DeadlineExceeded>`.
+    pub fn deadline_with(
+        mut self,
+        deadline: <CF as Clock>::Instant,
+        map: fn(DeadlineExceeded) -> E,
+    ) -> Self
+    where
+        CF: Clock,
+    {
+        self.config.deadline = Some(maybe_instant::<CF>(deadline));
+        self.deadline_exceeded = Some(map);
+        self
+    }
+
+    /// Wait for the delay hinted by the errors implementing [`RetryAfterHint`] before retrying.
+    ///
+    /// The hint replaces the delay of the backoff, clamped to the maximum delay of the backoff
+    /// builder if it has one, and is passed to [`BlockingRetryWithContext::adjust`] like the delay it replaces. Errors
+    /// without a hint are retried after the delay of the backoff, which still limits the number of
+    /// retries. The delays chosen with [`BlockingRetryWithContext::classify`] take precedence over the hints.
+    pub fn honor_hints(mut self) -> Self
+    where
+        E: RetryAfterHint,
+    {
+        self.hint = Some(E::retry_after);
+        self
+    }
+
+    /// Collect the errors of all attempts, returning a [`RetryError`](crate::RetryError) once the retry gives up.
+    ///
+    /// The error holds the error and the duration of every attempt, along with the delay
+    /// slept after it. This requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn collect_errors(mut self) -> CollectErrors<Self, E> {
+        self.config.timed = true;
+        CollectErrors::new(self)
+    }
 }
 
-impl<B, T, E, Ctx, F, SF, RF, NF, AF> BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, AF>
+impl<B, T, E, Ctx, F, SF, RF, NF, AF, CF>
+    BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, AF, CF>
 where
     B: Backoff,
     F: FnMut(Ctx) -> (Ctx, Result<T, E>),
     SF: BlockingSleeper,
-    RF: FnMut(&E) -> bool,
-    NF: FnMut(&E, Duration),
-    AF: FnMut(&E, Option<Duration>) -> Option<Duration>,
+    RF: WhenHook<E>,
+    NF: NotifyHook<E>,
+    AF: AdjustHook<E>,
+    CF: MaybeClock,
 {
     /// Call the retried function.
     ///
     /// TODO: implement [`FnOnce`] after it stable.
     pub fn call(mut self) -> (Ctx, Result<T, E>) {
+        self.call_with(&mut ())
+    }
+
+    /// Call the retried function, returning the reason it gave up along with the error.
+    ///
+    /// The [`GiveUpReason`] tells apart an error that is not retryable from a retry that ran out
+    /// of retries or time.
+    pub fn call_with_reason(mut self) -> (Ctx, Result<T, (E, GiveUpReason)>) {
+        let mut reason = None;
+        let (ctx, res) = self.call_with(&mut reason);
+        (
+            ctx,
+            res.map_err(|err| (err, reason.expect("retry must give up with a reason"))),
+        )
+    }
+
+    /// Call the retried function, reporting the failed attempts to `observer`.
+    pub(crate) fn call_with(
+        &mut self,
+        observer: &mut impl RetryObserver<E>,
+    ) -> (Ctx, Result<T, E>) {
         let mut ctx = self.ctx.take().expect("context must be valid");
         loop {
+            if let Some(map) = self.deadline_exceeded {
+                if self.config.deadline_passed() {
+                    observer.gave_up(GiveUpReason::DeadlineExceeded, Duration::ZERO);
+                    return (ctx, Err(map(DeadlineExceeded)));
+                }
+            }
+            self.config.start();
             let (xctx, result) = (self.f)(ctx);
             // return ctx ownership back
             ctx = xctx;
 
             match result {
                 Ok(v) => return (ctx, Ok(v)),
-                Err(err) => match self.config.decide(&err) {
-                    ControlFlow::Continue(dur) => {
-                        self.config.sleep.sleep(dur);
+                Err(err) => {
+                    let duration = self.config.attempt_duration();
+                    match self.config.decide(&err, self.hint) {
+                        ControlFlow::Continue(dur) => {
+                            observer.retried(err, duration, dur);
+                            self.config.sleep.sleep(dur);
+                            self.config.record_sleep(dur);
+                            observer.slept(dur);
+                        }
+                        ControlFlow::Break(reason) => {
+                            observer.gave_up(reason, duration);
+                            return (ctx, Err(err));
+                        }
                     }
-                    ControlFlow::Break(_) => return (ctx, Err(err)),
-                },
+                }
             }
         }
     }
@@ -226,4 +523,34 @@ mod tests {
         assert_eq!(*error_times.lock(), 1);
         Ok(())
     }
+
+    #[test]
+    fn test_retry_with_adjust() -> Result<()> {
+        let error_times = Mutex::new(0);
+
+        let backoff = ExponentialBuilder::default()
+            .with_min_delay(Duration::from_secs(60))
+            .with_max_times(2);
+
+        let (_, result) = {
+            |mut v: Test| {
+                let mut x = error_times.lock();
+                *x += 1;
+
+                let res = v.hello();
+                (v, res)
+            }
+        }
+        .retry(backoff)
+        .sleep(|_| {})
+        .context(Test)
+        // Override the long backoff delay so the test finishes quickly.
+        .adjust(|_, dur| dur.map(|_| Duration::from_millis(1)))
+        .call();
+
+        assert!(result.is_err());
+        // 1 attempt + 2 retries.
+        assert_eq!(*error_times.lock(), 3);
+        Ok(())
+    }
 }
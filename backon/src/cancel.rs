@@ -0,0 +1,52 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::Context;
+use core::task::Poll;
+use core::task::ready;
+
+/// Cancelled is the error of a retry cancelled by [`Retry::cancel_on`][crate::Retry::cancel_on].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Cancelled;
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "retry cancelled")
+    }
+}
+
+impl core::error::Error for Cancelled {}
+
+/// The future cancelling a retry and how to turn the cancellation into an error.
+pub(crate) struct CancelOn<F, E> {
+    fut: F,
+    map: fn(Cancelled) -> E,
+}
+
+impl<F: Future<Output = ()>, E> CancelOn<F, E> {
+    pub(crate) fn new(fut: F, map: fn(Cancelled) -> E) -> Self {
+        Self { fut, map }
+    }
+}
+
+/// Poll the cancellation of a retry, returning the error once it resolved.
+///
+/// A retry without cancellation is never cancelled.
+///
+/// # Safety
+///
+/// The cancellation must be pinned, it must not be moved until it's dropped.
+pub(crate) unsafe fn poll_cancel<F: Future<Output = ()>, E>(
+    cancel: &mut Option<CancelOn<F, E>>,
+    cx: &mut Context<'_>,
+) -> Poll<E> {
+    let Some(cancel) = cancel else {
+        return Poll::Pending;
+    };
+    // Safety: The caller guarantees the cancellation is pinned.
+    let fut = unsafe { Pin::new_unchecked(&mut cancel.fut) };
+
+    ready!(fut.poll(cx));
+    Poll::Ready((cancel.map)(Cancelled))
+}
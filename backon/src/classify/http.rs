@@ -0,0 +1,318 @@
+//! Classifiers of the HTTP responses, and parsing of their `Retry-After` header.
+//!
+//! The responses with the following status codes are retried:
+//!
+//! - `429 Too Many Requests`
+//! - `502 Bad Gateway`, `503 Service Unavailable` and `504 Gateway Timeout`
+//!
+//! The other status codes, including `501 Not Implemented` and the other server errors, are
+//! fatal since retrying the same request would fail again.
+//!
+//! The `Retry-After` header sent along with them is parsed by [`retry_after`], and can be
+//! waited for with the [`adjust_retry_after`] hook, or by implementing
+//! [`RetryAfterHint`](crate::RetryAfterHint) for the errors.
+//!
+//! This module requires the `http` feature.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use core::time::Duration;
+//! use std::time::SystemTime;
+//!
+//! use anyhow::Result;
+//! use backon::ExponentialBuilder;
+//! use backon::Retryable;
+//! use backon::classify;
+//! use http::HeaderMap;
+//! use http::StatusCode;
+//!
+//! #[derive(Debug)]
+//! struct HttpError {
+//!     status: StatusCode,
+//!     headers: HeaderMap,
+//! }
+//!
+//! async fn fetch() -> Result<String, HttpError> {
+//!     Err(HttpError {
+//!         status: StatusCode::SERVICE_UNAVAILABLE,
+//!         headers: HeaderMap::new(),
+//!     })
+//! }
+//!
+//! #[tokio::main(flavor = "current_thread")]
+//! async fn main() -> Result<()> {
+//!     let content = fetch
+//!         .retry(ExponentialBuilder::default())
+//!         .when(|e| classify::http::is_retryable(e.status))
+//!         // Wait for the Retry-After headers, up to a minute.
+//!         .adjust(classify::http::adjust_retry_after(
+//!             |e: &HttpError| Some(&e.headers),
+//!             Duration::from_secs(60),
+//!             SystemTime::now,
+//!         ))
+//!         .await
+//!         .map_err(|e| anyhow::anyhow!("fetch failed with {}", e.status))?;
+//!     println!("fetch succeeded: {}", content);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use core::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use http::HeaderMap;
+use http::HeaderValue;
+use http::StatusCode;
+use http::header::RETRY_AFTER;
+
+use crate::RetryDecision;
+
+/// Decide how to retry the response with the given status, see the [module docs](self) for
+/// the status codes retried.
+///
+/// The successful responses are not errors, and are not retried either.
+pub fn decide(status: StatusCode) -> RetryDecision {
+    match status {
+        StatusCode::TOO_MANY_REQUESTS
+        | StatusCode::BAD_GATEWAY
+        | StatusCode::SERVICE_UNAVAILABLE
+        | StatusCode::GATEWAY_TIMEOUT => RetryDecision::Retry,
+        _ => RetryDecision::Abort,
+    }
+}
+
+/// Check if the response with the given status is retryable, see the [module docs](self) for
+/// the status codes retried.
+pub fn is_retryable(status: StatusCode) -> bool {
+    decide(status) != RetryDecision::Abort
+}
+
+/// Return how long to wait before retrying, as told by the `Retry-After` header.
+///
+/// The header is parsed against the current system time, see [`parse_retry_after`]. The delay
+/// is not capped, which is left to the retry, for example with the maximum delay of the backoff
+/// applied to the [`RetryAfterHint`](crate::RetryAfterHint) of the errors.
+pub fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    parse_retry_after(headers.get(RETRY_AFTER)?, SystemTime::now())
+}
+
+/// Parse the value of a `Retry-After` header, into the delay from `now`.
+///
+/// Both forms of the header are supported: the delay in seconds like `120`, and the date
+/// in the IMF-fixdate format like `Wed, 21 Oct 2015 07:28:00 GMT`. A date in the past is no
+/// delay at all. The obsolete date formats are not supported, and return `None` like the
+/// invalid values.
+///
+/// # Examples
+///
+/// ```
+/// use std::time::Duration;
+/// use std::time::UNIX_EPOCH;
+///
+/// use backon::classify::http::parse_retry_after;
+/// use http::HeaderValue;
+///
+/// let now = UNIX_EPOCH + Duration::from_secs(1445412400);
+///
+/// assert_eq!(
+///     parse_retry_after(&HeaderValue::from_static("120"), now),
+///     Some(Duration::from_secs(120))
+/// );
+/// assert_eq!(
+///     parse_retry_after(&HeaderValue::from_static("Wed, 21 Oct 2015 07:28:00 GMT"), now),
+///     Some(Duration::from_secs(80))
+/// );
+/// assert_eq!(parse_retry_after(&HeaderValue::from_static("soon"), now), None);
+/// ```
+pub fn parse_retry_after(value: &HeaderValue, now: SystemTime) -> Option<Duration> {
+    let value = value.to_str().ok()?.trim();
+    if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit()) {
+        // Saturate the delays too large to be parsed, they are out of reach anyway.
+        return Some(Duration::from_secs(value.parse().unwrap_or(u64::MAX)));
+    }
+
+    let date = UNIX_EPOCH + Duration::from_secs(parse_imf_fixdate(value)?);
+    Some(date.duration_since(now).unwrap_or(Duration::ZERO))
+}
+
+/// Adjust the delays to the `Retry-After` header of the errors, read with `headers`.
+///
+/// The header is parsed against the time returned by `now`, usually [`SystemTime::now`], and
+/// the delay it asks for is capped at `max_delay` so that a server can't hold the retry for
+/// too long. The errors without the header, or with an invalid one, keep the delay of the
+/// backoff. Once the backoff is exhausted, the retry still gives up whatever the header says.
+pub fn adjust_retry_after<E>(
+    headers: impl Fn(&E) -> Option<&HeaderMap>,
+    max_delay: Duration,
+    now: impl Fn() -> SystemTime,
+) -> impl FnMut(&E, Option<Duration>) -> Option<Duration> {
+    move |err, dur| {
+        let dur = dur?;
+        let retry_after = headers(err)
+            .and_then(|headers| headers.get(RETRY_AFTER))
+            .and_then(|value| parse_retry_after(value, now()));
+        Some(retry_after.map_or(dur, |retry_after| retry_after.min(max_delay)))
+    }
+}
+
+/// Parse a date like `Sun, 06 Nov 1994 08:49:37 GMT` into the seconds since the UNIX epoch.
+fn parse_imf_fixdate(value: &str) -> Option<u64> {
+    let value = value.as_bytes();
+    if value.len() != 29
+        || &value[3..5] != b", "
+        || value[7] != b' '
+        || value[11] != b' '
+        || value[16] != b' '
+        || value[19] != b':'
+        || value[22] != b':'
+        || &value[25..] != b" GMT"
+    {
+        return None;
+    }
+
+    let number = |digits: &[u8]| -> Option<u64> {
+        digits.iter().try_fold(0, |n, b| {
+            b.is_ascii_digit().then(|| n * 10 + u64::from(b - b'0'))
+        })
+    };
+    let day = number(&value[5..7])?;
+    let month = match &value[8..11] {
+        b"Jan" => 1,
+        b"Feb" => 2,
+        b"Mar" => 3,
+        b"Apr" => 4,
+        b"May" => 5,
+        b"Jun" => 6,
+        b"Jul" => 7,
+        b"Aug" => 8,
+        b"Sep" => 9,
+        b"Oct" => 10,
+        b"Nov" => 11,
+        b"Dec" => 12,
+        _ => return None,
+    };
+    let year = number(&value[12..16])?;
+    let (hour, minute, second) = (
+        number(&value[17..19])?,
+        number(&value[20..22])?,
+        number(&value[23..25])?,
+    );
+    if year < 1970 || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+
+    Some(days_since_epoch(year, month, day) * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// Count the days from 1970-01-01 to the given date of the proleptic Gregorian calendar.
+fn days_since_epoch(year: u64, month: u64, day: u64) -> u64 {
+    // Start the years in March, so that the leap day is the last day of the year.
+    let year = if month <= 2 { year - 1 } else { year };
+    let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+    let days = year * 365 + year / 4 - year / 100 + year / 400 + day_of_year;
+    // The days from 0000-03-01 to 1970-01-01.
+    days - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use super::*;
+    use crate::BlockingRetryable;
+    use crate::ConstantBuilder;
+
+    #[test]
+    fn test_decide() {
+        for status in [429, 502, 503, 504] {
+            let status = StatusCode::from_u16(status).unwrap();
+            assert_eq!(decide(status), RetryDecision::Retry, "{status}");
+        }
+        for status in [200, 400, 404, 500, 501, 505] {
+            let status = StatusCode::from_u16(status).unwrap();
+            assert!(!is_retryable(status), "{status}");
+        }
+    }
+
+    #[test]
+    fn test_parse_imf_fixdate() {
+        assert_eq!(parse_imf_fixdate("Thu, 01 Jan 1970 00:00:00 GMT"), Some(0));
+        assert_eq!(
+            parse_imf_fixdate("Sun, 06 Nov 1994 08:49:37 GMT"),
+            Some(784111777)
+        );
+        assert_eq!(
+            parse_imf_fixdate("Thu, 29 Feb 2024 12:00:00 GMT"),
+            Some(1709208000)
+        );
+        assert_eq!(parse_imf_fixdate("Sun, 06 Nov 1994 08:49:37 UTC"), None);
+        assert_eq!(parse_imf_fixdate("Sun, 06 Foo 1994 08:49:37 GMT"), None);
+        assert_eq!(parse_imf_fixdate("Sun, 06 Nov 1994 24:49:37 GMT"), None);
+        // The obsolete RFC 850 format.
+        assert_eq!(parse_imf_fixdate("Sunday, 06-Nov-94 08:49:37 GMT"), None);
+    }
+
+    #[test]
+    fn test_parse_retry_after() {
+        let now = UNIX_EPOCH + Duration::from_secs(784111777);
+        let parse = |value| parse_retry_after(&HeaderValue::from_static(value), now);
+
+        assert_eq!(parse(" 5 "), Some(Duration::from_secs(5)));
+        assert_eq!(
+            parse("99999999999999999999999"),
+            Some(Duration::from_secs(u64::MAX))
+        );
+        assert_eq!(parse("-5"), None);
+        assert_eq!(parse("1.5"), None);
+        assert_eq!(
+            parse("Sun, 06 Nov 1994 08:50:00 GMT"),
+            Some(Duration::from_secs(23))
+        );
+        assert_eq!(parse("Sun, 06 Nov 1994 08:00:00 GMT"), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_adjust_retry_after() {
+        let now = UNIX_EPOCH + Duration::from_secs(784111777);
+        let mut sleeps = Vec::new();
+        let mut attempts = 0;
+        let result = (|| {
+            attempts += 1;
+            let mut headers = HeaderMap::new();
+            let value = match attempts {
+                1 => "7",
+                2 => "Sun, 06 Nov 1994 08:50:00 GMT",
+                3 => "99999999999999999999999",
+                _ => "soon",
+            };
+            headers.insert(RETRY_AFTER, HeaderValue::from_static(value));
+            Err::<(), _>(headers)
+        })
+        .retry(ConstantBuilder::default().with_max_times(4))
+        .sleep(|_| {})
+        .adjust(adjust_retry_after(
+            |headers: &HeaderMap| Some(headers),
+            Duration::from_secs(60),
+            || now,
+        ))
+        .notify(|_, dur| sleeps.push(dur))
+        .call();
+
+        assert!(result.is_err());
+        assert_eq!(
+            sleeps,
+            [
+                Duration::from_secs(7),
+                // The date is parsed against the given time.
+                Duration::from_secs(23),
+                // The delays are capped at the max delay.
+                Duration::from_secs(60),
+                // The invalid values keep the delay of the backoff.
+                Duration::from_secs(1),
+            ]
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Classifiers of [`std::io::Error`], for the file and socket code.
+//!
+//! The errors of the following kinds are retried:
+//!
+//! - [`ErrorKind::Interrupted`], immediately since the operation was interrupted by a signal.
+//! - [`ErrorKind::WouldBlock`] and [`ErrorKind::TimedOut`].
+//! - [`ErrorKind::ConnectionReset`], [`ErrorKind::ConnectionAborted`] and
+//!   [`ErrorKind::ConnectionRefused`].
+//!
+//! The other errors, including [`ErrorKind::PermissionDenied`] and [`ErrorKind::NotFound`],
+//! are fatal. Use an [`IoClassifier`] to change the decision for some kinds.
+//!
+//! # Examples
+//!
+//! ```no_run
+//! use std::fs;
+//! use std::io;
+//!
+//! use backon::BlockingRetryable;
+//! use backon::ExponentialBuilder;
+//! use backon::classify;
+//!
+//! fn main() -> io::Result<()> {
+//!     let content = (|| fs::read_to_string("/var/run/app.pid"))
+//!         .retry(ExponentialBuilder::default())
+//!         .classify(classify::io::decide)
+//!         .call()?;
+//!     println!("pid: {content}");
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use alloc::vec::Vec;
+use std::io;
+use std::io::ErrorKind;
+
+use crate::RetryDecision;
+
+/// The decision for the kinds classified by default, `None` for the other kinds.
+fn builtin(kind: ErrorKind) -> Option<RetryDecision> {
+    match kind {
+        ErrorKind::Interrupted => Some(RetryDecision::RetryNow),
+        ErrorKind::WouldBlock
+        | ErrorKind::TimedOut
+        | ErrorKind::ConnectionReset
+        | ErrorKind::ConnectionAborted
+        | ErrorKind::ConnectionRefused => Some(RetryDecision::Retry),
+        ErrorKind::PermissionDenied | ErrorKind::NotFound => Some(RetryDecision::Abort),
+        _ => None,
+    }
+}
+
+/// Decide how to retry the error, see the [module docs](self) for the kinds retried.
+///
+/// This can be passed to [`Retry::classify`](crate::Retry::classify) as is.
+pub fn decide(err: &io::Error) -> RetryDecision {
+    builtin(err.kind()).unwrap_or(RetryDecision::Abort)
+}
+
+/// Check if the error is retryable, see the [module docs](self) for the kinds retried.
+///
+/// This can be passed to [`Retry::when`](crate::Retry::when) as is, or to
+/// [`when::report::source_is`](crate::when::report::source_is) for the I/O errors wrapped in
+/// an `anyhow::Error`.
+pub fn is_retryable(err: &io::Error) -> bool {
+    decide(err) != RetryDecision::Abort
+}
+
+/// IoClassifier decides how to retry the I/O errors, with a custom decision for some kinds.
+///
+/// The kinds without a custom decision are classified like [`decide`], and the kinds it
+/// doesn't know about get the fallback decision, [`RetryDecision::Abort`] by default.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+/// use std::io::ErrorKind;
+///
+/// use backon::BlockingRetryable;
+/// use backon::ConstantBuilder;
+/// use backon::RetryDecision;
+/// use backon::classify::io::IoClassifier;
+///
+/// let classifier = IoClassifier::new()
+///     // The file is created by another process.
+///     .with_retryable(ErrorKind::NotFound)
+///     .with_fatal(ErrorKind::ConnectionRefused);
+///
+/// assert_eq!(
+///     classifier.decide(&ErrorKind::NotFound.into()),
+///     RetryDecision::Retry
+/// );
+///
+/// let result = (|| Err::<(), io::Error>(ErrorKind::ConnectionRefused.into()))
+///     .retry(ConstantBuilder::default())
+///     .sleep(|_| {})
+///     .classify(|e| classifier.decide(e))
+///     .call();
+///
+/// assert_eq!(result.unwrap_err().kind(), ErrorKind::ConnectionRefused);
+/// ```
+#[derive(Debug, Clone)]
+pub struct IoClassifier {
+    decisions: Vec<(ErrorKind, RetryDecision)>,
+    fallback: RetryDecision,
+}
+
+impl Default for IoClassifier {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl IoClassifier {
+    /// Create a classifier with the default decisions.
+    pub const fn new() -> Self {
+        IoClassifier {
+            decisions: Vec::new(),
+            fallback: RetryDecision::Abort,
+        }
+    }
+
+    /// Set the decision for the errors of the given kind.
+    pub fn with_decision(mut self, kind: ErrorKind, decision: RetryDecision) -> Self {
+        match self.decisions.iter_mut().find(|(k, _)| *k == kind) {
+            Some((_, d)) => *d = decision,
+            None => self.decisions.push((kind, decision)),
+        }
+        self
+    }
+
+    /// Retry the errors of the given kind.
+    pub fn with_retryable(self, kind: ErrorKind) -> Self {
+        self.with_decision(kind, RetryDecision::Retry)
+    }
+
+    /// Never retry the errors of the given kind.
+    pub fn with_fatal(self, kind: ErrorKind) -> Self {
+        self.with_decision(kind, RetryDecision::Abort)
+    }
+
+    /// Set the decision for the kinds neither set nor classified by default.
+    pub fn with_fallback(mut self, decision: RetryDecision) -> Self {
+        self.fallback = decision;
+        self
+    }
+
+    /// Decide how to retry the error.
+    pub fn decide(&self, err: &io::Error) -> RetryDecision {
+        let kind = err.kind();
+        self.decisions
+            .iter()
+            .find(|(k, _)| *k == kind)
+            .map(|(_, decision)| *decision)
+            .or_else(|| builtin(kind))
+            .unwrap_or(self.fallback)
+    }
+
+    /// Check if the error is retryable.
+    pub fn is_retryable(&self, err: &io::Error) -> bool {
+        self.decide(err) != RetryDecision::Abort
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::time::Duration;
+
+    use super::*;
+    use crate::BlockingRetryable;
+    use crate::ConstantBuilder;
+
+    #[test]
+    fn test_decide() {
+        for kind in [
+            ErrorKind::WouldBlock,
+            ErrorKind::TimedOut,
+            ErrorKind::ConnectionReset,
+            ErrorKind::ConnectionAborted,
+            ErrorKind::ConnectionRefused,
+        ] {
+            assert_eq!(decide(&kind.into()), RetryDecision::Retry, "{kind:?}");
+        }
+        assert_eq!(
+            decide(&ErrorKind::Interrupted.into()),
+            RetryDecision::RetryNow
+        );
+        for kind in [
+            ErrorKind::PermissionDenied,
+            ErrorKind::NotFound,
+            ErrorKind::InvalidData,
+            ErrorKind::Other,
+        ] {
+            assert!(!is_retryable(&kind.into()), "{kind:?}");
+        }
+    }
+
+    #[test]
+    fn test_classifier() {
+        let classifier = IoClassifier::new()
+            .with_retryable(ErrorKind::NotFound)
+            .with_fatal(ErrorKind::TimedOut)
+            .with_fatal(ErrorKind::NotFound)
+            .with_fallback(RetryDecision::Retry);
+
+        assert!(!classifier.is_retryable(&ErrorKind::NotFound.into()));
+        assert!(!classifier.is_retryable(&ErrorKind::TimedOut.into()));
+        assert!(!classifier.is_retryable(&ErrorKind::PermissionDenied.into()));
+        assert!(classifier.is_retryable(&ErrorKind::ConnectionReset.into()));
+        assert!(classifier.is_retryable(&ErrorKind::InvalidData.into()));
+    }
+
+    #[test]
+    fn test_retry_io_errors() {
+        let mut kinds = [
+            ErrorKind::Interrupted,
+            ErrorKind::TimedOut,
+            ErrorKind::PermissionDenied,
+        ]
+        .into_iter();
+        let mut sleeps = Vec::new();
+        let result = (|| Err::<(), io::Error>(kinds.next().unwrap().into()))
+            .retry(ConstantBuilder::default())
+            .sleep(|_| {})
+            .classify(decide)
+            .notify(|_, dur| sleeps.push(dur))
+            .call();
+
+        assert_eq!(result.unwrap_err().kind(), ErrorKind::PermissionDenied);
+        assert_eq!(sleeps, [Duration::ZERO, Duration::from_secs(1)]);
+    }
+}
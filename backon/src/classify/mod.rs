@@ -0,0 +1,8 @@
+//! Ready-made classifiers of the common errors, for [`Retry::when`](crate::Retry::when) and
+//! [`Retry::classify`](crate::Retry::classify).
+
+#[cfg(feature = "std")]
+pub mod io;
+
+#[cfg(feature = "http")]
+pub mod http;
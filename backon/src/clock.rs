@@ -0,0 +1,197 @@
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::AtomicU64;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+
+/// A clock is used to measure the time spent by a retry, see [`Retry::max_elapsed`][crate::Retry::max_elapsed].
+pub trait Clock: 'static {
+    /// A point in time returned by the clock.
+    type Instant: Copy + Ord;
+
+    /// Return the current instant.
+    fn now(&self) -> Self::Instant;
+
+    /// Return the duration elapsed from `earlier` to `later`, or zero if `later` is before `earlier`.
+    fn duration_between(&self, earlier: Self::Instant, later: Self::Instant) -> Duration;
+}
+
+/// A stub trait allowing non-[`Clock`] types to be used as a generic parameter in [`Retry`][crate::Retry].
+/// It does not provide actual functionality.
+#[doc(hidden)]
+pub trait MaybeClock: 'static {
+    type Instant: Copy;
+
+    fn now(&self) -> Self::Instant;
+
+    fn duration_between(&self, earlier: Self::Instant, later: Self::Instant) -> Duration;
+}
+
+/// All `Clock` will implement `MaybeClock`, but not vice versa.
+impl<T: Clock> MaybeClock for T {
+    type Instant = <T as Clock>::Instant;
+
+    fn now(&self) -> Self::Instant {
+        Clock::now(self)
+    }
+
+    fn duration_between(&self, earlier: Self::Instant, later: Self::Instant) -> Duration {
+        Clock::duration_between(self, earlier, later)
+    }
+}
+
+/// Convert an instant of a `Clock` into the instant of its `MaybeClock` implementation.
+///
+/// Both are the same type, but this can't be proven where the clock is only bound by `MaybeClock`.
+pub(crate) fn maybe_instant<C: Clock>(
+    instant: <C as Clock>::Instant,
+) -> <C as MaybeClock>::Instant {
+    instant
+}
+
+/// All `&'static Clock` implements `Clock`, which allows to share a clock like [`ManualClock`].
+impl<T: Clock> Clock for &'static T {
+    type Instant = T::Instant;
+
+    fn now(&self) -> Self::Instant {
+        (**self).now()
+    }
+
+    fn duration_between(&self, earlier: Self::Instant, later: Self::Instant) -> Duration {
+        (**self).duration_between(earlier, later)
+    }
+}
+
+/// The default implementation of `Clock` when no features are enabled.
+///
+/// It will fail to compile if [`Retry::max_elapsed`][crate::Retry::max_elapsed] is called without
+/// calling [`Retry::clock`][crate::Retry::clock] to provide a valid clock.
+#[cfg(not(feature = "std"))]
+pub type DefaultClock = PleaseEnableAFeatureOrProvideACustomClock;
+/// The default implementation of `Clock` while feature `std` enabled.
+///
+/// It uses [`std::time::Instant`].
+#[cfg(feature = "std")]
+pub type DefaultClock = StdClock;
+
+/// A placeholder type that does not implement [`Clock`] and will therefore fail to compile if used as one.
+///
+/// Users should enable the `std` feature of this crate when this type appears in compilation errors.
+/// Alternatively, a custom [`Clock`] implementation should be provided with [`crate::Retry::clock`].
+#[doc(hidden)]
+#[allow(dead_code)]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct PleaseEnableAFeatureOrProvideACustomClock;
+
+/// Implement `MaybeClock` but not `Clock`.
+///
+/// The time limits of the retries can only be set with a real [`Clock`], so this is never called.
+impl MaybeClock for PleaseEnableAFeatureOrProvideACustomClock {
+    type Instant = ();
+
+    fn now(&self) -> Self::Instant {}
+
+    fn duration_between(&self, _: Self::Instant, _: Self::Instant) -> Duration {
+        Duration::ZERO
+    }
+}
+
+/// The implementation of `Clock` that uses [`std::time::Instant`].
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdClock;
+
+#[cfg(feature = "std")]
+impl Clock for StdClock {
+    type Instant = std::time::Instant;
+
+    fn now(&self) -> Self::Instant {
+        std::time::Instant::now()
+    }
+
+    fn duration_between(&self, earlier: Self::Instant, later: Self::Instant) -> Duration {
+        later.saturating_duration_since(earlier)
+    }
+}
+
+/// A clock that only moves forward when told to, for deterministic tests.
+///
+/// Its instants are the durations elapsed since the clock was created. The clock is usually
+/// stored in a `static` and passed by reference, so that a test can advance it, for example
+/// from its sleeper.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use anyhow::Result;
+/// use backon::ConstantBuilder;
+/// use backon::ManualClock;
+/// use backon::Retryable;
+///
+/// static CLOCK: ManualClock = ManualClock::new();
+///
+/// async fn fetch() -> Result<String> {
+///     Err(anyhow::anyhow!("unavailable"))
+/// }
+///
+/// #[tokio::main(flavor = "current_thread")]
+/// async fn main() {
+///     let result = fetch
+///         .retry(ConstantBuilder::default().with_delay(Duration::from_secs(10)))
+///         .sleep(|dur| {
+///             CLOCK.advance(dur);
+///             std::future::ready(())
+///         })
+///         .clock(&CLOCK)
+///         .max_elapsed(Duration::from_secs(25))
+///         .await;
+///
+///     assert!(result.is_err());
+///     assert_eq!(CLOCK.now(), Duration::from_secs(20));
+/// }
+/// ```
+#[cfg(target_has_atomic = "64")]
+#[derive(Debug, Default)]
+pub struct ManualClock {
+    nanos: AtomicU64,
+}
+
+#[cfg(target_has_atomic = "64")]
+impl ManualClock {
+    /// Create a new `ManualClock` at instant zero.
+    pub const fn new() -> Self {
+        Self {
+            nanos: AtomicU64::new(0),
+        }
+    }
+
+    /// Move the clock forward by `dur`.
+    pub fn advance(&self, dur: Duration) {
+        let nanos = u64::try_from(dur.as_nanos()).unwrap_or(u64::MAX);
+        let _ = self
+            .nanos
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |v| {
+                Some(v.saturating_add(nanos))
+            });
+    }
+
+    /// Return the current instant of the clock.
+    pub fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+#[cfg(target_has_atomic = "64")]
+impl Clock for ManualClock {
+    type Instant = Duration;
+
+    fn now(&self) -> Self::Instant {
+        ManualClock::now(self)
+    }
+
+    fn duration_between(&self, earlier: Self::Instant, later: Self::Instant) -> Duration {
+        later.saturating_sub(earlier)
+    }
+}
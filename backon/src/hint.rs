@@ -0,0 +1,51 @@
+use core::time::Duration;
+
+/// RetryAfterHint is implemented by the errors that know how long to wait before retrying.
+///
+/// Servers often tell their clients when to come back, for example with the `Retry-After`
+/// header of an HTTP response. Libraries can ship this hint along with their errors, and the
+/// retries configured with `honor_hints`, for example [`Retry::honor_hints`](crate::Retry::honor_hints),
+/// wait for it instead of the delay computed by the backoff.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use backon::BlockingRetryable;
+/// use backon::ExponentialBuilder;
+/// use backon::RetryAfterHint;
+///
+/// #[derive(Debug)]
+/// enum Error {
+///     Unavailable,
+///     RateLimited { retry_after: Duration },
+/// }
+///
+/// impl RetryAfterHint for Error {
+///     fn retry_after(&self) -> Option<Duration> {
+///         match self {
+///             Error::Unavailable => None,
+///             Error::RateLimited { retry_after } => Some(*retry_after),
+///         }
+///     }
+/// }
+///
+/// fn fetch() -> Result<String, Error> {
+///     Err(Error::RateLimited {
+///         retry_after: Duration::from_millis(10),
+///     })
+/// }
+///
+/// let result = fetch
+///     .retry(ExponentialBuilder::default().with_max_times(1))
+///     .honor_hints()
+///     .notify(|_, dur| assert_eq!(dur, Duration::from_millis(10)))
+///     .call();
+///
+/// assert!(result.is_err());
+/// ```
+pub trait RetryAfterHint {
+    /// Return how long to wait before retrying, `None` to use the delay of the backoff.
+    fn retry_after(&self) -> Option<Duration>;
+}
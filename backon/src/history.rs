@@ -0,0 +1,565 @@
+use alloc::vec::Vec;
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::Context;
+use core::task::Poll;
+use core::task::ready;
+use core::time::Duration;
+
+use crate::Backoff;
+use crate::BlockingRetry;
+use crate::BlockingRetryWithContext;
+use crate::BlockingSleeper;
+use crate::GiveUpReason;
+use crate::Retry;
+use crate::RetryOutcome;
+use crate::RetryWithContext;
+use crate::Sleeper;
+use crate::clock::MaybeClock;
+use crate::outcome::OutcomeFn;
+use crate::retry_core::AdjustHook;
+use crate::retry_core::AsyncAdjustHook;
+use crate::retry_core::AsyncNotifyHook;
+use crate::retry_core::AsyncWhenHook;
+use crate::retry_core::NotifyHook;
+use crate::retry_core::RetryObserver;
+use crate::retry_core::WhenHook;
+
+/// RetryError is the error of a retry that collected the errors of all its attempts.
+///
+/// It's returned by the retries configured with `collect_errors`, for example
+/// [`Retry::collect_errors`], and requires the `alloc` feature.
+///
+/// Its [`Display`](fmt::Display) implementation prints a report of every attempt, while
+/// [`RetryError::last`] is the error that would have been returned without collecting them.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use backon::BlockingRetryable;
+/// use backon::ConstantBuilder;
+/// use backon::GiveUpReason;
+///
+/// fn fetch() -> Result<String, String> {
+///     Err("unavailable".to_string())
+/// }
+///
+/// let err = fetch
+///     .retry(ConstantBuilder::default().with_max_times(2))
+///     .sleep(|_| {})
+///     .collect_errors()
+///     .call()
+///     .unwrap_err();
+///
+/// assert_eq!(err.attempts().len(), 3);
+/// assert_eq!(err.attempts()[0].delay, Some(Duration::from_secs(1)));
+/// assert_eq!(err.last(), "unavailable");
+/// assert_eq!(err.reason(), GiveUpReason::BackoffExhausted);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetryError<E> {
+    attempts: Vec<FailedAttempt<E>>,
+    reason: GiveUpReason,
+}
+
+/// FailedAttempt is one of the failed attempts collected in a [`RetryError`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FailedAttempt<E> {
+    /// The error of the attempt.
+    ///
+    /// The error of the last attempt can also be a [`DeadlineExceeded`](crate::DeadlineExceeded)
+    /// or a [`Cancelled`](crate::Cancelled) error, which isn't returned by an attempt and whose
+    /// duration is zero.
+    pub error: E,
+    /// The time spent in the attempt, measured by the clock of the retry.
+    pub duration: Duration,
+    /// The delay slept after the attempt, or `None` for the last one.
+    pub delay: Option<Duration>,
+}
+
+impl<E> RetryError<E> {
+    /// The failed attempts, in order.
+    pub fn attempts(&self) -> &[FailedAttempt<E>] {
+        &self.attempts
+    }
+
+    /// Consume the error, returning the failed attempts.
+    pub fn into_attempts(self) -> Vec<FailedAttempt<E>> {
+        self.attempts
+    }
+
+    /// The reason the retry gave up.
+    pub fn reason(&self) -> GiveUpReason {
+        self.reason
+    }
+
+    /// The error returned last.
+    pub fn last(&self) -> &E {
+        &self
+            .attempts
+            .last()
+            .expect("retry error must have an attempt")
+            .error
+    }
+
+    /// Consume the error, returning the error returned last.
+    pub fn into_last(mut self) -> E {
+        self.attempts
+            .pop()
+            .expect("retry error must have an attempt")
+            .error
+    }
+}
+
+impl<E: fmt::Display> fmt::Display for RetryError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "retry gave up after {} attempts: {}",
+            self.attempts.len(),
+            self.reason
+        )?;
+        for (idx, attempt) in self.attempts.iter().enumerate() {
+            write!(
+                f,
+                "\n  attempt {} failed after {:?}: {}",
+                idx + 1,
+                attempt.duration,
+                attempt.error
+            )?;
+            if let Some(delay) = attempt.delay {
+                write!(f, ", retried after {delay:?}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<E: core::error::Error + 'static> core::error::Error for RetryError<E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        Some(self.last())
+    }
+}
+
+/// RetryStats describes how a retry went, whether it succeeded or not.
+///
+/// It's returned along with the result by the `with_stats` finishers, for example
+/// [`Retry::await_with_stats`], and requires the `alloc` feature.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use backon::BlockingRetryable;
+/// use backon::ConstantBuilder;
+///
+/// let mut attempts = 0;
+/// let (result, stats) = (|| {
+///     attempts += 1;
+///     if attempts < 3 { Err("unavailable") } else { Ok(attempts) }
+/// })
+/// .retry(ConstantBuilder::default())
+/// .sleep(|_| {})
+/// .call_with_stats();
+///
+/// assert_eq!(result, Ok(3));
+/// assert_eq!(stats.attempts, 3);
+/// assert_eq!(stats.slept, Duration::from_secs(2));
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RetryStats {
+    /// The number of attempts made, including the last one.
+    pub attempts: usize,
+    /// The total delay of the sleeps between the attempts, counted once they are over: a sleep
+    /// cut short by a cancellation is left out.
+    pub slept: Duration,
+    /// The time elapsed from the start of the first attempt to the end of the retry, measured by
+    /// the clock of the retry.
+    pub elapsed: Duration,
+    /// The delays slept after each failed attempt, once adjusted, and counted like `slept`.
+    pub delays: Vec<Duration>,
+}
+
+impl<E> RetryObserver<E> for RetryStats {
+    fn retried(&mut self, _: E, _: Duration, _: Duration) {}
+
+    fn not_ready(&mut self, _: Duration, _: Duration) {}
+
+    fn slept(&mut self, delay: Duration) {
+        self.delays.push(delay);
+    }
+
+    fn gave_up(&mut self, _: GiveUpReason, _: Duration) {}
+}
+
+/// The errors collected while retrying.
+struct History<E> {
+    attempts: Vec<FailedAttempt<E>>,
+    gave_up: Option<(GiveUpReason, Duration)>,
+}
+
+impl<E> History<E> {
+    /// Build the error once the retry returned `err`.
+    fn finish(&mut self, error: E) -> RetryError<E> {
+        let (reason, duration) = self
+            .gave_up
+            .take()
+            .expect("retry must give up with a reason");
+        let mut attempts = core::mem::take(&mut self.attempts);
+        attempts.push(FailedAttempt {
+            error,
+            duration,
+            delay: None,
+        });
+        RetryError { attempts, reason }
+    }
+}
+
+impl<E> RetryObserver<E> for History<E> {
+    fn retried(&mut self, error: E, duration: Duration, delay: Duration) {
+        self.attempts.push(FailedAttempt {
+            error,
+            duration,
+            delay: Some(delay),
+        });
+    }
+
+    /// The values that are not ready aren't failures, and are left out of the history.
+    fn not_ready(&mut self, _: Duration, _: Duration) {}
+
+    fn slept(&mut self, _: Duration) {}
+
+    fn gave_up(&mut self, reason: GiveUpReason, duration: Duration) {
+        self.gave_up = Some((reason, duration));
+    }
+}
+
+/// CollectErrors is a retry that returns a [`RetryError`] with the errors of all attempts.
+///
+/// It's created by the `collect_errors` method of the retries, for example
+/// [`Retry::collect_errors`]. Async retries are awaited, while blocking retries are run by
+/// calling [`CollectErrors::call`].
+pub struct CollectErrors<R, E> {
+    retry: R,
+    history: History<E>,
+}
+
+impl<R, E> CollectErrors<R, E> {
+    pub(crate) fn new(retry: R) -> Self {
+        Self {
+            retry,
+            history: History {
+                attempts: Vec::new(),
+                gave_up: None,
+            },
+        }
+    }
+}
+
+impl<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF> Future
+    for CollectErrors<Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF>, E>
+where
+    B: Backoff,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
+    FutureFn: FnMut() -> Fut,
+    SF: Sleeper,
+    RF: AsyncWhenHook<E>,
+    NF: AsyncNotifyHook<E>,
+    AF: AsyncAdjustHook<E>,
+    CF: MaybeClock,
+    XF: Future<Output = ()>,
+{
+    type Output = Result<T, RetryError<E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: This is safe because we don't move the `CollectErrors` struct and the retry,
+        // only project the pin to it.
+        let this = unsafe { self.get_unchecked_mut() };
+        let retry = unsafe { Pin::new_unchecked(&mut this.retry) };
+
+        let res = ready!(retry.poll_retry(cx, &mut this.history));
+        Poll::Ready(res.map_err(|err| this.history.finish(err)))
+    }
+}
+
+impl<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XF> Future
+    for CollectErrors<RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XF>, E>
+where
+    B: Backoff,
+    Fut: Future<Output = (Ctx, Result<T, E>)>,
+    FutureFn: FnMut(Ctx) -> Fut,
+    SF: Sleeper,
+    RF: AsyncWhenHook<E>,
+    NF: AsyncNotifyHook<E>,
+    AF: AsyncAdjustHook<E>,
+    CF: MaybeClock,
+    XF: Future<Output = ()>,
+{
+    type Output = (Ctx, Result<T, RetryError<E>>);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: This is safe because we don't move the `CollectErrors` struct and the retry,
+        // only project the pin to it.
+        let this = unsafe { self.get_unchecked_mut() };
+        let retry = unsafe { Pin::new_unchecked(&mut this.retry) };
+
+        let (ctx, res) = ready!(retry.poll_retry(cx, &mut this.history));
+        Poll::Ready((ctx, res.map_err(|err| this.history.finish(err))))
+    }
+}
+
+impl<B, T, E, F, SF, RF, NF, AF, CF> CollectErrors<BlockingRetry<B, T, E, F, SF, RF, NF, AF, CF>, E>
+where
+    B: Backoff,
+    F: OutcomeFn<Outcome: RetryOutcome<Ok = T, Err = E>>,
+    SF: BlockingSleeper,
+    RF: WhenHook<E>,
+    NF: NotifyHook<E>,
+    AF: AdjustHook<E>,
+    CF: MaybeClock,
+{
+    /// Call the retried function.
+    pub fn call(mut self) -> Result<T, RetryError<E>> {
+        self.retry
+            .call_with(&mut self.history)
+            .map_err(|err| self.history.finish(err))
+    }
+}
+
+impl<B, T, E, Ctx, F, SF, RF, NF, AF, CF>
+    CollectErrors<BlockingRetryWithContext<B, T, E, Ctx, F, SF, RF, NF, AF, CF>, E>
+where
+    B: Backoff,
+    F: FnMut(Ctx) -> (Ctx, Result<T, E>),
+    SF: BlockingSleeper,
+    RF: WhenHook<E>,
+    NF: NotifyHook<E>,
+    AF: AdjustHook<E>,
+    CF: MaybeClock,
+{
+    /// Call the retried function.
+    pub fn call(mut self) -> (Ctx, Result<T, RetryError<E>>) {
+        let (ctx, res) = self.retry.call_with(&mut self.history);
+        (ctx, res.map_err(|err| self.history.finish(err)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::String;
+    use alloc::string::ToString;
+    use core::cell::Cell;
+    use core::future::pending;
+    use core::future::poll_fn;
+    use core::future::ready;
+    use core::task::Poll;
+    use core::time::Duration;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    use super::*;
+    use crate::BlockingRetryable;
+    use crate::BlockingRetryableWithContext;
+    use crate::ConstantBuilder;
+    use crate::ManualClock;
+    use crate::Retryable;
+    use crate::RetryableWithContext;
+
+    #[test]
+    fn test_collect_errors_blocking() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut attempts = 0;
+        let err = (|| {
+            attempts += 1;
+            CLOCK.advance(Duration::from_millis(attempts));
+            Err::<(), String>(alloc::format!("error {attempts}"))
+        })
+        .retry(ConstantBuilder::default().with_max_times(2))
+        .sleep(|_| {})
+        .clock(&CLOCK)
+        .collect_errors()
+        .call()
+        .unwrap_err();
+
+        assert_eq!(
+            err.attempts(),
+            [
+                FailedAttempt {
+                    error: "error 1".to_string(),
+                    duration: Duration::from_millis(1),
+                    delay: Some(Duration::from_secs(1)),
+                },
+                FailedAttempt {
+                    error: "error 2".to_string(),
+                    duration: Duration::from_millis(2),
+                    delay: Some(Duration::from_secs(1)),
+                },
+                FailedAttempt {
+                    error: "error 3".to_string(),
+                    duration: Duration::from_millis(3),
+                    delay: None,
+                },
+            ]
+        );
+        assert_eq!(
+            err.to_string(),
+            "retry gave up after 3 attempts: backoff exhausted\n  \
+             attempt 1 failed after 1ms: error 1, retried after 1s\n  \
+             attempt 2 failed after 2ms: error 2, retried after 1s\n  \
+             attempt 3 failed after 3ms: error 3"
+        );
+        assert_eq!(err.into_last(), "error 3");
+    }
+
+    #[test]
+    fn test_collect_errors_blocking_with_context() {
+        let (ctx, res) = { |attempts: usize| (attempts + 1, Err::<(), _>(attempts)) }
+            .retry(ConstantBuilder::default().with_max_times(2))
+            .sleep(|_| {})
+            .context(0)
+            .collect_errors()
+            .call();
+
+        assert_eq!(ctx, 3);
+        let errors: Vec<_> = res
+            .unwrap_err()
+            .into_attempts()
+            .into_iter()
+            .map(|a| a.error)
+            .collect();
+        assert_eq!(errors, [0, 1, 2]);
+    }
+
+    #[async_test]
+    async fn test_collect_errors() {
+        let mut attempts = 0;
+        let result = (|| {
+            attempts += 1;
+            ready(if attempts < 3 {
+                Err(attempts)
+            } else {
+                Ok(attempts)
+            })
+        })
+        .retry(ConstantBuilder::default())
+        .sleep(|_| ready(()))
+        .collect_errors()
+        .await;
+
+        assert_eq!(result, Ok(3));
+
+        let err = (|| ready(Err::<(), _>("unavailable")))
+            .retry(ConstantBuilder::default().with_max_times(1))
+            .sleep(|_| ready(()))
+            .collect_errors()
+            .await
+            .unwrap_err();
+        assert_eq!(err.attempts().len(), 2);
+        assert_eq!(*err.last(), "unavailable");
+    }
+
+    #[async_test]
+    async fn test_collect_errors_with_context() {
+        let (ctx, res) = { |attempts: usize| ready((attempts + 1, Err::<(), _>(attempts))) }
+            .retry(ConstantBuilder::default().with_max_times(1))
+            .sleep(|_| ready(()))
+            .context(0)
+            .collect_errors()
+            .await;
+
+        assert_eq!(ctx, 2);
+        assert_eq!(res.unwrap_err().attempts().len(), 2);
+    }
+
+    #[test]
+    fn test_stats_blocking() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut attempts = 0;
+        let (result, stats) = (|| {
+            attempts += 1;
+            CLOCK.advance(Duration::from_millis(100));
+            if attempts < 3 {
+                Err("error")
+            } else {
+                Ok(attempts)
+            }
+        })
+        .retry(ConstantBuilder::default())
+        .sleep(|dur| CLOCK.advance(dur))
+        .adjust(|_, dur| dur.map(|dur| dur * 2))
+        .clock(&CLOCK)
+        .call_with_stats();
+
+        assert_eq!(result, Ok(3));
+        assert_eq!(stats.attempts, 3);
+        assert_eq!(stats.slept, Duration::from_secs(4));
+        assert_eq!(stats.elapsed, Duration::from_millis(4300));
+        assert_eq!(
+            stats.delays,
+            [Duration::from_secs(2), Duration::from_secs(2)]
+        );
+    }
+
+    #[async_test]
+    async fn test_stats() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let (result, stats) = (|| ready(Err::<(), _>("error")))
+            .retry(ConstantBuilder::default().with_max_times(2))
+            .sleep(|dur| {
+                CLOCK.advance(dur);
+                ready(())
+            })
+            .clock(&CLOCK)
+            .await_with_stats()
+            .await;
+
+        assert_eq!(result, Err("error"));
+        assert_eq!(stats.attempts, 3);
+        assert_eq!(stats.slept, Duration::from_secs(2));
+        assert_eq!(stats.elapsed, Duration::from_secs(2));
+        assert_eq!(stats.delays.len(), 2);
+    }
+
+    #[async_test]
+    async fn test_stats_cancelled() {
+        let attempts = Cell::new(0);
+        let (result, stats) = (|| {
+            attempts.set(attempts.get() + 1);
+            ready(Err::<(), _>("error"))
+        })
+        .retry(ConstantBuilder::default())
+        // Sleep forever unless cancelled.
+        .sleep(|_| pending::<()>())
+        .cancel_on_with(
+            poll_fn(|_| {
+                if attempts.get() > 0 {
+                    Poll::Ready(())
+                } else {
+                    Poll::Pending
+                }
+            }),
+            |_| "cancelled",
+        )
+        .await_with_stats()
+        .await;
+
+        assert_eq!(result, Err("cancelled"));
+        assert_eq!(stats.attempts, 1);
+        // The sleep was cut short, and never counted.
+        assert_eq!(stats.slept, Duration::ZERO);
+        assert!(stats.delays.is_empty());
+    }
+}
@@ -28,6 +28,18 @@
 //! - [`ConstantBuilder`]: backoff with a constant delay, limited to a specific number of attempts.
 //! - [`ExponentialBuilder`]: backoff with an exponential delay, also supports jitter.
 //! - [`FibonacciBuilder`]: backoff with a fibonacci delay, also supports jitter.
+//! - [`DecorrelatedJitterBuilder`]: backoff with a randomized delay that grows from the previous one, as recommended by AWS.
+//!
+//! With the `serde` feature enabled, these builders can be loaded from configuration files,
+//! and [`BackoffConfig`] picks one of them at runtime.
+//!
+//! Backoffs can be composed with the combinators provided by [`BackoffExt`], for example to
+//! cap the delays or to chain two strategies.
+//!
+//! Long-lived loops can rewind a backoff to its first delay with [`ResettableBackoff`].
+//!
+//! The delays planned by a builder can be previewed with their [`Schedule`], for example to
+//! assert on a policy in tests or to display it.
 //!
 //! # Sleep
 //!
@@ -211,17 +223,38 @@
 #![deny(unused_qualifications)]
 #![no_std]
 
-#[cfg(feature = "std-blocking-sleep")]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(any(feature = "std", feature = "std-blocking-sleep"))]
 extern crate std;
 
 mod backoff;
 pub use backoff::*;
 
+pub mod classify;
+
+mod clock;
+pub use clock::Clock;
+pub use clock::DefaultClock;
+#[cfg(target_has_atomic = "64")]
+pub use clock::ManualClock;
+#[cfg(feature = "std")]
+pub use clock::StdClock;
+
+mod hint;
+pub use hint::RetryAfterHint;
+
+mod outcome;
+pub use outcome::RetryOutcome;
+
 mod retry;
 pub use retry::Retry;
 pub use retry::Retryable;
 
 mod retry_core;
+pub use retry_core::GiveUpReason;
+pub use retry_core::RetryDecision;
+pub use retry_core::RetryState;
 
 mod retry_with_context;
 pub use retry_with_context::RetryWithContext;
@@ -237,6 +270,33 @@ pub use sleep::Sleeper;
 #[cfg(all(not(target_arch = "wasm32"), feature = "tokio-sleep"))]
 pub use sleep::TokioSleeper;
 
+mod timeout;
+pub use timeout::DeadlineExceeded;
+pub use timeout::Timeout;
+
+mod cancel;
+pub use cancel::Cancelled;
+
+#[cfg(feature = "alloc")]
+mod history;
+#[cfg(feature = "alloc")]
+pub use history::CollectErrors;
+#[cfg(feature = "alloc")]
+pub use history::FailedAttempt;
+#[cfg(feature = "alloc")]
+pub use history::RetryError;
+#[cfg(feature = "alloc")]
+pub use history::RetryStats;
+
+mod until;
+pub use until::Until;
+pub use until::UntilError;
+
+pub mod when;
+
+#[cfg(feature = "tower")]
+pub mod tower;
+
 mod blocking_retry;
 pub use blocking_retry::BlockingRetry;
 pub use blocking_retry::BlockingRetryable;
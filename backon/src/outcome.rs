@@ -0,0 +1,191 @@
+use core::ops::ControlFlow;
+
+/// RetryOutcome is the outcome of an attempt, telling a success apart from a failure to retry.
+///
+/// The retried functions can return any outcome: a failure goes through the `when`, `notify`
+/// and `adjust` hooks like an error, and the retry returns the outcome of the last attempt.
+///
+/// It's implemented for:
+///
+/// - `Result<T, E>`, which is retried on `Err`.
+/// - `Option<T>`, which is retried on `None`.
+/// - `bool`, which is retried on `false`.
+/// - `ControlFlow<B, C>`, which is retried on `Continue` until the function breaks.
+///
+/// # Examples
+///
+/// ```
+/// use std::collections::HashMap;
+///
+/// use backon::BlockingRetryable;
+/// use backon::ConstantBuilder;
+///
+/// let cache = HashMap::from([("key", "value")]);
+///
+/// let value = (|| cache.get("key"))
+///     .retry(ConstantBuilder::default())
+///     .sleep(|_| {})
+///     .call();
+///
+/// assert_eq!(value, Some(&"value"));
+/// ```
+pub trait RetryOutcome {
+    /// The value of a successful attempt.
+    type Ok;
+    /// The failure of an attempt, seen by the hooks.
+    type Err;
+
+    /// Split the outcome into a success or a failure.
+    fn into_result(self) -> Result<Self::Ok, Self::Err>;
+
+    /// Build the outcome back from a success or a failure.
+    fn from_result(res: Result<Self::Ok, Self::Err>) -> Self;
+}
+
+impl<T, E> RetryOutcome for Result<T, E> {
+    type Ok = T;
+    type Err = E;
+
+    fn into_result(self) -> Result<T, E> {
+        self
+    }
+
+    fn from_result(res: Result<T, E>) -> Self {
+        res
+    }
+}
+
+impl<T> RetryOutcome for Option<T> {
+    type Ok = T;
+    type Err = ();
+
+    fn into_result(self) -> Result<T, ()> {
+        self.ok_or(())
+    }
+
+    fn from_result(res: Result<T, ()>) -> Self {
+        res.ok()
+    }
+}
+
+impl RetryOutcome for bool {
+    type Ok = ();
+    type Err = ();
+
+    fn into_result(self) -> Result<(), ()> {
+        if self { Ok(()) } else { Err(()) }
+    }
+
+    fn from_result(res: Result<(), ()>) -> Self {
+        res.is_ok()
+    }
+}
+
+impl<B, C> RetryOutcome for ControlFlow<B, C> {
+    type Ok = B;
+    type Err = C;
+
+    fn into_result(self) -> Result<B, C> {
+        match self {
+            ControlFlow::Break(b) => Ok(b),
+            ControlFlow::Continue(c) => Err(c),
+        }
+    }
+
+    fn from_result(res: Result<B, C>) -> Self {
+        match res {
+            Ok(b) => ControlFlow::Break(b),
+            Err(c) => ControlFlow::Continue(c),
+        }
+    }
+}
+
+/// A blocking function returning the outcome of an attempt.
+#[doc(hidden)]
+pub trait OutcomeFn {
+    type Outcome: RetryOutcome;
+
+    fn attempt(&mut self) -> Self::Outcome;
+}
+
+impl<F, O> OutcomeFn for F
+where
+    F: FnMut() -> O,
+    O: RetryOutcome,
+{
+    type Outcome = O;
+
+    fn attempt(&mut self) -> O {
+        self()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::Cell;
+    use core::future::ready;
+    use core::time::Duration;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    use super::*;
+    use crate::BlockingRetryable;
+    use crate::ConstantBuilder;
+    use crate::Retryable;
+
+    #[test]
+    fn test_retry_option() {
+        let attempts = Cell::new(0);
+        let value = (|| {
+            attempts.set(attempts.get() + 1);
+            (attempts.get() == 3).then_some("value")
+        })
+        .retry(ConstantBuilder::default())
+        .sleep(|_| {})
+        .call();
+
+        assert_eq!(value, Some("value"));
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[test]
+    fn test_retry_bool() {
+        let attempts = Cell::new(0);
+        let done = (|| {
+            attempts.set(attempts.get() + 1);
+            false
+        })
+        .retry(ConstantBuilder::default().with_max_times(2))
+        .sleep(|_| {})
+        .notify(|_, _| {})
+        .call();
+
+        assert!(!done);
+        assert_eq!(attempts.get(), 3);
+    }
+
+    #[async_test]
+    async fn test_retry_control_flow() {
+        let attempts = Cell::new(0);
+        let mut seen = 0;
+        let flow = (|| {
+            attempts.set(attempts.get() + 1);
+            ready(if attempts.get() < 3 {
+                ControlFlow::Continue(attempts.get())
+            } else {
+                ControlFlow::Break("done")
+            })
+        })
+        .retry(ConstantBuilder::default())
+        .sleep(|_| ready(()))
+        // The hooks see the values the function continued with.
+        .notify(|attempt: &usize, _: Duration| seen = *attempt)
+        .await;
+
+        assert_eq!(flow, ControlFlow::Break("done"));
+        assert_eq!(seen, 2);
+    }
+}
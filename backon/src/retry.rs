@@ -1,25 +1,56 @@
 use core::future::Future;
+use core::future::Pending;
+use core::future::poll_fn;
 use core::ops::ControlFlow;
 use core::pin::Pin;
+use core::pin::pin;
 use core::task::Context;
 use core::task::Poll;
 use core::task::ready;
 use core::time::Duration;
 
 use crate::Backoff;
+use crate::Cancelled;
+use crate::Clock;
+#[cfg(feature = "alloc")]
+use crate::CollectErrors;
+use crate::DeadlineExceeded;
+use crate::DefaultClock;
 use crate::DefaultSleeper;
+use crate::GiveUpReason;
+use crate::RetryAfterHint;
+use crate::RetryDecision;
+use crate::RetryOutcome;
+use crate::RetryState;
+#[cfg(feature = "alloc")]
+use crate::RetryStats;
 use crate::Sleeper;
+use crate::Timeout;
+use crate::Until;
 use crate::backoff::BackoffBuilder;
+use crate::cancel::CancelOn;
+use crate::cancel::poll_cancel;
+use crate::clock::MaybeClock;
+use crate::clock::maybe_instant;
+use crate::retry_core::Async;
+use crate::retry_core::AsyncAdjustHook;
+use crate::retry_core::AsyncNotifyHook;
+use crate::retry_core::AsyncWhenHook;
+use crate::retry_core::Classify;
 use crate::retry_core::RetryConfig;
+use crate::retry_core::RetryObserver;
+use crate::retry_core::WithState;
 use crate::retry_core::always_retry;
 use crate::retry_core::identity_adjust;
 use crate::retry_core::noop_notify;
 use crate::sleep::MaybeSleeper;
+use crate::timeout::AttemptTimeout;
 
 /// Retryable will add retry support for functions that produce futures with results.
 ///
 /// This means all types that implement `FnMut() -> impl Future<Output = Result<T, E>>`
-/// will be able to use `retry`.
+/// will be able to use `retry`. The output can also be any other [`RetryOutcome`], for
+/// example an `Option<T>` retried on `None`.
 ///
 /// For example:
 ///
@@ -47,7 +78,7 @@ pub trait Retryable<
     B: BackoffBuilder,
     T,
     E,
-    Fut: Future<Output = Result<T, E>>,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
     FutureFn: FnMut() -> Fut,
 >
 {
@@ -58,11 +89,12 @@ pub trait Retryable<
 impl<B, T, E, Fut, FutureFn> Retryable<B, T, E, Fut, FutureFn> for FutureFn
 where
     B: BackoffBuilder,
-    Fut: Future<Output = Result<T, E>>,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
     FutureFn: FnMut() -> Fut,
 {
     fn retry(self, builder: B) -> Retry<B::Backoff, T, E, Fut, FutureFn> {
-        Retry::new(self, builder.build())
+        let max_delay = builder.max_delay();
+        Retry::new(self, builder.build(), max_delay)
     }
 }
 
@@ -71,49 +103,64 @@ pub struct Retry<
     B: Backoff,
     T,
     E,
-    Fut: Future<Output = Result<T, E>>,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
     FutureFn: FnMut() -> Fut,
     SF: MaybeSleeper = DefaultSleeper,
-    RF = fn(&E) -> bool,
-    NF = fn(&E, Duration),
-    AF = fn(&E, Option<Duration>) -> Option<Duration>,
+    RF: AsyncWhenHook<E> = fn(&E) -> bool,
+    NF: AsyncNotifyHook<E> = fn(&E, Duration),
+    AF: AsyncAdjustHook<E> = fn(&E, Option<Duration>) -> Option<Duration>,
+    CF: MaybeClock = DefaultClock,
+    XF = Pending<()>,
 > {
-    config: RetryConfig<B, SF, RF, NF, AF>,
+    config: RetryConfig<B, SF, RF, NF, AF, CF>,
     future_fn: FutureFn,
-    state: State<T, E, Fut, SF::Sleep>,
+    attempt_timeout: Option<AttemptTimeout<E>>,
+    deadline_exceeded: Option<fn(DeadlineExceeded) -> E>,
+    hint: Option<fn(&E) -> Option<Duration>>,
+    cancel: Option<CancelOn<XF, E>>,
+    state: StateOf<T, E, Fut, SF, RF, NF, AF>,
 }
 
 impl<B, T, E, Fut, FutureFn> Retry<B, T, E, Fut, FutureFn>
 where
     B: Backoff,
-    Fut: Future<Output = Result<T, E>>,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
     FutureFn: FnMut() -> Fut,
 {
     /// Initiate a new retry.
-    fn new(future_fn: FutureFn, backoff: B) -> Self {
+    fn new(future_fn: FutureFn, backoff: B, max_delay: Option<Duration>) -> Self {
         Retry {
             config: RetryConfig::new(
                 backoff,
+                max_delay,
                 DefaultSleeper::default(),
                 always_retry::<E>,
                 noop_notify::<E>,
                 identity_adjust::<E>,
+                DefaultClock::default(),
             ),
             future_fn,
+            attempt_timeout: None,
+            deadline_exceeded: None,
+            hint: None,
+            cancel: None,
             state: State::Idle,
         }
     }
 }
 
-impl<B, T, E, Fut, FutureFn, SF, RF, NF, AF> Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF>
+impl<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
+    Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
 where
     B: Backoff,
-    Fut: Future<Output = Result<T, E>>,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
     FutureFn: FnMut() -> Fut,
     SF: MaybeSleeper,
-    RF: FnMut(&E) -> bool,
-    NF: FnMut(&E, Duration),
-    AF: FnMut(&E, Option<Duration>) -> Option<Duration>,
+    RF: AsyncWhenHook<E>,
+    NF: AsyncNotifyHook<E>,
+    AF: AsyncAdjustHook<E>,
+    CF: MaybeClock,
+    XF: Future<Output = ()>,
 {
     /// Set the sleeper for retrying.
     ///
@@ -146,10 +193,17 @@ where
     ///     Ok(())
     /// }
     /// ```
-    pub fn sleep<SN: Sleeper>(self, sleep_fn: SN) -> Retry<B, T, E, Fut, FutureFn, SN, RF, NF, AF> {
+    pub fn sleep<SN: Sleeper>(
+        self,
+        sleep_fn: SN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SN, RF, NF, AF, CF, XF> {
         Retry {
             config: self.config.with_sleep(sleep_fn),
             future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
             state: State::Idle,
         }
     }
@@ -176,178 +230,984 @@ where
     /// async fn main() -> Result<()> {
     ///     let content = fetch
     ///         .retry(ExponentialBuilder::default())
-    ///         .when(|e| e.to_string() == "EOF")
+    ///         .when(|e| e.to_string() == "EOF")
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn when<RN: FnMut(&E) -> bool>(
+        self,
+        retryable: RN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RN, NF, AF, CF, XF> {
+        Retry {
+            config: self.config.with_retryable(retryable),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Set the conditions for retrying, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`Retry::when`], and the function also receives the progress of the retry,
+    /// for example to only retry some errors on the first attempts.
+    #[allow(clippy::type_complexity)]
+    pub fn when_with_state<RN: FnMut(&E, &RetryState) -> bool>(
+        self,
+        retryable: RN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, WithState<RN>, NF, AF, CF, XF> {
+        Retry {
+            config: self.config.timed().with_retryable(WithState(retryable)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Set how to handle the errors, with a function classifying them into a [`RetryDecision`].
+    ///
+    /// This replaces [`Retry::when`], and can also override the delay of the backoff without
+    /// matching the error again in [`Retry::adjust`]. The delay is still passed to `adjust`
+    /// afterwards, and the retry gives up once the backoff is exhausted whatever the decision.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use backon::ExponentialBuilder;
+    /// use backon::RetryDecision;
+    /// use backon::Retryable;
+    ///
+    /// #[derive(Debug)]
+    /// enum Error {
+    ///     Unavailable,
+    ///     RateLimited(Duration),
+    ///     Conflict,
+    ///     Invalid,
+    /// }
+    ///
+    /// async fn fetch() -> Result<String, Error> {
+    ///     Err(Error::Unavailable)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), Error> {
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .classify(|e| match e {
+    ///             Error::Unavailable => RetryDecision::Retry,
+    ///             Error::RateLimited(dur) => RetryDecision::RetryAfter(*dur),
+    ///             Error::Conflict => RetryDecision::RetryNow,
+    ///             Error::Invalid => RetryDecision::Abort,
+    ///         })
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn classify<RN: FnMut(&E) -> RetryDecision>(
+        self,
+        classify: RN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, Classify<RN>, NF, AF, CF, XF> {
+        Retry {
+            config: self.config.with_retryable(Classify(classify)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Set the conditions for retrying, with a function returning a future.
+    ///
+    /// This is the same as [`Retry::when`] for conditions that need to await, for example to
+    /// check a feature flag. The future must not borrow the error, so the function should
+    /// extract what it needs from it first.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
+    ///
+    /// async fn fetch() -> Result<String> {
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
+    /// }
+    ///
+    /// async fn retries_enabled() -> bool {
+    ///     // Check a feature flag, a leader lease...
+    ///     true
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .when_async(|_| retries_enabled())
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn when_async<RN, RFut>(
+        self,
+        retryable: RN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, Async<RN>, NF, AF, CF, XF>
+    where
+        RN: FnMut(&E) -> RFut,
+        RFut: Future<Output = bool>,
+    {
+        Retry {
+            config: self.config.with_retryable(Async(retryable)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Set to notify for all retry attempts.
+    ///
+    /// When a retry happens, the input function will be invoked with the error and the sleep duration before pausing.
+    ///
+    /// If not specified, this operation does nothing.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
+    ///
+    /// async fn fetch() -> Result<String> {
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .notify(|err: &anyhow::Error, dur: Duration| {
+    ///             println!("retrying error {:?} with sleeping {:?}", err, dur);
+    ///         })
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn notify<NN: FnMut(&E, Duration)>(
+        self,
+        notify: NN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, NN, AF, CF, XF> {
+        Retry {
+            config: self.config.with_notify(notify),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Set to notify for all retry attempts, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`Retry::notify`], and the function also receives the progress of the retry,
+    /// for example to log the attempt number alongside the error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::RetryState;
+    /// use backon::Retryable;
+    ///
+    /// async fn fetch() -> Result<String> {
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .notify_with_state(|err: &anyhow::Error, dur: Duration, state: &RetryState| {
+    ///             println!(
+    ///                 "attempt {} failed with {:?}, retrying after {:?}",
+    ///                 state.attempt, err, dur
+    ///             );
+    ///         })
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    #[allow(clippy::type_complexity)]
+    pub fn notify_with_state<NN: FnMut(&E, Duration, &RetryState)>(
+        self,
+        notify: NN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, WithState<NN>, AF, CF, XF> {
+        Retry {
+            config: self.config.timed().with_notify(WithState(notify)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Set to notify for all retry attempts, with a function returning a future.
+    ///
+    /// This is the same as [`Retry::notify`] for notifications that need to await, for example
+    /// to write an audit record. The retry sleeps once the future completed.
+    #[allow(clippy::type_complexity)]
+    pub fn notify_async<NN, NFut>(
+        self,
+        notify: NN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, Async<NN>, AF, CF, XF>
+    where
+        NN: FnMut(&E, Duration) -> NFut,
+        NFut: Future<Output = ()>,
+    {
+        Retry {
+            config: self.config.with_notify(Async(notify)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Sets the function to adjust the backoff duration for retry attempts.
+    ///
+    /// When a retry occurs, the provided function will be called with the error and the proposed backoff duration, allowing you to modify the final duration used.
+    ///
+    /// If the function returns `None`, it indicates that no further retries should be made, and the error will be returned regardless of the backoff duration provided by the input.
+    ///
+    /// If no `adjust` function is specified, the original backoff duration from the input will be used without modification.
+    ///
+    /// `adjust` can be used to implement dynamic backoff strategies, such as adjust backoff values from the http `Retry-After` headers.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    /// use std::error::Error;
+    /// use std::fmt::Display;
+    /// use std::fmt::Formatter;
+    ///
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
+    /// use reqwest::header::HeaderMap;
+    /// use reqwest::StatusCode;
+    ///
+    /// #[derive(Debug)]
+    /// struct HttpError {
+    ///     headers: HeaderMap,
+    /// }
+    ///
+    /// impl Display for HttpError {
+    ///     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+    ///         write!(f, "http error")
+    ///     }
+    /// }
+    ///
+    /// impl Error for HttpError {}
+    ///
+    /// async fn fetch() -> Result<String> {
+    ///     let resp = reqwest::get("https://www.rust-lang.org").await?;
+    ///     if resp.status() != StatusCode::OK {
+    ///         let source = HttpError {
+    ///             headers: resp.headers().clone(),
+    ///         };
+    ///         return Err(anyhow::Error::new(source));
+    ///     }
+    ///     Ok(resp.text().await?)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .adjust(|err, dur| {
+    ///             match err.downcast_ref::<HttpError>() {
+    ///                 Some(v) => {
+    ///                     if let Some(retry_after) = v.headers.get("Retry-After") {
+    ///                         // Parse the Retry-After header and adjust the backoff duration
+    ///                         let retry_after = retry_after.to_str().unwrap_or("0");
+    ///                         let retry_after = retry_after.parse::<u64>().unwrap_or(0);
+    ///                         Some(Duration::from_secs(retry_after))
+    ///                     } else {
+    ///                         dur
+    ///                     }
+    ///                 }
+    ///                 None => dur,
+    ///             }
+    ///         })
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn adjust<NAF: FnMut(&E, Option<Duration>) -> Option<Duration>>(
+        self,
+        adjust: NAF,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, NF, NAF, CF, XF> {
+        Retry {
+            config: self.config.with_adjust(adjust),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Sets the function to adjust the backoff duration, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`Retry::adjust`], and the function also receives the progress of the retry,
+    /// for example to shorten the delays once the retry took too long.
+    #[allow(clippy::type_complexity)]
+    pub fn adjust_with_state<NAF: FnMut(&E, Option<Duration>, &RetryState) -> Option<Duration>>(
+        self,
+        adjust: NAF,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, NF, WithState<NAF>, CF, XF> {
+        Retry {
+            config: self.config.timed().with_adjust(WithState(adjust)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Sets the function to adjust the backoff duration, with a function returning a future.
+    ///
+    /// This is the same as [`Retry::adjust`] for adjustments that need to await, for example to
+    /// ask a rate limiter for the delay.
+    #[allow(clippy::type_complexity)]
+    pub fn adjust_async<NAF, AFut>(
+        self,
+        adjust: NAF,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, NF, Async<NAF>, CF, XF>
+    where
+        NAF: FnMut(&E, Option<Duration>) -> AFut,
+        AFut: Future<Output = Option<Duration>>,
+    {
+        Retry {
+            config: self.config.with_adjust(Async(adjust)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle,
+        }
+    }
+
+    /// Set the clock used to measure the time limits of the retry.
+    ///
+    /// The clock should implement the [`Clock`] trait, for example [`ManualClock`](crate::ManualClock) in tests.
+    ///
+    /// If not specified, we use the [`DefaultClock`].
+    ///
+    /// The clock must be set before [`Retry::deadline`], which is an instant of the clock: a
+    /// deadline set before the clock is dropped along with the previous clock.
+    pub fn clock<CN: Clock>(
+        self,
+        clock: CN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CN, XF> {
+        Retry {
+            config: self.config.with_clock(clock),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: None,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: self.state,
+        }
+    }
+
+    /// Set the maximum time the whole retry can take.
+    ///
+    /// The time is measured by the clock from the start of the first attempt, so it includes the
+    /// time spent in the attempts as well as in the sleeps. No retry will be made if the next sleep
+    /// would end after `max_elapsed`, the last error is returned instead.
+    ///
+    /// Unlike `with_total_delay` on the backoff builders, which only sums the sleeps, this limit
+    /// follows the clock.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
+    ///
+    /// async fn fetch() -> Result<String> {
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default().without_max_times())
+    ///         // Give up if the content can't be fetched within 30s.
+    ///         .max_elapsed(Duration::from_secs(30))
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self
+    where
+        CF: Clock,
+    {
+        self.config.max_elapsed = Some(max_elapsed);
+        self.config.timed = true;
+        self
+    }
+
+    /// Set the deadline of the retry.
+    ///
+    /// The deadline is an instant of the clock, so a custom clock must be set with
+    /// [`Retry::clock`] beforehand. Sleeps are shortened to end at the deadline, and
+    /// no attempt starts after it: the retry returns a [`DeadlineExceeded`] converted into `E`
+    /// instead of the last error, which tells it apart from a retry ended by the backoff.
+    ///
+    /// Use [`Retry::deadline_with`] if `E` doesn't implement `From<DeadlineExceeded>`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use std::time::Duration;
+    /// use std::time::Instant;
+    ///
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
+    ///
+    /// async fn fetch() -> Result<String> {
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     // The deadline received from upstream.
+    ///     let deadline = Instant::now() + Duration::from_secs(10);
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .deadline(deadline)
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn deadline(self, deadline: <CF as Clock>::Instant) -> Self
+    where
+        CF: Clock,
+        E: From<DeadlineExceeded>,
+    {
+        self.deadline_with(deadline, E::from)
+    }
+
+    /// Set the deadline of the retry, with a function building the error returned once it's reached.
+    ///
+    /// This is the same as [`Retry::deadline`], for error types that don't implement
+    /// `From<DeadlineExceeded>`.
+    pub fn deadline_with(
+        mut self,
+        deadline: <CF as Clock>::Instant,
+        map: fn(DeadlineExceeded) -> E,
+    ) -> Self
+    where
+        CF: Clock,
+    {
+        self.config.deadline = Some(maybe_instant::<CF>(deadline));
+        self.deadline_exceeded = Some(map);
+        self
+    }
+
+    /// Wait for the delay hinted by the errors implementing [`RetryAfterHint`] before retrying.
+    ///
+    /// The hint replaces the delay of the backoff, clamped to the maximum delay of the backoff
+    /// builder if it has one, and is passed to [`Retry::adjust`] like the delay it replaces. Errors
+    /// without a hint are retried after the delay of the backoff, which still limits the number of
+    /// retries. The delays chosen with [`Retry::classify`] take precedence over the hints.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use backon::ExponentialBuilder;
+    /// use backon::RetryAfterHint;
+    /// use backon::Retryable;
+    ///
+    /// #[derive(Debug)]
+    /// struct RateLimited {
+    ///     retry_after: Option<Duration>,
+    /// }
+    ///
+    /// impl RetryAfterHint for RateLimited {
+    ///     fn retry_after(&self) -> Option<Duration> {
+    ///         self.retry_after
+    ///     }
+    /// }
+    ///
+    /// async fn fetch() -> Result<String, RateLimited> {
+    ///     Err(RateLimited {
+    ///         retry_after: Some(Duration::from_secs(5)),
+    ///     })
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<(), RateLimited> {
+    ///     let content = fetch
+    ///         // The hints are clamped to 30s.
+    ///         .retry(ExponentialBuilder::default().with_max_delay(Duration::from_secs(30)))
+    ///         .honor_hints()
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn honor_hints(mut self) -> Self
+    where
+        E: RetryAfterHint,
+    {
+        self.hint = Some(E::retry_after);
+        self
+    }
+
+    /// Cancel the retry once `cancel` resolves, for example on a shutdown signal.
+    ///
+    /// The cancellation is checked before every attempt and while sleeping: a pending sleep is
+    /// aborted and the retry returns a [`Cancelled`] converted into `E`. An attempt in progress
+    /// is not interrupted, the retry is cancelled once it fails.
+    ///
+    /// Use [`Retry::cancel_on_with`] if `E` doesn't implement `From<Cancelled>`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
+    /// use tokio::sync::oneshot;
+    ///
+    /// async fn fetch() -> Result<String> {
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     // Stop retrying once the shutdown is sent or `_shutdown_tx` is dropped.
+    ///     let (_shutdown_tx, shutdown_rx) = oneshot::channel::<()>();
+    ///
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .cancel_on(async move {
+    ///             shutdown_rx.await.ok();
+    ///         })
+    ///         .await?;
+    ///     println!("fetch succeeded: {}", content);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn cancel_on<XN: Future<Output = ()>>(
+        self,
+        cancel: XN,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XN>
+    where
+        E: From<Cancelled>,
+    {
+        self.cancel_on_with(cancel, E::from)
+    }
+
+    /// Cancel the retry once `cancel` resolves, with a function building the error of a cancelled retry.
+    ///
+    /// This is the same as [`Retry::cancel_on`], for error types that don't implement
+    /// `From<Cancelled>`.
+    pub fn cancel_on_with<XN: Future<Output = ()>>(
+        self,
+        cancel: XN,
+        map: fn(Cancelled) -> E,
+    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XN> {
+        Retry {
+            config: self.config,
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: Some(CancelOn::new(cancel, map)),
+            state: self.state,
+        }
+    }
+
+    /// Collect the errors of all attempts, returning a [`RetryError`](crate::RetryError) once the retry gives up.
+    ///
+    /// The error holds the error and the duration of every attempt, along with the delay
+    /// slept after it. This requires the `alloc` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
+    ///
+    /// async fn fetch() -> Result<String> {
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     match fetch.retry(ExponentialBuilder::default()).collect_errors().await {
+    ///         Ok(content) => println!("fetch succeeded: {}", content),
+    ///         // Print the error of every attempt.
+    ///         Err(err) => println!("fetch failed: {}", err),
+    ///     }
+    /// }
+    /// ```
+    #[cfg(feature = "alloc")]
+    pub fn collect_errors(mut self) -> CollectErrors<Self, E> {
+        self.config.timed = true;
+        CollectErrors::new(self)
+    }
+
+    /// Also retry the values that are not `ready`, for example to poll a job until it's done.
+    ///
+    /// A value for which `ready` returns `false` is retried with the same backoff and time
+    /// limits as an error, but without going through the `when`, `notify` and `adjust` hooks,
+    /// which only see the errors. Once the retry gives up, including at the deadline, the last
+    /// value is returned as an [`UntilError::NotReady`](crate::UntilError::NotReady) to tell it
+    /// apart from an error.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
+    /// use backon::UntilError;
+    ///
+    /// async fn job_status() -> Result<String> {
+    ///     Ok(reqwest::get("https://example.com/jobs/42")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     match job_status
+    ///         .retry(ExponentialBuilder::default())
+    ///         .until(|status| status == "done")
+    ///         .await
+    ///     {
+    ///         Ok(status) => println!("job finished: {}", status),
+    ///         Err(UntilError::NotReady(status)) => println!("job still {} after retrying", status),
+    ///         Err(UntilError::Failed(err)) => println!("job status failed: {}", err),
+    ///     }
+    /// }
+    /// ```
+    pub fn until<P: FnMut(&T) -> bool>(self, ready: P) -> Until<Self, P> {
+        Until::new(self, ready)
+    }
+
+    /// Set the timeout of every attempt.
+    ///
+    /// Each call to the future function is raced against the sleeper. If the attempt doesn't
+    /// complete within `timeout`, its future is dropped and the attempt fails with a [`Timeout`]
+    /// converted into `E`. The timed out attempt is then handled like any other error: it goes
+    /// through `when`, `notify` and `adjust`, and is retried according to the backoff.
+    ///
+    /// Use [`Retry::attempt_timeout_with`] if `E` doesn't implement `From<Timeout>`.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
+    ///
+    /// async fn fetch() -> Result<String> {
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let content = fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         // Retry the attempts that take longer than 5s.
+    ///         .attempt_timeout(Duration::from_secs(5))
     ///         .await?;
     ///     println!("fetch succeeded: {}", content);
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub fn when<RN: FnMut(&E) -> bool>(
-        self,
-        retryable: RN,
-    ) -> Retry<B, T, E, Fut, FutureFn, SF, RN, NF, AF> {
-        Retry {
-            config: self.config.with_retryable(retryable),
-            future_fn: self.future_fn,
-            state: self.state,
-        }
+    pub fn attempt_timeout(self, timeout: Duration) -> Self
+    where
+        E: From<Timeout>,
+    {
+        self.attempt_timeout_with(timeout, E::from)
     }
 
-    /// Set to notify for all retry attempts.
+    /// Set the timeout of every attempt, with a function building the error of a timed out attempt.
     ///
-    /// When a retry happens, the input function will be invoked with the error and the sleep duration before pausing.
-    ///
-    /// If not specified, this operation does nothing.
+    /// This is the same as [`Retry::attempt_timeout`], for error types that don't implement
+    /// `From<Timeout>`.
     ///
     /// # Examples
     ///
     /// ```no_run
     /// use core::time::Duration;
     ///
-    /// use anyhow::Result;
     /// use backon::ExponentialBuilder;
     /// use backon::Retryable;
     ///
-    /// async fn fetch() -> Result<String> {
-    ///     Ok(reqwest::get("https://www.rust-lang.org")
-    ///         .await?
-    ///         .text()
-    ///         .await?)
+    /// async fn fetch() -> Result<String, String> {
+    ///     Ok("hello, world!".to_string())
     /// }
     ///
     /// #[tokio::main(flavor = "current_thread")]
-    /// async fn main() -> Result<()> {
+    /// async fn main() -> Result<(), String> {
     ///     let content = fetch
     ///         .retry(ExponentialBuilder::default())
-    ///         .notify(|err: &anyhow::Error, dur: Duration| {
-    ///             println!("retrying error {:?} with sleeping {:?}", err, dur);
-    ///         })
+    ///         .attempt_timeout_with(Duration::from_secs(5), |err| err.to_string())
     ///         .await?;
     ///     println!("fetch succeeded: {}", content);
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub fn notify<NN: FnMut(&E, Duration)>(
-        self,
-        notify: NN,
-    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, NN, AF> {
-        Retry {
-            config: self.config.with_notify(notify),
-            future_fn: self.future_fn,
-            state: self.state,
-        }
+    pub fn attempt_timeout_with(mut self, timeout: Duration, map: fn(Timeout) -> E) -> Self {
+        self.attempt_timeout = Some(AttemptTimeout::new(timeout, map));
+        self
     }
+}
 
-    /// Sets the function to adjust the backoff duration for retry attempts.
-    ///
-    /// When a retry occurs, the provided function will be called with the error and the proposed backoff duration, allowing you to modify the final duration used.
-    ///
-    /// If the function returns `None`, it indicates that no further retries should be made, and the error will be returned regardless of the backoff duration provided by the input.
-    ///
-    /// If no `adjust` function is specified, the original backoff duration from the input will be used without modification.
+/// The state of a retry, with the futures of its sleeper and hooks.
+type StateOf<T, E, Fut, SF, RF, NF, AF> = State<
+    T,
+    E,
+    Fut,
+    <SF as MaybeSleeper>::Sleep,
+    <RF as AsyncWhenHook<E>>::Future,
+    <AF as AsyncAdjustHook<E>>::Future,
+    <NF as AsyncNotifyHook<E>>::Future,
+>;
+
+/// State maintains internal state of retry.
+#[derive(Default)]
+enum State<
+    T,
+    E,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
+    SleepFut: Future,
+    WhenFut,
+    AdjustFut,
+    NotifyFut,
+> {
+    #[default]
+    Idle,
+    /// Polling the attempt, raced against its timeout if any.
+    Polling(Fut, Option<SleepFut>),
+    /// Checking if the error of the failed attempt is retryable.
+    Checking(Option<E>, RetryState, WhenFut),
+    /// Adjusting the delay proposed by the backoff.
+    Adjusting(Option<E>, RetryState, Option<Duration>, AdjustFut),
+    /// Notifying the retry before sleeping for the delay.
+    Notifying(Option<E>, RetryState, Duration, NotifyFut),
+    /// Sleeping for the delay, which is counted once the sleep is over, along with the last value
+    /// if it was not ready.
+    Sleeping(SleepFut, Duration, Option<T>),
+}
+
+impl<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
+    Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
+where
+    B: Backoff,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
+    FutureFn: FnMut() -> Fut,
+    SF: Sleeper,
+    RF: AsyncWhenHook<E>,
+    NF: AsyncNotifyHook<E>,
+    AF: AsyncAdjustHook<E>,
+    CF: MaybeClock,
+    XF: Future<Output = ()>,
+{
+    /// Await the retry, returning the reason it gave up along with the error.
     ///
-    /// `adjust` can be used to implement dynamic backoff strategies, such as adjust backoff values from the http `Retry-After` headers.
+    /// The [`GiveUpReason`] tells apart an error that is not retryable from a retry that ran out
+    /// of retries or time.
     ///
     /// # Examples
     ///
     /// ```no_run
-    /// use core::time::Duration;
-    /// use std::error::Error;
-    /// use std::fmt::Display;
-    /// use std::fmt::Formatter;
-    ///
     /// use anyhow::Result;
     /// use backon::ExponentialBuilder;
     /// use backon::Retryable;
-    /// use reqwest::header::HeaderMap;
-    /// use reqwest::StatusCode;
     ///
-    /// #[derive(Debug)]
-    /// struct HttpError {
-    ///     headers: HeaderMap,
+    /// async fn fetch() -> Result<String> {
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
     /// }
     ///
-    /// impl Display for HttpError {
-    ///     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
-    ///         write!(f, "http error")
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() {
+    ///     match fetch
+    ///         .retry(ExponentialBuilder::default())
+    ///         .await_with_reason()
+    ///         .await
+    ///     {
+    ///         Ok(content) => println!("fetch succeeded: {}", content),
+    ///         Err((err, reason)) if reason.is_permanent() => println!("fetch failed: {}", err),
+    ///         Err((err, reason)) => println!("fetch gave up ({}): {}", reason, err),
     ///     }
     /// }
+    /// ```
+    pub async fn await_with_reason(self) -> Result<T, (E, GiveUpReason)> {
+        let mut reason = None;
+        let mut retry = pin!(self);
+        let res = poll_fn(|cx| retry.as_mut().poll_retry(cx, &mut reason)).await;
+        res.map_err(|err| (err, reason.expect("retry must give up with a reason")))
+    }
+
+    /// Await the retry, returning the [`RetryStats`](crate::RetryStats) along with the result.
     ///
-    /// impl Error for HttpError {}
+    /// This requires the `alloc` feature.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::Retryable;
     ///
     /// async fn fetch() -> Result<String> {
-    ///     let resp = reqwest::get("https://www.rust-lang.org").await?;
-    ///     if resp.status() != StatusCode::OK {
-    ///         let source = HttpError {
-    ///             headers: resp.headers().clone(),
-    ///         };
-    ///         return Err(anyhow::Error::new(source));
-    ///     }
-    ///     Ok(resp.text().await?)
+    ///     Ok(reqwest::get("https://www.rust-lang.org")
+    ///         .await?
+    ///         .text()
+    ///         .await?)
     /// }
     ///
     /// #[tokio::main(flavor = "current_thread")]
     /// async fn main() -> Result<()> {
-    ///     let content = fetch
+    ///     let (result, stats) = fetch
     ///         .retry(ExponentialBuilder::default())
-    ///         .adjust(|err, dur| {
-    ///             match err.downcast_ref::<HttpError>() {
-    ///                 Some(v) => {
-    ///                     if let Some(retry_after) = v.headers.get("Retry-After") {
-    ///                         // Parse the Retry-After header and adjust the backoff duration
-    ///                         let retry_after = retry_after.to_str().unwrap_or("0");
-    ///                         let retry_after = retry_after.parse::<u64>().unwrap_or(0);
-    ///                         Some(Duration::from_secs(retry_after))
-    ///                     } else {
-    ///                         dur
-    ///                     }
-    ///                 }
-    ///                 None => dur,
-    ///             }
-    ///         })
-    ///         .await?;
+    ///         .await_with_stats()
+    ///         .await;
+    ///     println!(
+    ///         "fetch finished on attempt {} after {:?}",
+    ///         stats.attempts, stats.elapsed
+    ///     );
+    ///     let content = result?;
     ///     println!("fetch succeeded: {}", content);
     ///
     ///     Ok(())
     /// }
     /// ```
-    pub fn adjust<NAF: FnMut(&E, Option<Duration>) -> Option<Duration>>(
-        self,
-        adjust: NAF,
-    ) -> Retry<B, T, E, Fut, FutureFn, SF, RF, NF, NAF> {
-        Retry {
-            config: self.config.with_adjust(adjust),
-            future_fn: self.future_fn,
-            state: self.state,
-        }
+    #[cfg(feature = "alloc")]
+    pub async fn await_with_stats(mut self) -> (Result<T, E>, RetryStats) {
+        self.config.timed = true;
+        let mut stats = RetryStats::default();
+        let mut retry = pin!(self);
+        let res = poll_fn(|cx| retry.as_mut().poll_retry(cx, &mut stats)).await;
+        stats.attempts = retry.config.attempts;
+        stats.slept = retry.config.slept;
+        stats.elapsed = retry.config.elapsed();
+        (res, stats)
     }
-}
 
-/// State maintains internal state of retry.
-#[derive(Default)]
-enum State<T, E, Fut: Future<Output = Result<T, E>>, SleepFut: Future> {
-    #[default]
-    Idle,
-    Polling(Fut),
-    Sleeping(SleepFut),
-}
-
-impl<B, T, E, Fut, FutureFn, SF, RF, NF, AF> Future
-    for Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF>
-where
-    B: Backoff,
-    Fut: Future<Output = Result<T, E>>,
-    FutureFn: FnMut() -> Fut,
-    SF: Sleeper,
-    RF: FnMut(&E) -> bool,
-    NF: FnMut(&E, Duration),
-    AF: FnMut(&E, Option<Duration>) -> Option<Duration>,
-{
-    type Output = Result<T, E>;
+    /// Poll the retry, reporting the failed attempts to `observer`.
+    pub(crate) fn poll_retry(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        observer: &mut impl RetryObserver<E>,
+    ) -> Poll<Result<T, E>> {
+        self.poll_retry_until(cx, observer, &mut |_: &T| true)
+    }
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    /// Poll the retry, also retrying the values that are not `ready`.
+    ///
+    /// The last value is returned if the retry gives up before one is ready, `observer` is then
+    /// told why it gave up.
+    pub(crate) fn poll_retry_until(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        observer: &mut impl RetryObserver<E>,
+        ready: &mut impl FnMut(&T) -> bool,
+    ) -> Poll<Result<T, E>> {
         // Safety: This is safe because we don't move the `Retry` struct itself,
         // only its internal state.
         //
@@ -357,36 +1217,133 @@ where
         loop {
             match &mut this.state {
                 State::Idle => {
+                    // Safety: This is safe because we don't move the `Retry` struct and the
+                    // cancellation, only its internal state.
+                    if let Poll::Ready(err) = unsafe { poll_cancel(&mut this.cancel, cx) } {
+                        observer.gave_up(GiveUpReason::Cancelled, Duration::ZERO);
+                        return Poll::Ready(Err(err));
+                    }
+                    if let Some(map) = this.deadline_exceeded {
+                        if this.config.deadline_passed() {
+                            observer.gave_up(GiveUpReason::DeadlineExceeded, Duration::ZERO);
+                            return Poll::Ready(Err(map(DeadlineExceeded)));
+                        }
+                    }
+                    this.config.start();
                     let fut = (this.future_fn)();
-                    this.state = State::Polling(fut);
+                    let timer = this
+                        .attempt_timeout
+                        .as_ref()
+                        .map(|timeout| this.config.sleep.sleep(timeout.duration));
+                    this.state = State::Polling(fut, timer);
                     continue;
                 }
-                State::Polling(fut) => {
+                State::Polling(fut, timer) => {
                     // Safety: This is safe because we don't move the `Retry` struct and this fut,
                     // only its internal state.
                     //
                     // We do the exactly same thing like `pin_project` but without depending on it directly.
                     let mut fut = unsafe { Pin::new_unchecked(fut) };
 
-                    match ready!(fut.as_mut().poll(cx)) {
-                        Ok(v) => return Poll::Ready(Ok(v)),
-                        Err(err) => match this.config.decide(&err) {
-                            ControlFlow::Continue(dur) => {
-                                this.state = State::Sleeping(this.config.sleep.sleep(dur));
-                                continue;
+                    let err = match fut.as_mut().poll(cx).map(RetryOutcome::into_result) {
+                        Poll::Ready(Ok(v)) if ready(&v) => return Poll::Ready(Ok(v)),
+                        Poll::Ready(Ok(v)) => {
+                            let duration = this.config.attempt_duration();
+                            let candidate = this.config.backoff.next();
+                            match this.config.settle(candidate, candidate) {
+                                ControlFlow::Continue(dur) => {
+                                    observer.not_ready(duration, dur);
+                                    let sleep = this.config.sleep.sleep(dur);
+                                    this.state = State::Sleeping(sleep, dur, Some(v));
+                                    continue;
+                                }
+                                ControlFlow::Break(reason) => {
+                                    observer.gave_up(reason, duration);
+                                    return Poll::Ready(Ok(v));
+                                }
                             }
-                            ControlFlow::Break(_) => return Poll::Ready(Err(err)),
+                        }
+                        Poll::Ready(Err(err)) => err,
+                        Poll::Pending => match (timer, &this.attempt_timeout) {
+                            (Some(timer), Some(timeout)) => {
+                                // Safety: Same as above, the timer is pinned in the state and never moved.
+                                let timer = unsafe { Pin::new_unchecked(timer) };
+                                ready!(timer.poll(cx));
+                                timeout.error()
+                            }
+                            _ => return Poll::Pending,
                         },
+                    };
+                    let state = this.config.state();
+                    let fut = this.config.retryable.call(&err, &state);
+                    this.state = State::Checking(Some(err), state, fut);
+                    continue;
+                }
+                State::Checking(err, state, fut) => {
+                    // Safety: Same as above, the future is pinned in the state and never moved.
+                    let fut = unsafe { Pin::new_unchecked(fut) };
+                    let decision = ready!(fut.poll(cx));
+                    let (err, state) = (err.take().expect("error must be valid"), *state);
+                    let hint = this.hint.and_then(|hint| hint(&err));
+                    let candidate = match this.config.candidate(decision, hint) {
+                        ControlFlow::Continue(candidate) => candidate,
+                        ControlFlow::Break(reason) => {
+                            observer.gave_up(reason, state.last_attempt_duration);
+                            return Poll::Ready(Err(err));
+                        }
+                    };
+                    let fut = this.config.adjust.call(&err, candidate, &state);
+                    this.state = State::Adjusting(Some(err), state, candidate, fut);
+                    continue;
+                }
+                State::Adjusting(err, state, candidate, fut) => {
+                    // Safety: Same as above, the future is pinned in the state and never moved.
+                    let fut = unsafe { Pin::new_unchecked(fut) };
+                    let adjusted = ready!(fut.poll(cx));
+                    let (err, state) = (err.take().expect("error must be valid"), *state);
+                    match this.config.settle(*candidate, adjusted) {
+                        ControlFlow::Continue(dur) => {
+                            let fut = this.config.notify.call(&err, dur, &state);
+                            this.state = State::Notifying(Some(err), state, dur, fut);
+                            continue;
+                        }
+                        ControlFlow::Break(reason) => {
+                            observer.gave_up(reason, state.last_attempt_duration);
+                            return Poll::Ready(Err(err));
+                        }
                     }
                 }
-                State::Sleeping(sl) => {
+                State::Notifying(err, state, dur, fut) => {
+                    // Safety: Same as above, the future is pinned in the state and never moved.
+                    let fut = unsafe { Pin::new_unchecked(fut) };
+                    ready!(fut.poll(cx));
+                    let (err, dur) = (err.take().expect("error must be valid"), *dur);
+                    observer.retried(err, state.last_attempt_duration, dur);
+                    this.state = State::Sleeping(this.config.sleep.sleep(dur), dur, None);
+                    continue;
+                }
+                State::Sleeping(sl, dur, value) => {
                     // Safety: This is safe because we don't move the `Retry` struct and this fut,
                     // only its internal state.
                     //
                     // We do the exactly same thing like `pin_project` but without depending on it directly.
                     let mut sl = unsafe { Pin::new_unchecked(sl) };
 
+                    // Safety: Same as above, the cancellation is never moved.
+                    if let Poll::Ready(err) = unsafe { poll_cancel(&mut this.cancel, cx) } {
+                        observer.gave_up(GiveUpReason::Cancelled, Duration::ZERO);
+                        return Poll::Ready(Err(err));
+                    }
                     ready!(sl.as_mut().poll(cx));
+                    this.config.record_sleep(*dur);
+                    observer.slept(*dur);
+                    // Keep the last value rather than an error once the deadline is reached.
+                    if let Some(v) = value.take() {
+                        if this.config.deadline_passed() {
+                            observer.gave_up(GiveUpReason::DeadlineExceeded, Duration::ZERO);
+                            return Poll::Ready(Ok(v));
+                        }
+                    }
                     this.state = State::Idle;
                     continue;
                 }
@@ -395,6 +1352,26 @@ where
     }
 }
 
+impl<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF> Future
+    for Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
+where
+    B: Backoff,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
+    FutureFn: FnMut() -> Fut,
+    SF: Sleeper,
+    RF: AsyncWhenHook<E>,
+    NF: AsyncNotifyHook<E>,
+    AF: AsyncAdjustHook<E>,
+    CF: MaybeClock,
+    XF: Future<Output = ()>,
+{
+    type Output = Fut::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.poll_retry(cx, &mut ()).map(RetryOutcome::from_result)
+    }
+}
+
 #[cfg(test)]
 #[cfg(any(feature = "tokio-sleep", feature = "gloo-timers-sleep",))]
 mod default_sleeper_tests {
@@ -428,6 +1405,17 @@ mod default_sleeper_tests {
         assert_eq!("test_query meets error", result.unwrap_err().to_string());
     }
 
+    #[test]
+    async fn test_retry_without_clock() {
+        // The default clock can't be read on wasm32-unknown-unknown, nothing here reads it.
+        let result = always_error
+            .retry(ExponentialBuilder::default().with_min_delay(Duration::from_millis(1)))
+            .notify(|_, _| {})
+            .await;
+
+        assert!(result.is_err());
+    }
+
     #[test]
     async fn test_retry_with_not_retryable_error() {
         let error_times = Mutex::new(0);
@@ -534,6 +1522,8 @@ mod custom_sleeper_tests {
     extern crate alloc;
 
     use alloc::string::ToString;
+    use alloc::vec::Vec;
+    use core::cell::Cell;
     use core::future::ready;
     use core::time::Duration;
 
@@ -543,7 +1533,9 @@ mod custom_sleeper_tests {
     use wasm_bindgen_test::wasm_bindgen_test as test;
 
     use super::*;
+    use crate::ConstantBuilder;
     use crate::ExponentialBuilder;
+    use crate::ManualClock;
 
     async fn always_error() -> anyhow::Result<()> {
         Err(anyhow::anyhow!("test_query meets error"))
@@ -559,4 +1551,348 @@ mod custom_sleeper_tests {
         assert!(result.is_err());
         assert_eq!("test_query meets error", result.unwrap_err().to_string());
     }
+
+    #[test]
+    async fn test_retry_without_timing() {
+        struct UnreadableClock;
+
+        impl Clock for UnreadableClock {
+            type Instant = ();
+
+            fn now(&self) {
+                panic!("the clock is read")
+            }
+
+            fn duration_between(&self, _: (), _: ()) -> Duration {
+                Duration::ZERO
+            }
+        }
+
+        let mut notified = Vec::new();
+        let result = always_error
+            .retry(ConstantBuilder::default().with_max_times(2))
+            .sleep(|_| ready(()))
+            .clock(UnreadableClock)
+            .notify(|_, dur| notified.push(dur))
+            .await;
+
+        // Nothing uses the time, so the clock is never read.
+        assert!(result.is_err());
+        assert_eq!(notified, [Duration::from_secs(1), Duration::from_secs(1)]);
+    }
+
+    #[test]
+    async fn test_retry_with_max_elapsed() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut attempts = 0;
+        let result = (|| {
+            attempts += 1;
+            // Every attempt takes 5s.
+            CLOCK.advance(Duration::from_secs(5));
+            always_error()
+        })
+        .retry(
+            ConstantBuilder::default()
+                .with_delay(Duration::from_secs(10))
+                .without_max_times(),
+        )
+        .sleep(|dur| {
+            CLOCK.advance(dur);
+            ready(())
+        })
+        .clock(&CLOCK)
+        .max_elapsed(Duration::from_secs(40))
+        .await;
+
+        assert!(result.is_err());
+        // The third retry would end at 45s.
+        assert_eq!(attempts, 3);
+        assert_eq!(CLOCK.now(), Duration::from_secs(35));
+    }
+
+    #[test]
+    async fn test_retry_with_state() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut states = Vec::new();
+        let result = (|| {
+            // Every attempt takes 5s.
+            CLOCK.advance(Duration::from_secs(5));
+            always_error()
+        })
+        .retry(
+            ConstantBuilder::default()
+                .with_delay(Duration::from_secs(10))
+                .with_max_times(2),
+        )
+        .sleep(|dur| {
+            CLOCK.advance(dur);
+            ready(())
+        })
+        .clock(&CLOCK)
+        .when_with_state(|_, state| state.last_attempt_duration == Duration::from_secs(5))
+        .notify_with_state(|_, _, state| states.push((state.attempt, state.elapsed, state.slept)))
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(
+            states,
+            [
+                (1, Duration::from_secs(5), Duration::ZERO),
+                (2, Duration::from_secs(20), Duration::from_secs(10)),
+            ]
+        );
+    }
+
+    #[test]
+    async fn test_retry_with_classify() {
+        let mut attempts = 0;
+        let mut sleeps = Vec::new();
+        let result = (|| {
+            attempts += 1;
+            ready(match attempts {
+                1 => Err::<(), _>("unavailable"),
+                2 => Err("rate limited"),
+                3 => Err("conflict"),
+                _ => Err("invalid"),
+            })
+        })
+        .retry(ConstantBuilder::default().with_delay(Duration::from_secs(1)))
+        .sleep(|_| ready(()))
+        .classify(|e| match *e {
+            "unavailable" => RetryDecision::Retry,
+            "rate limited" => RetryDecision::RetryAfter(Duration::from_millis(500)),
+            "conflict" => RetryDecision::RetryNow,
+            _ => RetryDecision::Abort,
+        })
+        .notify(|_: &&str, dur: Duration| sleeps.push(dur))
+        .await;
+
+        assert_eq!(result, Err("invalid"));
+        assert_eq!(attempts, 4);
+        assert_eq!(
+            sleeps,
+            [
+                Duration::from_secs(1),
+                Duration::from_millis(500),
+                Duration::ZERO
+            ]
+        );
+    }
+
+    #[test]
+    async fn test_retry_with_hints() {
+        #[derive(Debug, PartialEq)]
+        struct HintedError(Option<Duration>);
+
+        impl RetryAfterHint for HintedError {
+            fn retry_after(&self) -> Option<Duration> {
+                self.0
+            }
+        }
+
+        let mut decisions = [RetryDecision::Retry, RetryDecision::RetryNow]
+            .into_iter()
+            .cycle();
+        let mut sleeps = Vec::new();
+        let result = (|| ready(Err::<(), _>(HintedError(Some(Duration::from_secs(5))))))
+            .retry(ConstantBuilder::default().with_max_times(2))
+            .sleep(|_| ready(()))
+            .honor_hints()
+            // The decisions of `classify` take precedence over the hints.
+            .classify(|_| decisions.next().unwrap())
+            .notify(|_, dur| sleeps.push(dur))
+            .await;
+
+        assert!(result.is_err());
+        // The hint is clamped to the delay of the constant backoff.
+        assert_eq!(sleeps, [Duration::from_secs(1), Duration::ZERO]);
+    }
+
+    #[test]
+    async fn test_retry_with_deadline() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut attempts = 0;
+        let mut sleeps = Vec::new();
+        let result = (|| {
+            attempts += 1;
+            always_error()
+        })
+        .retry(
+            ConstantBuilder::default()
+                .with_delay(Duration::from_secs(10))
+                .without_max_times(),
+        )
+        .sleep(|dur| {
+            CLOCK.advance(dur);
+            ready(())
+        })
+        .notify(|_, dur| sleeps.push(dur))
+        .clock(&CLOCK)
+        .deadline(Duration::from_secs(25))
+        .await;
+
+        assert!(result.unwrap_err().is::<DeadlineExceeded>());
+        assert_eq!(attempts, 3);
+        // The last sleep is shortened to end at the deadline.
+        assert_eq!(
+            sleeps,
+            [
+                Duration::from_secs(10),
+                Duration::from_secs(10),
+                Duration::from_secs(5)
+            ]
+        );
+        assert_eq!(CLOCK.now(), Duration::from_secs(25));
+    }
+
+    #[test]
+    async fn test_retry_with_cancel_on() {
+        let attempts = Cell::new(0);
+        let result = (|| {
+            attempts.set(attempts.get() + 1);
+            always_error()
+        })
+        .retry(ConstantBuilder::default())
+        // Sleep forever unless aborted.
+        .sleep(|_| core::future::pending::<()>())
+        .cancel_on(poll_fn(|_| {
+            if attempts.get() > 0 {
+                Poll::Ready(())
+            } else {
+                Poll::Pending
+            }
+        }))
+        .await;
+
+        assert!(result.unwrap_err().is::<Cancelled>());
+        assert_eq!(attempts.get(), 1);
+    }
+
+    #[test]
+    async fn test_retry_with_deadline_passed() {
+        static CLOCK: ManualClock = ManualClock::new();
+        CLOCK.advance(Duration::from_secs(10));
+
+        let mut attempts = 0;
+        let result = (|| {
+            attempts += 1;
+            always_error()
+        })
+        .retry(ConstantBuilder::default())
+        .sleep(|_| ready(()))
+        .clock(&CLOCK)
+        .deadline(Duration::from_secs(5))
+        .await;
+
+        assert!(result.unwrap_err().is::<DeadlineExceeded>());
+        assert_eq!(attempts, 0);
+    }
+
+    /// A future that is pending once before resolving to `value`.
+    fn yield_once<V>(value: V) -> impl Future<Output = V> {
+        let mut value = Some(value);
+        let mut yielded = false;
+        poll_fn(move |cx| {
+            if yielded {
+                return Poll::Ready(value.take().expect("value must be valid"));
+            }
+            yielded = true;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        })
+    }
+
+    #[test]
+    async fn test_retry_with_async_hooks() {
+        let attempts = Cell::new(0);
+        let mut delays = Vec::new();
+        let result = (|| {
+            attempts.set(attempts.get() + 1);
+            always_error()
+        })
+        .retry(
+            ConstantBuilder::default()
+                .with_delay(Duration::from_secs(1))
+                .with_max_times(5),
+        )
+        .sleep(|_| ready(()))
+        // Stop retrying after the third attempt.
+        .when_async(|_| yield_once(attempts.get() < 3))
+        .adjust_async(|_, dur| yield_once(dur.map(|dur| dur * 2)))
+        .notify_async(|_, dur| {
+            delays.push(dur);
+            yield_once(())
+        })
+        .await_with_reason()
+        .await;
+
+        assert_eq!(result.unwrap_err().1, GiveUpReason::NotRetryable);
+        assert_eq!(attempts.get(), 3);
+        assert_eq!(delays, [Duration::from_secs(2); 2]);
+    }
+
+    #[test]
+    async fn test_retry_with_reason() {
+        let result = always_error
+            .retry(ConstantBuilder::default())
+            .sleep(|_| ready(()))
+            .when(|_| false)
+            .await_with_reason()
+            .await;
+        assert_eq!(result.unwrap_err().1, GiveUpReason::NotRetryable);
+
+        let result = always_error
+            .retry(ConstantBuilder::default())
+            .sleep(|_| core::future::pending::<()>())
+            .cancel_on(ready(()))
+            .await_with_reason()
+            .await;
+        let (err, reason) = result.unwrap_err();
+        assert!(err.is::<Cancelled>());
+        assert_eq!(reason, GiveUpReason::Cancelled);
+    }
+
+    #[test]
+    async fn test_retry_with_attempt_timeout() {
+        let mut attempts = 0;
+        let mut notified = Vec::new();
+        let result = (|| {
+            attempts += 1;
+            let first = attempts == 1;
+            async move {
+                if first {
+                    // The first attempt never completes.
+                    core::future::pending::<()>().await;
+                }
+                Ok::<_, anyhow::Error>(attempts)
+            }
+        })
+        .retry(ConstantBuilder::default())
+        .sleep(|_| ready(()))
+        .attempt_timeout(Duration::from_secs(1))
+        .notify(|err, _| notified.push(err.to_string()))
+        .await;
+
+        assert_eq!(result.unwrap(), 2);
+        assert_eq!(notified, ["attempt timed out after 1s"]);
+    }
+
+    #[test]
+    async fn test_retry_with_attempt_timeout_exhausted() {
+        let mut attempts = 0;
+        let result = (|| {
+            attempts += 1;
+            async { core::future::pending::<Result<(), Timeout>>().await }
+        })
+        .retry(ConstantBuilder::default().with_max_times(2))
+        .sleep(|_| ready(()))
+        .attempt_timeout_with(Duration::from_secs(1), |err| err)
+        .await;
+
+        assert_eq!(result.unwrap_err().duration(), Duration::from_secs(1));
+        assert_eq!(attempts, 3);
+    }
 }
@@ -1,7 +1,15 @@
+use core::fmt;
+use core::future::Future;
+use core::future::Ready;
+use core::future::ready;
 use core::ops::ControlFlow;
+use core::pin::Pin;
+use core::task::Context;
+use core::task::Poll;
 use core::time::Duration;
 
 use crate::Backoff;
+use crate::clock::MaybeClock;
 
 pub(crate) fn always_retry<E>(_: &E) -> bool {
     true
@@ -13,22 +21,382 @@ pub(crate) fn identity_adjust<E>(_: &E, dur: Option<Duration>) -> Option<Duratio
     dur
 }
 
+/// GiveUpReason tells why a retry stopped retrying and returned an error.
+///
+/// It's returned along with the error by the `with_reason` finishers, for example
+/// [`Retry::await_with_reason`](crate::Retry::await_with_reason).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum GiveUpReason {
+    /// The error is not retryable according to the `when` predicate.
+    NotRetryable,
+    /// The backoff returned no more delay.
+    BackoffExhausted,
+    /// The `adjust` function returned `None` while the backoff had a delay.
+    AdjustReturnedNone,
+    /// Sleeping for the next delay would exceed `max_elapsed`.
+    MaxElapsedExceeded,
+    /// The deadline has been reached, the error is a [`DeadlineExceeded`](crate::DeadlineExceeded).
+    DeadlineExceeded,
+    /// The retry has been cancelled, the error is a [`Cancelled`](crate::Cancelled).
+    Cancelled,
+}
+
+impl GiveUpReason {
+    /// Whether the retry stopped without retrying an error that can't be retried.
+    ///
+    /// All other reasons mean the retry gave up after running out of retries or time.
+    pub fn is_permanent(&self) -> bool {
+        matches!(self, GiveUpReason::NotRetryable)
+    }
+}
+
+impl fmt::Display for GiveUpReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let reason = match self {
+            GiveUpReason::NotRetryable => "error is not retryable",
+            GiveUpReason::BackoffExhausted => "backoff exhausted",
+            GiveUpReason::AdjustReturnedNone => "adjust returned no delay",
+            GiveUpReason::MaxElapsedExceeded => "max elapsed time exceeded",
+            GiveUpReason::DeadlineExceeded => "deadline exceeded",
+            GiveUpReason::Cancelled => "cancelled",
+        };
+        f.write_str(reason)
+    }
+}
+
+/// RetryDecision tells how to handle a failed attempt, see [`Retry::classify`](crate::Retry::classify).
+///
+/// The decisions retrying the attempt still consume a delay of the backoff, so the retry gives
+/// up once the backoff is exhausted whatever the delay used.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RetryDecision {
+    /// Don't retry, the error is returned.
+    Abort,
+    /// Retry after the delay of the backoff.
+    Retry,
+    /// Retry after the given delay instead of the one of the backoff, clamped to the maximum
+    /// delay of the backoff builder if it has one.
+    RetryAfter(Duration),
+    /// Retry immediately.
+    RetryNow,
+}
+
+impl From<bool> for RetryDecision {
+    /// Retry with the backoff if `retryable`, abort otherwise.
+    fn from(retryable: bool) -> Self {
+        if retryable {
+            RetryDecision::Retry
+        } else {
+            RetryDecision::Abort
+        }
+    }
+}
+
+/// RetryState describes the progress of a retry when deciding whether to retry an error.
+///
+/// It's passed to the hooks set with the `with_state` methods, for example
+/// [`Retry::notify_with_state`](crate::Retry::notify_with_state).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct RetryState {
+    /// The number of the attempt that failed, starting at 1.
+    pub attempt: usize,
+    /// The time elapsed since the start of the first attempt, measured by the clock of the retry.
+    pub elapsed: Duration,
+    /// The total delay slept before the attempt that failed.
+    pub slept: Duration,
+    /// The time spent in the attempt that failed.
+    pub last_attempt_duration: Duration,
+}
+
+/// A hook deciding whether an error is retryable, see [`Retry::when`](crate::Retry::when).
+#[doc(hidden)]
+pub trait WhenHook<E> {
+    fn call(&mut self, err: &E, state: &RetryState) -> RetryDecision;
+}
+
+/// A hook notified before sleeping, see [`Retry::notify`](crate::Retry::notify).
+#[doc(hidden)]
+pub trait NotifyHook<E> {
+    fn call(&mut self, err: &E, dur: Duration, state: &RetryState);
+}
+
+/// A hook adjusting the delay before retrying, see [`Retry::adjust`](crate::Retry::adjust).
+#[doc(hidden)]
+pub trait AdjustHook<E> {
+    fn call(&mut self, err: &E, dur: Option<Duration>, state: &RetryState) -> Option<Duration>;
+}
+
+/// A hook receiving the [`RetryState`], set with the `with_state` methods.
+#[doc(hidden)]
+pub struct WithState<F>(pub(crate) F);
+
+/// A hook classifying the errors, set with the `classify` methods.
+#[doc(hidden)]
+pub struct Classify<F>(pub(crate) F);
+
+impl<E, F: FnMut(&E) -> bool> WhenHook<E> for F {
+    fn call(&mut self, err: &E, _: &RetryState) -> RetryDecision {
+        self(err).into()
+    }
+}
+
+impl<E, F: FnMut(&E, &RetryState) -> bool> WhenHook<E> for WithState<F> {
+    fn call(&mut self, err: &E, state: &RetryState) -> RetryDecision {
+        (self.0)(err, state).into()
+    }
+}
+
+impl<E, F: FnMut(&E) -> RetryDecision> WhenHook<E> for Classify<F> {
+    fn call(&mut self, err: &E, _: &RetryState) -> RetryDecision {
+        (self.0)(err)
+    }
+}
+
+impl<E, F: FnMut(&E, Duration)> NotifyHook<E> for F {
+    fn call(&mut self, err: &E, dur: Duration, _: &RetryState) {
+        self(err, dur)
+    }
+}
+
+impl<E, F: FnMut(&E, Duration, &RetryState)> NotifyHook<E> for WithState<F> {
+    fn call(&mut self, err: &E, dur: Duration, state: &RetryState) {
+        (self.0)(err, dur, state)
+    }
+}
+
+impl<E, F: FnMut(&E, Option<Duration>) -> Option<Duration>> AdjustHook<E> for F {
+    fn call(&mut self, err: &E, dur: Option<Duration>, _: &RetryState) -> Option<Duration> {
+        self(err, dur)
+    }
+}
+
+impl<E, F: FnMut(&E, Option<Duration>, &RetryState) -> Option<Duration>> AdjustHook<E>
+    for WithState<F>
+{
+    fn call(&mut self, err: &E, dur: Option<Duration>, state: &RetryState) -> Option<Duration> {
+        (self.0)(err, dur, state)
+    }
+}
+
+/// A hook deciding whether an error is retryable, possibly asynchronously, see
+/// [`Retry::when_async`](crate::Retry::when_async).
+#[doc(hidden)]
+pub trait AsyncWhenHook<E> {
+    type Future: Future<Output = RetryDecision>;
+
+    fn call(&mut self, err: &E, state: &RetryState) -> Self::Future;
+}
+
+/// A hook notified before sleeping, possibly asynchronously, see
+/// [`Retry::notify_async`](crate::Retry::notify_async).
+#[doc(hidden)]
+pub trait AsyncNotifyHook<E> {
+    type Future: Future<Output = ()>;
+
+    fn call(&mut self, err: &E, dur: Duration, state: &RetryState) -> Self::Future;
+}
+
+/// A hook adjusting the delay before retrying, possibly asynchronously, see
+/// [`Retry::adjust_async`](crate::Retry::adjust_async).
+#[doc(hidden)]
+pub trait AsyncAdjustHook<E> {
+    type Future: Future<Output = Option<Duration>>;
+
+    fn call(&mut self, err: &E, dur: Option<Duration>, state: &RetryState) -> Self::Future;
+}
+
+/// A hook returning a future, set with the `async` methods.
+#[doc(hidden)]
+pub struct Async<F>(pub(crate) F);
+
+impl<E, F: FnMut(&E) -> bool> AsyncWhenHook<E> for F {
+    type Future = Ready<RetryDecision>;
+
+    fn call(&mut self, err: &E, state: &RetryState) -> Self::Future {
+        ready(WhenHook::call(self, err, state))
+    }
+}
+
+impl<E, F: FnMut(&E, &RetryState) -> bool> AsyncWhenHook<E> for WithState<F> {
+    type Future = Ready<RetryDecision>;
+
+    fn call(&mut self, err: &E, state: &RetryState) -> Self::Future {
+        ready(WhenHook::call(self, err, state))
+    }
+}
+
+impl<E, F: FnMut(&E) -> RetryDecision> AsyncWhenHook<E> for Classify<F> {
+    type Future = Ready<RetryDecision>;
+
+    fn call(&mut self, err: &E, state: &RetryState) -> Self::Future {
+        ready(WhenHook::call(self, err, state))
+    }
+}
+
+impl<E, F, Fut> AsyncWhenHook<E> for Async<F>
+where
+    F: FnMut(&E) -> Fut,
+    Fut: Future<Output = bool>,
+{
+    type Future = IntoDecision<Fut>;
+
+    fn call(&mut self, err: &E, _: &RetryState) -> Self::Future {
+        IntoDecision((self.0)(err))
+    }
+}
+
+/// The future of an async `when` hook, turning whether the error is retryable into a decision.
+#[doc(hidden)]
+pub struct IntoDecision<Fut>(Fut);
+
+impl<Fut: Future<Output = bool>> Future for IntoDecision<Fut> {
+    type Output = RetryDecision;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<RetryDecision> {
+        // Safety: This is safe because we don't move the `IntoDecision` struct and the future,
+        // only project the pin to it.
+        let fut = unsafe { self.map_unchecked_mut(|this| &mut this.0) };
+        fut.poll(cx).map(RetryDecision::from)
+    }
+}
+
+impl<E, F: FnMut(&E, Duration)> AsyncNotifyHook<E> for F {
+    type Future = Ready<()>;
+
+    fn call(&mut self, err: &E, dur: Duration, state: &RetryState) -> Self::Future {
+        NotifyHook::call(self, err, dur, state);
+        ready(())
+    }
+}
+
+impl<E, F: FnMut(&E, Duration, &RetryState)> AsyncNotifyHook<E> for WithState<F> {
+    type Future = Ready<()>;
+
+    fn call(&mut self, err: &E, dur: Duration, state: &RetryState) -> Self::Future {
+        NotifyHook::call(self, err, dur, state);
+        ready(())
+    }
+}
+
+impl<E, F, Fut> AsyncNotifyHook<E> for Async<F>
+where
+    F: FnMut(&E, Duration) -> Fut,
+    Fut: Future<Output = ()>,
+{
+    type Future = Fut;
+
+    fn call(&mut self, err: &E, dur: Duration, _: &RetryState) -> Self::Future {
+        (self.0)(err, dur)
+    }
+}
+
+impl<E, F: FnMut(&E, Option<Duration>) -> Option<Duration>> AsyncAdjustHook<E> for F {
+    type Future = Ready<Option<Duration>>;
+
+    fn call(&mut self, err: &E, dur: Option<Duration>, state: &RetryState) -> Self::Future {
+        ready(AdjustHook::call(self, err, dur, state))
+    }
+}
+
+impl<E, F: FnMut(&E, Option<Duration>, &RetryState) -> Option<Duration>> AsyncAdjustHook<E>
+    for WithState<F>
+{
+    type Future = Ready<Option<Duration>>;
+
+    fn call(&mut self, err: &E, dur: Option<Duration>, state: &RetryState) -> Self::Future {
+        ready(AdjustHook::call(self, err, dur, state))
+    }
+}
+
+impl<E, F, Fut> AsyncAdjustHook<E> for Async<F>
+where
+    F: FnMut(&E, Option<Duration>) -> Fut,
+    Fut: Future<Output = Option<Duration>>,
+{
+    type Future = Fut;
+
+    fn call(&mut self, err: &E, dur: Option<Duration>, _: &RetryState) -> Self::Future {
+        (self.0)(err, dur)
+    }
+}
+
+/// Observe the failed attempts of a retry, used to report on the retry once it's done.
+pub(crate) trait RetryObserver<E> {
+    /// The attempt failed with `err` after `duration`, and will be retried after `delay`.
+    fn retried(&mut self, err: E, duration: Duration, delay: Duration);
+
+    /// The attempt returned a value that is not ready after `duration`, and will be retried after
+    /// `delay`.
+    fn not_ready(&mut self, duration: Duration, delay: Duration);
+
+    /// The sleep of `delay` before the next attempt is over, a sleep cut short by a cancellation
+    /// never is.
+    fn slept(&mut self, delay: Duration);
+
+    /// The retry gave up for `reason`, after an attempt that took `duration` or zero if the
+    /// retry gave up before starting an attempt.
+    fn gave_up(&mut self, reason: GiveUpReason, duration: Duration);
+}
+
+/// Observing nothing, for the retries awaited directly.
+impl<E> RetryObserver<E> for () {
+    fn retried(&mut self, _: E, _: Duration, _: Duration) {}
+
+    fn not_ready(&mut self, _: Duration, _: Duration) {}
+
+    fn slept(&mut self, _: Duration) {}
+
+    fn gave_up(&mut self, _: GiveUpReason, _: Duration) {}
+}
+
+/// Observing the reason the retry gave up, for the `with_reason` finishers.
+impl<E> RetryObserver<E> for Option<GiveUpReason> {
+    fn retried(&mut self, _: E, _: Duration, _: Duration) {}
+
+    fn not_ready(&mut self, _: Duration, _: Duration) {}
+
+    fn slept(&mut self, _: Duration) {}
+
+    fn gave_up(&mut self, reason: GiveUpReason, _: Duration) {
+        *self = Some(reason);
+    }
+}
+
 /// Shared configuration for retry executors.
-pub(crate) struct RetryConfig<B, Sleep, RetryFn, NotifyFn, AdjustFn> {
+pub(crate) struct RetryConfig<B, Sleep, RetryFn, NotifyFn, AdjustFn, Clk: MaybeClock> {
     pub(crate) backoff: B,
     pub(crate) sleep: Sleep,
     pub(crate) retryable: RetryFn,
     pub(crate) notify: NotifyFn,
     pub(crate) adjust: AdjustFn,
+    pub(crate) clock: Clk,
+    pub(crate) max_elapsed: Option<Duration>,
+    /// The maximum delay of the backoff, the hints of the errors are clamped to it.
+    pub(crate) max_delay: Option<Duration>,
+    pub(crate) deadline: Option<Clk::Instant>,
+    /// Whether the attempts are timed, the clock is only read when something uses the time.
+    pub(crate) timed: bool,
+    started_at: Option<Clk::Instant>,
+    attempt_started_at: Option<Clk::Instant>,
+    /// The number of attempts started.
+    pub(crate) attempts: usize,
+    /// The total delay of the sleeps.
+    pub(crate) slept: Duration,
 }
 
-impl<B, Sleep, RetryFn, NotifyFn, AdjustFn> RetryConfig<B, Sleep, RetryFn, NotifyFn, AdjustFn> {
+impl<B, Sleep, RetryFn, NotifyFn, AdjustFn, Clk: MaybeClock>
+    RetryConfig<B, Sleep, RetryFn, NotifyFn, AdjustFn, Clk>
+{
     pub(crate) fn new(
         backoff: B,
+        max_delay: Option<Duration>,
         sleep: Sleep,
         retryable: RetryFn,
         notify: NotifyFn,
         adjust: AdjustFn,
+        clock: Clk,
     ) -> Self {
         RetryConfig {
             backoff,
@@ -36,74 +404,279 @@ impl<B, Sleep, RetryFn, NotifyFn, AdjustFn> RetryConfig<B, Sleep, RetryFn, Notif
             retryable,
             notify,
             adjust,
+            clock,
+            max_elapsed: None,
+            max_delay,
+            deadline: None,
+            timed: false,
+            started_at: None,
+            attempt_started_at: None,
+            attempts: 0,
+            slept: Duration::ZERO,
         }
     }
 
-    pub(crate) fn with_sleep<S>(self, sleep: S) -> RetryConfig<B, S, RetryFn, NotifyFn, AdjustFn> {
+    /// Time the attempts, for the limits, hooks and reports using the time spent.
+    pub(crate) fn timed(mut self) -> Self {
+        self.timed = true;
+        self
+    }
+
+    pub(crate) fn with_sleep<S>(
+        self,
+        sleep: S,
+    ) -> RetryConfig<B, S, RetryFn, NotifyFn, AdjustFn, Clk> {
         RetryConfig {
             backoff: self.backoff,
             sleep,
             retryable: self.retryable,
             notify: self.notify,
             adjust: self.adjust,
+            clock: self.clock,
+            max_elapsed: self.max_elapsed,
+            max_delay: self.max_delay,
+            deadline: self.deadline,
+            timed: self.timed,
+            started_at: self.started_at,
+            attempt_started_at: self.attempt_started_at,
+            attempts: self.attempts,
+            slept: self.slept,
         }
     }
 
     pub(crate) fn with_retryable<R>(
         self,
         retryable: R,
-    ) -> RetryConfig<B, Sleep, R, NotifyFn, AdjustFn> {
+    ) -> RetryConfig<B, Sleep, R, NotifyFn, AdjustFn, Clk> {
         RetryConfig {
             backoff: self.backoff,
             sleep: self.sleep,
             retryable,
             notify: self.notify,
             adjust: self.adjust,
+            clock: self.clock,
+            max_elapsed: self.max_elapsed,
+            max_delay: self.max_delay,
+            deadline: self.deadline,
+            timed: self.timed,
+            started_at: self.started_at,
+            attempt_started_at: self.attempt_started_at,
+            attempts: self.attempts,
+            slept: self.slept,
         }
     }
 
-    pub(crate) fn with_notify<N>(self, notify: N) -> RetryConfig<B, Sleep, RetryFn, N, AdjustFn> {
+    pub(crate) fn with_notify<N>(
+        self,
+        notify: N,
+    ) -> RetryConfig<B, Sleep, RetryFn, N, AdjustFn, Clk> {
         RetryConfig {
             backoff: self.backoff,
             sleep: self.sleep,
             retryable: self.retryable,
             notify,
             adjust: self.adjust,
+            clock: self.clock,
+            max_elapsed: self.max_elapsed,
+            max_delay: self.max_delay,
+            deadline: self.deadline,
+            timed: self.timed,
+            started_at: self.started_at,
+            attempt_started_at: self.attempt_started_at,
+            attempts: self.attempts,
+            slept: self.slept,
         }
     }
 
-    pub(crate) fn with_adjust<A>(self, adjust: A) -> RetryConfig<B, Sleep, RetryFn, NotifyFn, A> {
+    pub(crate) fn with_adjust<A>(
+        self,
+        adjust: A,
+    ) -> RetryConfig<B, Sleep, RetryFn, NotifyFn, A, Clk> {
         RetryConfig {
             backoff: self.backoff,
             sleep: self.sleep,
             retryable: self.retryable,
             notify: self.notify,
             adjust,
+            clock: self.clock,
+            max_elapsed: self.max_elapsed,
+            max_delay: self.max_delay,
+            deadline: self.deadline,
+            timed: self.timed,
+            started_at: self.started_at,
+            attempt_started_at: self.attempt_started_at,
+            attempts: self.attempts,
+            slept: self.slept,
+        }
+    }
+
+    /// Replace the clock, the instants measured by the previous clock, including the deadline, are dropped.
+    pub(crate) fn with_clock<C: MaybeClock>(
+        self,
+        clock: C,
+    ) -> RetryConfig<B, Sleep, RetryFn, NotifyFn, AdjustFn, C> {
+        RetryConfig {
+            backoff: self.backoff,
+            sleep: self.sleep,
+            retryable: self.retryable,
+            notify: self.notify,
+            adjust: self.adjust,
+            clock,
+            max_elapsed: self.max_elapsed,
+            max_delay: self.max_delay,
+            deadline: None,
+            timed: self.timed,
+            started_at: None,
+            attempt_started_at: None,
+            attempts: 0,
+            slept: Duration::ZERO,
+        }
+    }
+
+    /// Record the start of the attempt, and of the retries for the first one, must be called before every attempt.
+    ///
+    /// The clock is only read if the attempts are timed, since it can't be read on every platform,
+    /// for example `std::time::Instant::now` panics on `wasm32-unknown-unknown`.
+    pub(crate) fn start(&mut self) {
+        if self.timed {
+            let now = self.clock.now();
+            if self.started_at.is_none() {
+                self.started_at = Some(now);
+            }
+            self.attempt_started_at = Some(now);
+        }
+        self.attempts += 1;
+    }
+
+    /// Return the time elapsed since the start of the first attempt.
+    pub(crate) fn elapsed(&self) -> Duration {
+        match self.started_at {
+            Some(started_at) => self.clock.duration_between(started_at, self.clock.now()),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Return the time elapsed since the start of the current attempt.
+    pub(crate) fn attempt_duration(&self) -> Duration {
+        match self.attempt_started_at {
+            Some(started_at) => self.clock.duration_between(started_at, self.clock.now()),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Return the state of the retry once the current attempt failed.
+    pub(crate) fn state(&self) -> RetryState {
+        RetryState {
+            attempt: self.attempts,
+            elapsed: self.elapsed(),
+            slept: self.slept,
+            last_attempt_duration: self.attempt_duration(),
+        }
+    }
+
+    /// Count the time slept before the next attempt, once the sleep is over.
+    ///
+    /// A sleep cut short by a cancellation is never over, and is not counted.
+    pub(crate) fn record_sleep(&mut self, dur: Duration) {
+        self.slept = self.slept.saturating_add(dur);
+    }
+
+    /// Check if the deadline has passed, no attempt can start after it.
+    pub(crate) fn deadline_passed(&self) -> bool {
+        match self.deadline {
+            Some(deadline) => {
+                self.clock.duration_between(self.clock.now(), deadline) == Duration::ZERO
+            }
+            None => false,
+        }
+    }
+
+    /// Shorten `dur` so that sleeping for it ends at the deadline at the latest.
+    fn until_deadline(&self, dur: Duration) -> Duration {
+        match self.deadline {
+            Some(deadline) => dur.min(self.clock.duration_between(self.clock.now(), deadline)),
+            None => dur,
+        }
+    }
+
+    /// Check if sleeping for `dur` keeps the retries within the time limits.
+    fn within_limits(&self, dur: Duration) -> bool {
+        match self.max_elapsed {
+            Some(max_elapsed) => self.elapsed().saturating_add(dur) <= max_elapsed,
+            None => true,
         }
     }
 }
 
-impl<B, Sleep, RetryFn, NotifyFn, AdjustFn> RetryConfig<B, Sleep, RetryFn, NotifyFn, AdjustFn>
+impl<B, Sleep, RetryFn, NotifyFn, AdjustFn, Clk>
+    RetryConfig<B, Sleep, RetryFn, NotifyFn, AdjustFn, Clk>
 where
     B: Backoff,
+    Clk: MaybeClock,
 {
-    pub(crate) fn decide<E>(&mut self, err: &E) -> ControlFlow<(), Duration>
-    where
-        RetryFn: FnMut(&E) -> bool,
-        NotifyFn: FnMut(&E, Duration),
-        AdjustFn: FnMut(&E, Option<Duration>) -> Option<Duration>,
-    {
-        if !(self.retryable)(err) {
-            return ControlFlow::Break(());
+    /// Clamp a delay chosen instead of the one of the backoff to its maximum delay.
+    fn clamp(&self, dur: Duration) -> Duration {
+        match self.max_delay {
+            Some(max_delay) => dur.min(max_delay),
+            None => dur,
         }
+    }
 
-        let candidate = self.backoff.next();
-        match (self.adjust)(err, candidate) {
-            Some(dur) => {
-                (self.notify)(err, dur);
-                ControlFlow::Continue(dur)
-            }
-            None => ControlFlow::Break(()),
+    /// Turn the decision on a failed attempt into the delay proposed before adjusting it.
+    ///
+    /// The decisions retrying the attempt consume a delay of the backoff, `None` once it's exhausted.
+    /// `hint` is the delay hinted by the error, it replaces the delay of the backoff for
+    /// [`RetryDecision::Retry`]. The delays replacing the one of the backoff are clamped to its
+    /// maximum delay.
+    pub(crate) fn candidate(
+        &mut self,
+        decision: RetryDecision,
+        hint: Option<Duration>,
+    ) -> ControlFlow<GiveUpReason, Option<Duration>> {
+        let candidate = match decision {
+            RetryDecision::Abort => return ControlFlow::Break(GiveUpReason::NotRetryable),
+            RetryDecision::Retry => self.backoff.next().map(|dur| match hint {
+                Some(hint) => self.clamp(hint),
+                None => dur,
+            }),
+            RetryDecision::RetryAfter(dur) => self.backoff.next().map(|_| self.clamp(dur)),
+            RetryDecision::RetryNow => self.backoff.next().map(|_| Duration::ZERO),
+        };
+        ControlFlow::Continue(candidate)
+    }
+
+    /// Settle the delay before the next attempt once adjusted, or the reason to give up.
+    ///
+    /// `candidate` is the delay proposed by the backoff.
+    pub(crate) fn settle(
+        &mut self,
+        candidate: Option<Duration>,
+        adjusted: Option<Duration>,
+    ) -> ControlFlow<GiveUpReason, Duration> {
+        match adjusted {
+            Some(dur) if self.within_limits(dur) => ControlFlow::Continue(self.until_deadline(dur)),
+            Some(_) => ControlFlow::Break(GiveUpReason::MaxElapsedExceeded),
+            None if candidate.is_none() => ControlFlow::Break(GiveUpReason::BackoffExhausted),
+            None => ControlFlow::Break(GiveUpReason::AdjustReturnedNone),
         }
     }
+
+    pub(crate) fn decide<E>(
+        &mut self,
+        err: &E,
+        hint: Option<fn(&E) -> Option<Duration>>,
+    ) -> ControlFlow<GiveUpReason, Duration>
+    where
+        RetryFn: WhenHook<E>,
+        NotifyFn: NotifyHook<E>,
+        AdjustFn: AdjustHook<E>,
+    {
+        let state = self.state();
+        let decision = self.retryable.call(err, &state);
+        let candidate = self.candidate(decision, hint.and_then(|hint| hint(err)))?;
+        let adjusted = self.adjust.call(err, candidate, &state);
+        let dur = self.settle(candidate, adjusted)?;
+        self.notify.call(err, dur, &state);
+        ControlFlow::Continue(dur)
+    }
 }
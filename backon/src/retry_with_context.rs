@@ -1,20 +1,46 @@
 use core::future::Future;
+use core::future::Pending;
+use core::future::poll_fn;
 use core::ops::ControlFlow;
 use core::pin::Pin;
+use core::pin::pin;
 use core::task::Context;
 use core::task::Poll;
 use core::task::ready;
 use core::time::Duration;
 
 use crate::Backoff;
+use crate::Cancelled;
+use crate::Clock;
+#[cfg(feature = "alloc")]
+use crate::CollectErrors;
+use crate::DeadlineExceeded;
+use crate::DefaultClock;
 use crate::DefaultSleeper;
+use crate::GiveUpReason;
+use crate::RetryAfterHint;
+use crate::RetryDecision;
+use crate::RetryState;
 use crate::Sleeper;
+use crate::Timeout;
 use crate::backoff::BackoffBuilder;
+use crate::cancel::CancelOn;
+use crate::cancel::poll_cancel;
+use crate::clock::MaybeClock;
+use crate::clock::maybe_instant;
+use crate::retry_core::Async;
+use crate::retry_core::AsyncAdjustHook;
+use crate::retry_core::AsyncNotifyHook;
+use crate::retry_core::AsyncWhenHook;
+use crate::retry_core::Classify;
 use crate::retry_core::RetryConfig;
+use crate::retry_core::RetryObserver;
+use crate::retry_core::WithState;
 use crate::retry_core::always_retry;
 use crate::retry_core::identity_adjust;
 use crate::retry_core::noop_notify;
 use crate::sleep::MaybeSleeper;
+use crate::timeout::AttemptTimeout;
 
 /// `RetryableWithContext` adds retry support for functions that produce futures with results
 /// and context.
@@ -100,7 +126,8 @@ where
     FutureFn: FnMut(Ctx) -> Fut,
 {
     fn retry(self, builder: B) -> RetryWithContext<B::Backoff, T, E, Ctx, Fut, FutureFn> {
-        RetryWithContext::new(self, builder.build())
+        let max_delay = builder.max_delay();
+        RetryWithContext::new(self, builder.build(), max_delay)
     }
 }
 
@@ -113,13 +140,19 @@ pub struct RetryWithContext<
     Fut: Future<Output = (Ctx, Result<T, E>)>,
     FutureFn: FnMut(Ctx) -> Fut,
     SF: MaybeSleeper = DefaultSleeper,
-    RF = fn(&E) -> bool,
-    NF = fn(&E, Duration),
-    AF = fn(&E, Option<Duration>) -> Option<Duration>,
+    RF: AsyncWhenHook<E> = fn(&E) -> bool,
+    NF: AsyncNotifyHook<E> = fn(&E, Duration),
+    AF: AsyncAdjustHook<E> = fn(&E, Option<Duration>) -> Option<Duration>,
+    CF: MaybeClock = DefaultClock,
+    XF = Pending<()>,
 > {
-    config: RetryConfig<B, SF, RF, NF, AF>,
+    config: RetryConfig<B, SF, RF, NF, AF, CF>,
     future_fn: FutureFn,
-    state: State<T, E, Ctx, Fut, SF::Sleep>,
+    attempt_timeout: Option<ContextTimeout<E, Ctx>>,
+    deadline_exceeded: Option<fn(DeadlineExceeded) -> E>,
+    hint: Option<fn(&E) -> Option<Duration>>,
+    cancel: Option<CancelOn<XF, E>>,
+    state: StateOf<T, E, Ctx, Fut, SF, RF, NF, AF>,
 }
 
 impl<B, T, E, Ctx, Fut, FutureFn> RetryWithContext<B, T, E, Ctx, Fut, FutureFn>
@@ -129,31 +162,39 @@ where
     FutureFn: FnMut(Ctx) -> Fut,
 {
     /// Create a new retry.
-    fn new(future_fn: FutureFn, backoff: B) -> Self {
+    fn new(future_fn: FutureFn, backoff: B, max_delay: Option<Duration>) -> Self {
         RetryWithContext {
             config: RetryConfig::new(
                 backoff,
+                max_delay,
                 DefaultSleeper::default(),
                 always_retry::<E>,
                 noop_notify::<E>,
                 identity_adjust::<E>,
+                DefaultClock::default(),
             ),
             future_fn,
+            attempt_timeout: None,
+            deadline_exceeded: None,
+            hint: None,
+            cancel: None,
             state: State::Idle(None),
         }
     }
 }
 
-impl<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF>
-    RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF>
+impl<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
+    RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
 where
     B: Backoff,
     Fut: Future<Output = (Ctx, Result<T, E>)>,
     FutureFn: FnMut(Ctx) -> Fut,
     SF: MaybeSleeper,
-    RF: FnMut(&E) -> bool,
-    NF: FnMut(&E, Duration),
-    AF: FnMut(&E, Option<Duration>) -> Option<Duration>,
+    RF: AsyncWhenHook<E>,
+    NF: AsyncNotifyHook<E>,
+    AF: AsyncAdjustHook<E>,
+    CF: MaybeClock,
+    XF: Future<Output = ()>,
 {
     /// Set the sleeper for retrying.
     ///
@@ -163,7 +204,7 @@ where
     pub fn sleep<SN: Sleeper>(
         self,
         sleep_fn: SN,
-    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SN, RF, NF, AF> {
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SN, RF, NF, AF, CF, XF> {
         assert!(
             matches!(self.state, State::Idle(None)),
             "sleep must be set before context"
@@ -172,6 +213,10 @@ where
         RetryWithContext {
             config: self.config.with_sleep(sleep_fn),
             future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
             state: State::Idle(None),
         }
     }
@@ -182,10 +227,14 @@ where
     pub fn context(
         self,
         context: Ctx,
-    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF> {
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XF> {
         RetryWithContext {
             config: self.config,
             future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
             state: State::Idle(Some(context)),
         }
     }
@@ -222,11 +271,81 @@ where
     pub fn when<RN: FnMut(&E) -> bool>(
         self,
         retryable: RN,
-    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RN, NF, AF> {
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RN, NF, AF, CF, XF> {
         RetryWithContext {
             config: self.config.with_retryable(retryable),
             future_fn: self.future_fn,
-            state: self.state,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
+        }
+    }
+
+    /// Set the conditions for retrying, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`RetryWithContext::when`], and the function also receives the progress of the retry,
+    /// for example to only retry some errors on the first attempts.
+    #[allow(clippy::type_complexity)]
+    pub fn when_with_state<RN: FnMut(&E, &RetryState) -> bool>(
+        self,
+        retryable: RN,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, WithState<RN>, NF, AF, CF, XF> {
+        RetryWithContext {
+            config: self.config.timed().with_retryable(WithState(retryable)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
+        }
+    }
+
+    /// Set how to handle the errors, with a function classifying them into a [`RetryDecision`].
+    ///
+    /// This replaces [`RetryWithContext::when`], and can also override the delay of the backoff without
+    /// matching the error again in [`RetryWithContext::adjust`]. The delay is still passed to `adjust`
+    /// afterwards, and the retry gives up once the backoff is exhausted whatever the decision.
+    #[allow(clippy::type_complexity)]
+    pub fn classify<RN: FnMut(&E) -> RetryDecision>(
+        self,
+        classify: RN,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, Classify<RN>, NF, AF, CF, XF> {
+        RetryWithContext {
+            config: self.config.with_retryable(Classify(classify)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
+        }
+    }
+
+    /// Set the conditions for retrying, with a function returning a future.
+    ///
+    /// This is the same as [`RetryWithContext::when`] for conditions that need to await, for example to
+    /// check a feature flag. The future must not borrow the error, so the function should
+    /// extract what it needs from it first.
+    #[allow(clippy::type_complexity)]
+    pub fn when_async<RN, RFut>(
+        self,
+        retryable: RN,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, Async<RN>, NF, AF, CF, XF>
+    where
+        RN: FnMut(&E) -> RFut,
+        RFut: Future<Output = bool>,
+    {
+        RetryWithContext {
+            config: self.config.with_retryable(Async(retryable)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
         }
     }
 
@@ -268,36 +387,420 @@ where
     pub fn notify<NN: FnMut(&E, Duration)>(
         self,
         notify: NN,
-    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NN, AF> {
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NN, AF, CF, XF> {
         RetryWithContext {
             config: self.config.with_notify(notify),
             future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
+        }
+    }
+
+    /// Set to notify for all retry attempts, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`RetryWithContext::notify`], and the function also receives the progress of the retry,
+    /// for example to log the attempt number alongside the error.
+    #[allow(clippy::type_complexity)]
+    pub fn notify_with_state<NN: FnMut(&E, Duration, &RetryState)>(
+        self,
+        notify: NN,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, WithState<NN>, AF, CF, XF> {
+        RetryWithContext {
+            config: self.config.timed().with_notify(WithState(notify)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
+        }
+    }
+
+    /// Set to notify for all retry attempts, with a function returning a future.
+    ///
+    /// This is the same as [`RetryWithContext::notify`] for notifications that need to await, for example
+    /// to write an audit record. The retry sleeps once the future completed.
+    #[allow(clippy::type_complexity)]
+    pub fn notify_async<NN, NFut>(
+        self,
+        notify: NN,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, Async<NN>, AF, CF, XF>
+    where
+        NN: FnMut(&E, Duration) -> NFut,
+        NFut: Future<Output = ()>,
+    {
+        RetryWithContext {
+            config: self.config.with_notify(Async(notify)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
+        }
+    }
+
+    /// Sets the function to adjust the backoff duration for retry attempts.
+    ///
+    /// This works like [`Retry::adjust`](crate::Retry::adjust): the function receives the error and the
+    /// delay proposed by the backoff, and returns the delay to sleep instead, or `None` to stop retrying.
+    ///
+    /// # Examples
+    ///
+    /// ```no_run
+    /// use core::time::Duration;
+    ///
+    /// use anyhow::anyhow;
+    /// use anyhow::Result;
+    /// use backon::ExponentialBuilder;
+    /// use backon::RetryableWithContext;
+    ///
+    /// struct Test;
+    ///
+    /// impl Test {
+    ///     async fn hello(&mut self) -> Result<usize> {
+    ///         Err(anyhow!("rate limited"))
+    ///     }
+    /// }
+    ///
+    /// #[tokio::main(flavor = "current_thread")]
+    /// async fn main() -> Result<()> {
+    ///     let (_, result) = {
+    ///         |mut v: Test| async {
+    ///             let res = v.hello().await;
+    ///             (v, res)
+    ///         }
+    ///     }
+    ///     .retry(ExponentialBuilder::default())
+    ///     .context(Test)
+    ///     .adjust(|err, dur| {
+    ///         if err.to_string() == "rate limited" {
+    ///             Some(Duration::from_secs(5))
+    ///         } else {
+    ///             dur
+    ///         }
+    ///     })
+    ///     .await;
+    ///     println!("fetch result: {:?}", result);
+    ///
+    ///     Ok(())
+    /// }
+    /// ```
+    pub fn adjust<NAF: FnMut(&E, Option<Duration>) -> Option<Duration>>(
+        self,
+        adjust: NAF,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, NAF, CF, XF> {
+        RetryWithContext {
+            config: self.config.with_adjust(adjust),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
+        }
+    }
+
+    /// Sets the function to adjust the backoff duration, with the [`RetryState`] of the retry.
+    ///
+    /// This is the same as [`RetryWithContext::adjust`], and the function also receives the progress of the retry,
+    /// for example to shorten the delays once the retry took too long.
+    #[allow(clippy::type_complexity)]
+    pub fn adjust_with_state<NAF: FnMut(&E, Option<Duration>, &RetryState) -> Option<Duration>>(
+        self,
+        adjust: NAF,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, WithState<NAF>, CF, XF> {
+        RetryWithContext {
+            config: self.config.timed().with_adjust(WithState(adjust)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
+        }
+    }
+
+    /// Sets the function to adjust the backoff duration, with a function returning a future.
+    ///
+    /// This is the same as [`RetryWithContext::adjust`] for adjustments that need to await, for example to
+    /// ask a rate limiter for the delay.
+    #[allow(clippy::type_complexity)]
+    pub fn adjust_async<NAF, AFut>(
+        self,
+        adjust: NAF,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, Async<NAF>, CF, XF>
+    where
+        NAF: FnMut(&E, Option<Duration>) -> AFut,
+        AFut: Future<Output = Option<Duration>>,
+    {
+        RetryWithContext {
+            config: self.config.with_adjust(Async(adjust)),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: self.cancel,
+            state: State::Idle(self.state.into_context()),
+        }
+    }
+
+    /// Set the clock used to measure the time limits of the retry.
+    ///
+    /// The clock should implement the [`Clock`] trait, for example [`ManualClock`](crate::ManualClock) in tests.
+    ///
+    /// If not specified, we use the [`DefaultClock`].
+    ///
+    /// The clock must be set before [`RetryWithContext::deadline`], which is an instant of the clock: a
+    /// deadline set before the clock is dropped along with the previous clock.
+    pub fn clock<CN: Clock>(
+        self,
+        clock: CN,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CN, XF> {
+        RetryWithContext {
+            config: self.config.with_clock(clock),
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: None,
+            hint: self.hint,
+            cancel: self.cancel,
             state: self.state,
         }
     }
+
+    /// Set the maximum time the whole retry can take.
+    ///
+    /// The time is measured by the clock from the start of the first attempt, so it includes the
+    /// time spent in the attempts as well as in the sleeps. No retry will be made if the next sleep
+    /// would end after `max_elapsed`, the last error is returned instead.
+    ///
+    /// Unlike `with_total_delay` on the backoff builders, which only sums the sleeps, this limit
+    /// follows the clock.
+    pub fn max_elapsed(mut self, max_elapsed: Duration) -> Self
+    where
+        CF: Clock,
+    {
+        self.config.max_elapsed = Some(max_elapsed);
+        self.config.timed = true;
+        self
+    }
+
+    /// Set the deadline of the retry.
+    ///
+    /// The deadline is an instant of the clock, so a custom clock must be set with
+    /// [`RetryWithContext::clock`] beforehand. Sleeps are shortened to end at the deadline, and
+    /// no attempt starts after it: the retry returns a [`DeadlineExceeded`] converted into `E`
+    /// instead of the last error, which tells it apart from a retry ended by the backoff.
+    ///
+    /// Use [`RetryWithContext::deadline_with`] if `E` doesn't implement `From<DeadlineExceeded>`.
+    pub fn deadline(self, deadline: <CF as Clock>::Instant) -> Self
+    where
+        CF: Clock,
+        E: From<DeadlineExceeded>,
+    {
+        self.deadline_with(deadline, E::from)
+    }
+
+    /// Set the deadline of the retry, with a function building the error returned once it's reached.
+    ///
+    /// This is the same as [`RetryWithContext::deadline`], for error types that don't implement
+    /// `From<DeadlineExceeded>`.
+    pub fn deadline_with(
+        mut self,
+        deadline: <CF as Clock>::Instant,
+        map: fn(DeadlineExceeded) -> E,
+    ) -> Self
+    where
+        CF: Clock,
+    {
+        self.config.deadline = Some(maybe_instant::<CF>(deadline));
+        self.deadline_exceeded = Some(map);
+        self
+    }
+
+    /// Wait for the delay hinted by the errors implementing [`RetryAfterHint`] before retrying.
+    ///
+    /// The hint replaces the delay of the backoff, clamped to the maximum delay of the backoff
+    /// builder if it has one, and is passed to [`RetryWithContext::adjust`] like the delay it replaces. Errors
+    /// without a hint are retried after the delay of the backoff, which still limits the number of
+    /// retries. The delays chosen with [`RetryWithContext::classify`] take precedence over the hints.
+    pub fn honor_hints(mut self) -> Self
+    where
+        E: RetryAfterHint,
+    {
+        self.hint = Some(E::retry_after);
+        self
+    }
+
+    /// Cancel the retry once `cancel` resolves, for example on a shutdown signal.
+    ///
+    /// The cancellation is checked before every attempt and while sleeping: a pending sleep is
+    /// aborted and the retry returns a [`Cancelled`] converted into `E`. An attempt in progress
+    /// is not interrupted, the retry is cancelled once it fails.
+    ///
+    /// The context is returned along with the error.
+    ///
+    /// Use [`RetryWithContext::cancel_on_with`] if `E` doesn't implement `From<Cancelled>`.
+    pub fn cancel_on<XN: Future<Output = ()>>(
+        self,
+        cancel: XN,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XN>
+    where
+        E: From<Cancelled>,
+    {
+        self.cancel_on_with(cancel, E::from)
+    }
+
+    /// Cancel the retry once `cancel` resolves, with a function building the error of a cancelled retry.
+    ///
+    /// This is the same as [`RetryWithContext::cancel_on`], for error types that don't implement
+    /// `From<Cancelled>`.
+    pub fn cancel_on_with<XN: Future<Output = ()>>(
+        self,
+        cancel: XN,
+        map: fn(Cancelled) -> E,
+    ) -> RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XN> {
+        RetryWithContext {
+            config: self.config,
+            future_fn: self.future_fn,
+            attempt_timeout: self.attempt_timeout,
+            deadline_exceeded: self.deadline_exceeded,
+            hint: self.hint,
+            cancel: Some(CancelOn::new(cancel, map)),
+            state: self.state,
+        }
+    }
+
+    /// Collect the errors of all attempts, returning a [`RetryError`](crate::RetryError) once the retry gives up.
+    ///
+    /// The error holds the error and the duration of every attempt, along with the delay
+    /// slept after it. This requires the `alloc` feature.
+    #[cfg(feature = "alloc")]
+    pub fn collect_errors(mut self) -> CollectErrors<Self, E> {
+        self.config.timed = true;
+        CollectErrors::new(self)
+    }
+
+    /// Set the timeout of every attempt.
+    ///
+    /// Each call to the future function is raced against the sleeper. If the attempt doesn't
+    /// complete within `timeout`, its future is dropped and the attempt fails with a [`Timeout`]
+    /// converted into `E`, which is handled like any other error.
+    ///
+    /// As the context is dropped along with the timed out future, a clone of the context is
+    /// taken before every attempt. The next attempt, or the caller if no retry is made, gets this
+    /// clone back.
+    pub fn attempt_timeout(self, timeout: Duration) -> Self
+    where
+        E: From<Timeout>,
+        Ctx: Clone,
+    {
+        self.attempt_timeout_with(timeout, E::from)
+    }
+
+    /// Set the timeout of every attempt, with a function building the error of a timed out attempt.
+    ///
+    /// This is the same as [`RetryWithContext::attempt_timeout`], for error types that don't
+    /// implement `From<Timeout>`.
+    pub fn attempt_timeout_with(mut self, timeout: Duration, map: fn(Timeout) -> E) -> Self
+    where
+        Ctx: Clone,
+    {
+        self.attempt_timeout = Some((AttemptTimeout::new(timeout, map), Ctx::clone));
+        self
+    }
 }
 
+/// The attempt timeout with the function cloning the context before every attempt.
+type ContextTimeout<E, Ctx> = (AttemptTimeout<E>, fn(&Ctx) -> Ctx);
+
+/// The state of a retry, with the futures of its sleeper and hooks.
+type StateOf<T, E, Ctx, Fut, SF, RF, NF, AF> = State<
+    T,
+    E,
+    Ctx,
+    Fut,
+    <SF as MaybeSleeper>::Sleep,
+    <RF as AsyncWhenHook<E>>::Future,
+    <AF as AsyncAdjustHook<E>>::Future,
+    <NF as AsyncNotifyHook<E>>::Future,
+>;
+
 /// State maintains internal state of retry.
-enum State<T, E, Ctx, Fut: Future<Output = (Ctx, Result<T, E>)>, SleepFut: Future> {
+enum State<
+    T,
+    E,
+    Ctx,
+    Fut: Future<Output = (Ctx, Result<T, E>)>,
+    SleepFut: Future,
+    WhenFut,
+    AdjustFut,
+    NotifyFut,
+> {
     Idle(Option<Ctx>),
-    Polling(Fut),
-    Sleeping((Option<Ctx>, SleepFut)),
+    /// Polling the attempt, raced against its timeout if any with a clone of the context.
+    Polling(Fut, Option<Ctx>, Option<SleepFut>),
+    /// Checking if the error of the failed attempt is retryable.
+    Checking(Option<(Ctx, E)>, RetryState, WhenFut),
+    /// Adjusting the delay proposed by the backoff.
+    Adjusting(Option<(Ctx, E)>, RetryState, Option<Duration>, AdjustFut),
+    /// Notifying the retry before sleeping for the delay.
+    Notifying(Option<(Ctx, E)>, RetryState, Duration, NotifyFut),
+    /// Sleeping for the delay, which is counted once the sleep is over.
+    Sleeping((Option<Ctx>, SleepFut), Duration),
+}
+
+impl<T, E, Ctx, Fut, SleepFut, WhenFut, AdjustFut, NotifyFut>
+    State<T, E, Ctx, Fut, SleepFut, WhenFut, AdjustFut, NotifyFut>
+where
+    Fut: Future<Output = (Ctx, Result<T, E>)>,
+    SleepFut: Future,
+{
+    /// Take the context of a retry that has not started, to rebuild it with other hooks.
+    fn into_context(self) -> Option<Ctx> {
+        match self {
+            State::Idle(ctx) => ctx,
+            _ => None,
+        }
+    }
 }
 
-impl<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF> Future
-    for RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF>
+impl<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
+    RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
 where
     B: Backoff,
     Fut: Future<Output = (Ctx, Result<T, E>)>,
     FutureFn: FnMut(Ctx) -> Fut,
     SF: Sleeper,
-    RF: FnMut(&E) -> bool,
-    NF: FnMut(&E, Duration),
-    AF: FnMut(&E, Option<Duration>) -> Option<Duration>,
+    RF: AsyncWhenHook<E>,
+    NF: AsyncNotifyHook<E>,
+    AF: AsyncAdjustHook<E>,
+    CF: MaybeClock,
+    XF: Future<Output = ()>,
 {
-    type Output = (Ctx, Result<T, E>);
+    /// Await the retry, returning the reason it gave up along with the error.
+    ///
+    /// The [`GiveUpReason`] tells apart an error that is not retryable from a retry that ran out
+    /// of retries or time.
+    pub async fn await_with_reason(self) -> (Ctx, Result<T, (E, GiveUpReason)>) {
+        let mut reason = None;
+        let mut retry = pin!(self);
+        let (ctx, res) = poll_fn(|cx| retry.as_mut().poll_retry(cx, &mut reason)).await;
+        (
+            ctx,
+            res.map_err(|err| (err, reason.expect("retry must give up with a reason"))),
+        )
+    }
 
-    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+    /// Poll the retry, reporting the failed attempts to `observer`.
+    pub(crate) fn poll_retry(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        observer: &mut impl RetryObserver<E>,
+    ) -> Poll<(Ctx, Result<T, E>)> {
         // Safety: This is safe because we don't move the `Retry` struct itself,
         // only its internal state.
         //
@@ -308,38 +811,119 @@ where
             match &mut this.state {
                 State::Idle(ctx) => {
                     let ctx = ctx.take().expect("context must be valid");
+                    // Safety: This is safe because we don't move the `Retry` struct and the
+                    // cancellation, only its internal state.
+                    if let Poll::Ready(err) = unsafe { poll_cancel(&mut this.cancel, cx) } {
+                        observer.gave_up(GiveUpReason::Cancelled, Duration::ZERO);
+                        return Poll::Ready((ctx, Err(err)));
+                    }
+                    if let Some(map) = this.deadline_exceeded {
+                        if this.config.deadline_passed() {
+                            observer.gave_up(GiveUpReason::DeadlineExceeded, Duration::ZERO);
+                            return Poll::Ready((ctx, Err(map(DeadlineExceeded))));
+                        }
+                    }
+                    this.config.start();
+                    let (backup, timer) = match &this.attempt_timeout {
+                        Some((timeout, clone_ctx)) => (
+                            Some(clone_ctx(&ctx)),
+                            Some(this.config.sleep.sleep(timeout.duration)),
+                        ),
+                        None => (None, None),
+                    };
                     let fut = (this.future_fn)(ctx);
-                    this.state = State::Polling(fut);
+                    this.state = State::Polling(fut, backup, timer);
                     continue;
                 }
-                State::Polling(fut) => {
+                State::Polling(fut, backup, timer) => {
                     // Safety: This is safe because we don't move the `Retry` struct and this fut,
                     // only its internal state.
                     //
                     // We do the exactly same thing like `pin_project` but without depending on it directly.
                     let mut fut = unsafe { Pin::new_unchecked(fut) };
 
-                    let (ctx, res) = ready!(fut.as_mut().poll(cx));
-                    match res {
-                        Ok(v) => return Poll::Ready((ctx, Ok(v))),
-                        Err(err) => match this.config.decide(&err) {
-                            ControlFlow::Continue(dur) => {
-                                let sleep = this.config.sleep.sleep(dur);
-                                this.state = State::Sleeping((Some(ctx), sleep));
-                                continue;
+                    let (ctx, err) = match fut.as_mut().poll(cx) {
+                        Poll::Ready((ctx, Ok(v))) => return Poll::Ready((ctx, Ok(v))),
+                        Poll::Ready((ctx, Err(err))) => (ctx, err),
+                        Poll::Pending => match (timer, &this.attempt_timeout) {
+                            (Some(timer), Some((timeout, _))) => {
+                                // Safety: Same as above, the timer is pinned in the state and never moved.
+                                let timer = unsafe { Pin::new_unchecked(timer) };
+                                ready!(timer.poll(cx));
+                                let ctx = backup.take().expect("context must be valid");
+                                (ctx, timeout.error())
                             }
-                            ControlFlow::Break(_) => return Poll::Ready((ctx, Err(err))),
+                            _ => return Poll::Pending,
                         },
+                    };
+                    let state = this.config.state();
+                    let fut = this.config.retryable.call(&err, &state);
+                    this.state = State::Checking(Some((ctx, err)), state, fut);
+                    continue;
+                }
+                State::Checking(failed, state, fut) => {
+                    // Safety: Same as above, the future is pinned in the state and never moved.
+                    let fut = unsafe { Pin::new_unchecked(fut) };
+                    let decision = ready!(fut.poll(cx));
+                    let (ctx, err) = failed.take().expect("context must be valid");
+                    let state = *state;
+                    let hint = this.hint.and_then(|hint| hint(&err));
+                    let candidate = match this.config.candidate(decision, hint) {
+                        ControlFlow::Continue(candidate) => candidate,
+                        ControlFlow::Break(reason) => {
+                            observer.gave_up(reason, state.last_attempt_duration);
+                            return Poll::Ready((ctx, Err(err)));
+                        }
+                    };
+                    let fut = this.config.adjust.call(&err, candidate, &state);
+                    this.state = State::Adjusting(Some((ctx, err)), state, candidate, fut);
+                    continue;
+                }
+                State::Adjusting(failed, state, candidate, fut) => {
+                    // Safety: Same as above, the future is pinned in the state and never moved.
+                    let fut = unsafe { Pin::new_unchecked(fut) };
+                    let adjusted = ready!(fut.poll(cx));
+                    let (ctx, err) = failed.take().expect("context must be valid");
+                    let state = *state;
+                    match this.config.settle(*candidate, adjusted) {
+                        ControlFlow::Continue(dur) => {
+                            let fut = this.config.notify.call(&err, dur, &state);
+                            this.state = State::Notifying(Some((ctx, err)), state, dur, fut);
+                            continue;
+                        }
+                        ControlFlow::Break(reason) => {
+                            observer.gave_up(reason, state.last_attempt_duration);
+                            return Poll::Ready((ctx, Err(err)));
+                        }
                     }
                 }
-                State::Sleeping((ctx, sl)) => {
+                State::Notifying(failed, state, dur, fut) => {
+                    // Safety: Same as above, the future is pinned in the state and never moved.
+                    let fut = unsafe { Pin::new_unchecked(fut) };
+                    ready!(fut.poll(cx));
+                    let (ctx, err) = failed.take().expect("context must be valid");
+                    let dur = *dur;
+                    observer.retried(err, state.last_attempt_duration, dur);
+                    let sleep = this.config.sleep.sleep(dur);
+                    this.state = State::Sleeping((Some(ctx), sleep), dur);
+                    continue;
+                }
+                State::Sleeping((ctx, sl), dur) => {
                     // Safety: This is safe because we don't move the `Retry` struct and this fut,
                     // only its internal state.
                     //
                     // We do the exactly same thing like `pin_project` but without depending on it directly.
                     let mut sl = unsafe { Pin::new_unchecked(sl) };
 
+                    // Safety: Same as above, the cancellation is never moved.
+                    if let Poll::Ready(err) = unsafe { poll_cancel(&mut this.cancel, cx) } {
+                        observer.gave_up(GiveUpReason::Cancelled, Duration::ZERO);
+                        let ctx = ctx.take().expect("context must be valid");
+                        return Poll::Ready((ctx, Err(err)));
+                    }
                     ready!(sl.as_mut().poll(cx));
+                    this.config.record_sleep(*dur);
+                    observer.slept(*dur);
                     let ctx = ctx.take().expect("context must be valid");
                     this.state = State::Idle(Some(ctx));
                     continue;
@@ -349,12 +933,33 @@ where
     }
 }
 
+impl<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XF> Future
+    for RetryWithContext<B, T, E, Ctx, Fut, FutureFn, SF, RF, NF, AF, CF, XF>
+where
+    B: Backoff,
+    Fut: Future<Output = (Ctx, Result<T, E>)>,
+    FutureFn: FnMut(Ctx) -> Fut,
+    SF: Sleeper,
+    RF: AsyncWhenHook<E>,
+    NF: AsyncNotifyHook<E>,
+    AF: AsyncAdjustHook<E>,
+    CF: MaybeClock,
+    XF: Future<Output = ()>,
+{
+    type Output = (Ctx, Result<T, E>);
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        self.poll_retry(cx, &mut ())
+    }
+}
+
 #[cfg(test)]
 #[cfg(any(feature = "tokio-sleep", feature = "gloo-timers-sleep",))]
 mod tests {
     extern crate alloc;
 
     use alloc::string::ToString;
+    use alloc::vec::Vec;
     use core::time::Duration;
 
     use anyhow::Result;
@@ -405,4 +1010,103 @@ mod tests {
         // only once.
         assert_eq!(*error_times.lock().await, 1);
     }
+
+    #[test]
+    async fn test_retry_with_adjust() {
+        let error_times = Mutex::new(0);
+
+        let backoff = ExponentialBuilder::default()
+            .with_min_delay(Duration::from_secs(60))
+            .with_max_times(2);
+
+        let (_, result) = {
+            |mut v: Test| async {
+                let mut x = error_times.lock().await;
+                *x += 1;
+
+                let res = v.hello().await;
+                (v, res)
+            }
+        }
+        .retry(backoff)
+        .context(Test)
+        // Override the long backoff delay so the test finishes quickly.
+        .adjust(|_, dur| dur.map(|_| Duration::from_millis(1)))
+        .await;
+
+        assert!(result.is_err());
+        // 1 attempt + 2 retries.
+        assert_eq!(*error_times.lock().await, 3);
+    }
+
+    #[test]
+    async fn test_retry_with_attempt_timeout() {
+        let mut calls = 0;
+        let (attempts, result) = {
+            |attempts: usize| {
+                calls += 1;
+                let first = calls == 1;
+                async move {
+                    if first {
+                        // The first attempt never completes and loses its context.
+                        core::future::pending::<()>().await;
+                    }
+                    (attempts + 1, Ok::<_, anyhow::Error>(()))
+                }
+            }
+        }
+        .retry(ExponentialBuilder::default())
+        .sleep(|_| core::future::ready(()))
+        .context(0)
+        .attempt_timeout(Duration::from_millis(1))
+        .await;
+
+        assert!(result.is_ok());
+        // The second attempt got the context back from before the first one.
+        assert_eq!(attempts, 1);
+    }
+
+    #[test]
+    async fn test_retry_with_async_hooks() {
+        let mut delays = Vec::new();
+        let (attempts, result) =
+            { |attempts: usize| async move { (attempts + 1, Err::<(), _>(anyhow!("retryable"))) } }
+                .retry(ExponentialBuilder::default().with_max_times(2))
+                .sleep(|_| core::future::ready(()))
+                .context(0)
+                .when_async(|e| {
+                    let retryable = e.to_string() == "retryable";
+                    async move { retryable }
+                })
+                .adjust_async(|_, dur| async move { dur.map(|_| Duration::from_millis(1)) })
+                .notify_async(|_, dur| {
+                    delays.push(dur);
+                    async {}
+                })
+                .await;
+
+        assert!(result.is_err());
+        // 1 attempt + 2 retries, the context is kept by the hooks set after it.
+        assert_eq!(attempts, 3);
+        assert_eq!(delays, [Duration::from_millis(1); 2]);
+    }
+
+    #[test]
+    async fn test_retry_with_cancel_on() {
+        let (ctx, result) = {
+            |mut attempts: usize| async move {
+                attempts += 1;
+                (attempts, Err::<(), _>(anyhow!("retryable")))
+            }
+        }
+        .retry(ExponentialBuilder::default())
+        .sleep(|_| core::future::pending::<()>())
+        .context(0)
+        .cancel_on_with(core::future::ready(()), |err| anyhow!(err))
+        .await;
+
+        assert_eq!(result.unwrap_err().to_string(), "retry cancelled");
+        // Cancelled before the first attempt.
+        assert_eq!(ctx, 0);
+    }
 }
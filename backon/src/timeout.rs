@@ -0,0 +1,82 @@
+use core::fmt;
+use core::time::Duration;
+
+/// Timeout is the error of an attempt that didn't complete within its timeout.
+///
+/// It's returned for the attempts timed out by [`Retry::attempt_timeout`][crate::Retry::attempt_timeout]
+/// and converted into the error type of the retried function.
+///
+/// # Examples
+///
+/// ```
+/// use core::time::Duration;
+///
+/// use backon::Timeout;
+///
+/// #[derive(Debug)]
+/// enum Error {
+///     Timeout(Timeout),
+///     Io(std::io::Error),
+/// }
+///
+/// impl From<Timeout> for Error {
+///     fn from(err: Timeout) -> Self {
+///         Error::Timeout(err)
+///     }
+/// }
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Timeout {
+    duration: Duration,
+}
+
+impl Timeout {
+    /// The timeout the attempt exceeded.
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+}
+
+impl fmt::Display for Timeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "attempt timed out after {:?}", self.duration)
+    }
+}
+
+impl core::error::Error for Timeout {}
+
+/// The timeout of every attempt and how to turn it into an error.
+pub(crate) struct AttemptTimeout<E> {
+    pub(crate) duration: Duration,
+    map: fn(Timeout) -> E,
+}
+
+impl<E> AttemptTimeout<E> {
+    pub(crate) fn new(duration: Duration, map: fn(Timeout) -> E) -> Self {
+        Self { duration, map }
+    }
+
+    /// Build the error of a timed out attempt.
+    pub(crate) fn error(&self) -> E {
+        (self.map)(Timeout {
+            duration: self.duration,
+        })
+    }
+}
+
+/// DeadlineExceeded is the error of a retry that reached its deadline.
+///
+/// It's returned instead of the last error when no attempt could start before the deadline set by
+/// [`Retry::deadline`][crate::Retry::deadline], so that it can be told apart from a retry ended by
+/// the backoff.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct DeadlineExceeded;
+
+impl fmt::Display for DeadlineExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "retry deadline exceeded")
+    }
+}
+
+impl core::error::Error for DeadlineExceeded {}
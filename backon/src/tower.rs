@@ -0,0 +1,434 @@
+//! Retry the requests of the [tower](https://docs.rs/tower) services, like the hyper or tonic
+//! clients, with the backoffs of backon.
+//!
+//! [`RetryLayer`] adds the retries to a service stack, while [`RetryPolicy`] implements
+//! [`tower::retry::Policy`] for the stacks already built with tower's own
+//! [`Retry`](tower::retry::Retry) middleware.
+//!
+//! Every request gets a fresh backoff built from the [`BackoffBuilder`], and the responses and
+//! errors are classified into a [`RetryDecision`] by a user function. A request is retried only
+//! if it could be cloned before sending it: the requests implementing [`Clone`] are cloned by
+//! default, see [`RetryLayer::clone_request_with`] for the others.
+//!
+//! This module requires the `tower` feature.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::convert::Infallible;
+//! use std::sync::atomic::AtomicUsize;
+//! use std::sync::atomic::Ordering;
+//!
+//! use backon::ExponentialBuilder;
+//! use backon::RetryDecision;
+//! use backon::tower::RetryLayer;
+//! use tower::ServiceBuilder;
+//! use tower::ServiceExt;
+//! use tower::service_fn;
+//!
+//! #[derive(Debug, PartialEq)]
+//! enum Status {
+//!     Ok,
+//!     Unavailable,
+//! }
+//!
+//! static ATTEMPTS: AtomicUsize = AtomicUsize::new(0);
+//!
+//! #[tokio::main(flavor = "current_thread")]
+//! async fn main() -> Result<(), Infallible> {
+//!     let service = ServiceBuilder::new()
+//!         .layer(RetryLayer::new(
+//!             ExponentialBuilder::default(),
+//!             |res: &Result<Status, Infallible>| match res {
+//!                 Ok(Status::Unavailable) => RetryDecision::Retry,
+//!                 _ => RetryDecision::Abort,
+//!             },
+//!         ))
+//!         .service(service_fn(|_: &str| async {
+//!             // The first attempt fails.
+//!             Ok(match ATTEMPTS.fetch_add(1, Ordering::Relaxed) {
+//!                 0 => Status::Unavailable,
+//!                 _ => Status::Ok,
+//!             })
+//!         }));
+//!
+//!     assert_eq!(service.oneshot("hello").await?, Status::Ok);
+//!     assert_eq!(ATTEMPTS.load(Ordering::Relaxed), 2);
+//!
+//!     Ok(())
+//! }
+//! ```
+
+use core::future::Future;
+use core::time::Duration;
+
+use tower::Layer;
+use tower::retry::Policy;
+
+use crate::BackoffBuilder;
+use crate::DefaultSleeper;
+use crate::RetryDecision;
+use crate::Sleeper;
+
+/// CloneRequest clones the requests before sending them, so that they can be retried.
+///
+/// It's implemented by [`CloneRequests`], the default of [`RetryPolicy`], and by the functions
+/// given to [`RetryPolicy::clone_request_with`].
+pub trait CloneRequest<Req> {
+    /// Clone the request, `None` to send it once without retrying it.
+    fn clone_request(&mut self, req: &Req) -> Option<Req>;
+}
+
+/// Clone the requests implementing [`Clone`], the default of [`RetryPolicy`].
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CloneRequests;
+
+impl<Req: Clone> CloneRequest<Req> for CloneRequests {
+    fn clone_request(&mut self, req: &Req) -> Option<Req> {
+        Some(req.clone())
+    }
+}
+
+impl<Req, F: FnMut(&Req) -> Option<Req>> CloneRequest<Req> for F {
+    fn clone_request(&mut self, req: &Req) -> Option<Req> {
+        self(req)
+    }
+}
+
+/// RetryPolicy retries the requests of a tower service with a backon backoff.
+///
+/// It's used by [`RetryLayer`], and implements [`tower::retry::Policy`] to be given to tower's
+/// [`RetryLayer`](tower::retry::RetryLayer) directly. tower clones the policy for every request,
+/// and the clones start over from a fresh backoff.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+///
+/// use backon::ConstantBuilder;
+/// use backon::tower::RetryPolicy;
+/// use tower::ServiceBuilder;
+/// use tower::retry::RetryLayer;
+/// use tower::service_fn;
+///
+/// let policy = RetryPolicy::new(
+///     ConstantBuilder::default(),
+///     // Retry all the errors.
+///     |res: &Result<String, io::Error>| res.is_err(),
+/// );
+///
+/// let service = ServiceBuilder::new()
+///     .layer(RetryLayer::new(policy))
+///     .service(service_fn(|name: String| async move {
+///         Ok::<_, io::Error>(format!("hello, {name}"))
+///     }));
+/// ```
+pub struct RetryPolicy<B: BackoffBuilder, C, SF = DefaultSleeper, RC = CloneRequests> {
+    builder: B,
+    backoff: Option<B::Backoff>,
+    classify: C,
+    sleeper: SF,
+    clone_request: RC,
+}
+
+impl<B: BackoffBuilder, C> RetryPolicy<B, C> {
+    /// Create a policy retrying with the backoffs built by `builder`, for the responses and
+    /// errors classified as retryable by `classify`.
+    ///
+    /// `classify` returns either a [`RetryDecision`] or a `bool`, `true` for a retry after the
+    /// delay of the backoff.
+    pub fn new(builder: B, classify: C) -> Self {
+        RetryPolicy {
+            builder,
+            backoff: None,
+            classify,
+            sleeper: DefaultSleeper::default(),
+            clone_request: CloneRequests,
+        }
+    }
+}
+
+impl<B: BackoffBuilder, C, SF, RC> RetryPolicy<B, C, SF, RC> {
+    /// Set the sleeper for retrying.
+    ///
+    /// The default sleeper is the one of [`Retry::sleep`](crate::Retry::sleep).
+    pub fn sleep<SN: Sleeper>(self, sleeper: SN) -> RetryPolicy<B, C, SN, RC> {
+        RetryPolicy {
+            builder: self.builder,
+            backoff: self.backoff,
+            classify: self.classify,
+            sleeper,
+            clone_request: self.clone_request,
+        }
+    }
+
+    /// Set the function cloning the requests before sending them.
+    ///
+    /// The requests for which it returns `None` are sent once, without retrying them. This is
+    /// useful for the requests that don't implement [`Clone`], like the HTTP requests with a
+    /// streaming body.
+    pub fn clone_request_with<Req, RN>(self, clone_request: RN) -> RetryPolicy<B, C, SF, RN>
+    where
+        RN: FnMut(&Req) -> Option<Req>,
+    {
+        RetryPolicy {
+            builder: self.builder,
+            backoff: self.backoff,
+            classify: self.classify,
+            sleeper: self.sleeper,
+            clone_request,
+        }
+    }
+}
+
+/// Cloning a policy starts over from a fresh backoff.
+impl<B, C, SF, RC> Clone for RetryPolicy<B, C, SF, RC>
+where
+    B: BackoffBuilder + Clone,
+    C: Clone,
+    SF: Clone,
+    RC: Clone,
+{
+    fn clone(&self) -> Self {
+        RetryPolicy {
+            builder: self.builder.clone(),
+            backoff: None,
+            classify: self.classify.clone(),
+            sleeper: self.sleeper.clone(),
+            clone_request: self.clone_request.clone(),
+        }
+    }
+}
+
+impl<B, C, SF, RC, D, Req, Res, E> Policy<Req, Res, E> for RetryPolicy<B, C, SF, RC>
+where
+    B: BackoffBuilder + Clone,
+    C: FnMut(&Result<Res, E>) -> D,
+    D: Into<RetryDecision>,
+    SF: Sleeper<Sleep: Future<Output = ()>>,
+    RC: CloneRequest<Req>,
+{
+    type Future = SF::Sleep;
+
+    fn retry(&mut self, _: &mut Req, result: &mut Result<Res, E>) -> Option<Self::Future> {
+        let max_delay = self.builder.max_delay().unwrap_or(Duration::MAX);
+        let backoff = self
+            .backoff
+            .get_or_insert_with(|| self.builder.clone().build());
+        let dur = match (self.classify)(result).into() {
+            RetryDecision::Abort => return None,
+            RetryDecision::Retry => backoff.next()?,
+            RetryDecision::RetryAfter(dur) => backoff.next().map(|_| dur.min(max_delay))?,
+            RetryDecision::RetryNow => backoff.next().map(|_| Duration::ZERO)?,
+        };
+        Some(self.sleeper.sleep(dur))
+    }
+
+    fn clone_request(&mut self, req: &Req) -> Option<Req> {
+        self.clone_request.clone_request(req)
+    }
+}
+
+/// RetryLayer retries the requests of the services it wraps with a backon backoff.
+///
+/// The services are wrapped in tower's [`Retry`](tower::retry::Retry) middleware, with a
+/// [`RetryPolicy`]. See the [module docs](self) for an example.
+pub struct RetryLayer<B: BackoffBuilder, C, SF = DefaultSleeper, RC = CloneRequests> {
+    policy: RetryPolicy<B, C, SF, RC>,
+}
+
+impl<B: BackoffBuilder, C> RetryLayer<B, C> {
+    /// Create a layer retrying with the backoffs built by `builder`, for the responses and
+    /// errors classified as retryable by `classify`.
+    ///
+    /// See [`RetryPolicy::new`] for the values `classify` returns.
+    pub fn new(builder: B, classify: C) -> Self {
+        RetryLayer {
+            policy: RetryPolicy::new(builder, classify),
+        }
+    }
+}
+
+impl<B: BackoffBuilder, C, SF, RC> RetryLayer<B, C, SF, RC> {
+    /// Set the sleeper for retrying.
+    ///
+    /// See [`RetryPolicy::sleep`] for details.
+    pub fn sleep<SN: Sleeper>(self, sleeper: SN) -> RetryLayer<B, C, SN, RC> {
+        RetryLayer {
+            policy: self.policy.sleep(sleeper),
+        }
+    }
+
+    /// Set the function cloning the requests before sending them.
+    ///
+    /// See [`RetryPolicy::clone_request_with`] for details.
+    pub fn clone_request_with<Req, RN>(self, clone_request: RN) -> RetryLayer<B, C, SF, RN>
+    where
+        RN: FnMut(&Req) -> Option<Req>,
+    {
+        RetryLayer {
+            policy: self.policy.clone_request_with(clone_request),
+        }
+    }
+}
+
+impl<B, C, SF, RC> Clone for RetryLayer<B, C, SF, RC>
+where
+    B: BackoffBuilder + Clone,
+    C: Clone,
+    SF: Clone,
+    RC: Clone,
+{
+    fn clone(&self) -> Self {
+        RetryLayer {
+            policy: self.policy.clone(),
+        }
+    }
+}
+
+impl<S, B, C, SF, RC> Layer<S> for RetryLayer<B, C, SF, RC>
+where
+    B: BackoffBuilder + Clone,
+    C: Clone,
+    SF: Clone,
+    RC: Clone,
+{
+    type Service = tower::retry::Retry<RetryPolicy<B, C, SF, RC>, S>;
+
+    fn layer(&self, service: S) -> Self::Service {
+        tower::retry::Retry::new(self.policy.clone(), service)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use tower::ServiceBuilder;
+    use tower::ServiceExt;
+    use tower::service_fn;
+
+    use super::*;
+    use crate::ConstantBuilder;
+    use crate::ExponentialBuilder;
+
+    #[tokio::test]
+    async fn test_retry_layer() {
+        let sleeps = Arc::new(Mutex::new(Vec::new()));
+        let attempts = Arc::new(Mutex::new(0));
+        let layer = RetryLayer::new(
+            ConstantBuilder::default().with_max_times(3),
+            |res: &Result<u32, &str>| match res {
+                Ok(_) => RetryDecision::Abort,
+                Err("rate limited") => RetryDecision::RetryAfter(Duration::from_millis(500)),
+                Err(_) => RetryDecision::Retry,
+            },
+        )
+        .sleep({
+            let sleeps = sleeps.clone();
+            move |dur| {
+                sleeps.lock().unwrap().push(dur);
+                async {}
+            }
+        });
+        let service = ServiceBuilder::new().layer(layer).service(service_fn({
+            let attempts = attempts.clone();
+            move |req: u32| {
+                let mut attempts = attempts.lock().unwrap();
+                *attempts += 1;
+                let res = match *attempts % 3 {
+                    1 => Err("unavailable"),
+                    2 => Err("rate limited"),
+                    _ => Ok(req),
+                };
+                async move { res }
+            }
+        }));
+
+        assert_eq!(service.clone().oneshot(7).await, Ok(7));
+        assert_eq!(
+            *sleeps.lock().unwrap(),
+            [Duration::from_secs(1), Duration::from_millis(500)]
+        );
+
+        // Every request starts over from a fresh backoff.
+        sleeps.lock().unwrap().clear();
+        assert_eq!(service.oneshot(8).await, Ok(8));
+        assert_eq!(*attempts.lock().unwrap(), 6);
+        assert_eq!(
+            *sleeps.lock().unwrap(),
+            [Duration::from_secs(1), Duration::from_millis(500)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_exhausted() {
+        let attempts = Arc::new(Mutex::new(0));
+        let policy = RetryPolicy::new(
+            ConstantBuilder::default().with_max_times(2),
+            |res: &Result<(), &str>| res.is_err(),
+        )
+        .sleep(|_| async {});
+        let service = ServiceBuilder::new()
+            .layer(tower::retry::RetryLayer::new(policy))
+            .service(service_fn({
+                let attempts = attempts.clone();
+                move |_: ()| {
+                    *attempts.lock().unwrap() += 1;
+                    async { Err::<(), _>("unavailable") }
+                }
+            }));
+
+        assert_eq!(service.oneshot(()).await, Err("unavailable"));
+        assert_eq!(*attempts.lock().unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_retry_policy_max_delay() {
+        let sleeps = Arc::new(Mutex::new(Vec::new()));
+        let policy = RetryPolicy::new(
+            ExponentialBuilder::default()
+                .with_max_delay(Duration::from_secs(3))
+                .with_max_times(1),
+            |_: &Result<(), &str>| RetryDecision::RetryAfter(Duration::from_secs(10)),
+        )
+        .sleep({
+            let sleeps = sleeps.clone();
+            move |dur| {
+                sleeps.lock().unwrap().push(dur);
+                async {}
+            }
+        });
+        let service = ServiceBuilder::new()
+            .layer(tower::retry::RetryLayer::new(policy))
+            .service(service_fn(|_: ()| async { Err::<(), _>("rate limited") }));
+
+        // The delays asked for are clamped to the max delay of the backoff.
+        assert_eq!(service.oneshot(()).await, Err("rate limited"));
+        assert_eq!(*sleeps.lock().unwrap(), [Duration::from_secs(3)]);
+    }
+
+    #[tokio::test]
+    async fn test_retry_layer_without_clone() {
+        let attempts = Arc::new(Mutex::new(0));
+        let layer = RetryLayer::new(ConstantBuilder::default(), |res: &Result<(), &str>| {
+            res.is_err()
+        })
+        .sleep(|_| async {})
+        .clone_request_with(|_: &()| None);
+        let service = ServiceBuilder::new().layer(layer).service(service_fn({
+            let attempts = attempts.clone();
+            move |_: ()| {
+                *attempts.lock().unwrap() += 1;
+                async { Err::<(), _>("unavailable") }
+            }
+        }));
+
+        // The requests that can't be cloned are not retried.
+        assert_eq!(service.oneshot(()).await, Err("unavailable"));
+        assert_eq!(*attempts.lock().unwrap(), 1);
+    }
+}
@@ -0,0 +1,272 @@
+use core::fmt;
+use core::future::Future;
+use core::pin::Pin;
+use core::task::Context;
+use core::task::Poll;
+use core::task::ready;
+
+use crate::Backoff;
+use crate::BlockingRetry;
+use crate::BlockingSleeper;
+use crate::GiveUpReason;
+use crate::Retry;
+use crate::RetryOutcome;
+use crate::Sleeper;
+use crate::clock::MaybeClock;
+use crate::outcome::OutcomeFn;
+use crate::retry_core::AdjustHook;
+use crate::retry_core::AsyncAdjustHook;
+use crate::retry_core::AsyncNotifyHook;
+use crate::retry_core::AsyncWhenHook;
+use crate::retry_core::NotifyHook;
+use crate::retry_core::WhenHook;
+
+/// UntilError is the error of a retry that also retries the values that are not ready.
+///
+/// It's returned by the retries configured with `until`, for example [`Retry::until`], and
+/// tells apart the last error from the last value returned before the retry gave up.
+///
+/// # Examples
+///
+/// ```
+/// use backon::BlockingRetryable;
+/// use backon::ConstantBuilder;
+/// use backon::UntilError;
+///
+/// #[derive(Debug, PartialEq)]
+/// enum Status {
+///     Pending,
+///     Done,
+/// }
+///
+/// fn status() -> Result<Status, String> {
+///     Ok(Status::Pending)
+/// }
+///
+/// let err = status
+///     .retry(ConstantBuilder::default().with_max_times(2))
+///     .sleep(|_| {})
+///     .until(|status| *status == Status::Done)
+///     .call()
+///     .unwrap_err();
+///
+/// assert_eq!(err, UntilError::NotReady(Status::Pending));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UntilError<T, E> {
+    /// The last attempt failed with this error.
+    Failed(E),
+    /// The retry gave up while the last value returned was not ready.
+    NotReady(T),
+}
+
+impl<T, E> UntilError<T, E> {
+    /// Map a retry result, given the reason the retry gave up if it did.
+    fn from_result(res: Result<T, E>, gave_up: Option<GiveUpReason>) -> Result<T, Self> {
+        match res {
+            Ok(v) if gave_up.is_some() => Err(UntilError::NotReady(v)),
+            Ok(v) => Ok(v),
+            Err(err) => Err(UntilError::Failed(err)),
+        }
+    }
+}
+
+impl<T, E: fmt::Display> fmt::Display for UntilError<T, E> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UntilError::Failed(err) => write!(f, "{err}"),
+            UntilError::NotReady(_) => write!(f, "retry gave up before the value was ready"),
+        }
+    }
+}
+
+impl<T: fmt::Debug, E: core::error::Error + 'static> core::error::Error for UntilError<T, E> {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            UntilError::Failed(err) => Some(err),
+            UntilError::NotReady(_) => None,
+        }
+    }
+}
+
+/// Until is a retry that also retries the values that are not ready.
+///
+/// It's created by the `until` method of the retries, for example [`Retry::until`]. Async
+/// retries are awaited, while blocking retries are run by calling [`Until::call`].
+pub struct Until<R, P> {
+    retry: R,
+    ready: P,
+}
+
+impl<R, P> Until<R, P> {
+    pub(crate) fn new(retry: R, ready: P) -> Self {
+        Until { retry, ready }
+    }
+}
+
+impl<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF, P> Future
+    for Until<Retry<B, T, E, Fut, FutureFn, SF, RF, NF, AF, CF, XF>, P>
+where
+    B: Backoff,
+    Fut: Future<Output: RetryOutcome<Ok = T, Err = E>>,
+    FutureFn: FnMut() -> Fut,
+    SF: Sleeper,
+    RF: AsyncWhenHook<E>,
+    NF: AsyncNotifyHook<E>,
+    AF: AsyncAdjustHook<E>,
+    CF: MaybeClock,
+    XF: Future<Output = ()>,
+    P: FnMut(&T) -> bool,
+{
+    type Output = Result<T, UntilError<T, E>>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        // Safety: This is safe because we don't move the `Until` struct and the retry,
+        // only project the pin to it.
+        let this = unsafe { self.get_unchecked_mut() };
+        let retry = unsafe { Pin::new_unchecked(&mut this.retry) };
+
+        let mut gave_up = None;
+        let res = ready!(retry.poll_retry_until(cx, &mut gave_up, &mut this.ready));
+        Poll::Ready(UntilError::from_result(res, gave_up))
+    }
+}
+
+impl<B, T, E, F, SF, RF, NF, AF, CF, P> Until<BlockingRetry<B, T, E, F, SF, RF, NF, AF, CF>, P>
+where
+    B: Backoff,
+    F: OutcomeFn<Outcome: RetryOutcome<Ok = T, Err = E>>,
+    SF: BlockingSleeper,
+    RF: WhenHook<E>,
+    NF: NotifyHook<E>,
+    AF: AdjustHook<E>,
+    CF: MaybeClock,
+    P: FnMut(&T) -> bool,
+{
+    /// Call the retried function.
+    pub fn call(mut self) -> Result<T, UntilError<T, E>> {
+        let mut gave_up = None;
+        let res = self.retry.call_until(&mut gave_up, &mut self.ready);
+        UntilError::from_result(res, gave_up)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use core::future::ready;
+    use core::time::Duration;
+
+    #[cfg(not(target_arch = "wasm32"))]
+    use tokio::test as async_test;
+    #[cfg(target_arch = "wasm32")]
+    use wasm_bindgen_test::wasm_bindgen_test as async_test;
+
+    use super::*;
+    use crate::BlockingRetryable;
+    use crate::ConstantBuilder;
+    use crate::ManualClock;
+    use crate::Retryable;
+
+    #[test]
+    fn test_blocking_until_ready() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut polls = 0;
+        let result = (|| {
+            polls += 1;
+            Ok::<_, &str>(polls)
+        })
+        .retry(ConstantBuilder::default())
+        .sleep(|dur| CLOCK.advance(dur))
+        .clock(&CLOCK)
+        .until(|polls| *polls == 3)
+        .call();
+
+        assert_eq!(result, Ok(3));
+        // Slept twice before the third value was ready.
+        assert_eq!(CLOCK.now(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_blocking_until_errors() {
+        let mut polls = 0;
+        let result = (|| {
+            polls += 1;
+            if polls == 2 {
+                Err("unavailable")
+            } else {
+                Ok(polls)
+            }
+        })
+        .retry(ConstantBuilder::default().with_max_times(5))
+        .sleep(|_| {})
+        .when(|_| false)
+        .until(|_| false)
+        .call();
+
+        // Errors are still retried according to `when`.
+        assert_eq!(result, Err(UntilError::Failed("unavailable")));
+        assert_eq!(polls, 2);
+    }
+
+    #[async_test]
+    async fn test_until_not_ready() {
+        let mut polls = 0;
+        let result = (|| {
+            polls += 1;
+            ready(Ok::<_, &str>(polls))
+        })
+        .retry(ConstantBuilder::default().with_max_times(2))
+        .sleep(|_| ready(()))
+        .until(|_| false)
+        .await;
+
+        // The last value is returned once the backoff is exhausted.
+        assert_eq!(result, Err(UntilError::NotReady(3)));
+    }
+
+    #[test]
+    fn test_blocking_until_deadline() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut polls = 0;
+        let result = (|| {
+            polls += 1;
+            Ok::<_, &str>(polls)
+        })
+        .retry(ConstantBuilder::default().with_delay(Duration::from_secs(10)))
+        .sleep(|dur| CLOCK.advance(dur))
+        .clock(&CLOCK)
+        .deadline_with(Duration::from_secs(25), |_| "deadline exceeded")
+        .until(|_| false)
+        .call();
+
+        // The last value is returned rather than the deadline error.
+        assert_eq!(result, Err(UntilError::NotReady(3)));
+        assert_eq!(CLOCK.now(), Duration::from_secs(25));
+    }
+
+    #[async_test]
+    async fn test_until_deadline() {
+        static CLOCK: ManualClock = ManualClock::new();
+
+        let mut polls = 0;
+        let result = (|| {
+            polls += 1;
+            ready(Ok::<_, &str>(polls))
+        })
+        .retry(ConstantBuilder::default().with_delay(Duration::from_secs(10)))
+        .sleep(|dur| {
+            CLOCK.advance(dur);
+            ready(())
+        })
+        .clock(&CLOCK)
+        .deadline_with(Duration::from_secs(25), |_| "deadline exceeded")
+        .until(|_| false)
+        .await;
+
+        // The last value is returned rather than the deadline error.
+        assert_eq!(result, Err(UntilError::NotReady(3)));
+        assert_eq!(CLOCK.now(), Duration::from_secs(25));
+    }
+}
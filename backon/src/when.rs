@@ -0,0 +1,267 @@
+//! Predicates for [`Retry::when`](crate::Retry::when) looking into the sources of the errors.
+//!
+//! The transient root cause of an error, like a timed out connection, is often wrapped by the
+//! errors of the layers above it. These predicates walk the chain of [`Error::source`], starting
+//! from the error itself, so that `when` doesn't have to loop over it.
+//!
+//! The predicates of this module take the errors implementing [`Error`], while the ones of
+//! [`report`] take the error reports like `Box<dyn Error + Send + Sync>`, or `anyhow::Error`
+//! and `eyre::Report` with the `anyhow` and `eyre` features.
+//!
+//! # Examples
+//!
+//! ```
+//! use std::error::Error;
+//! use std::io;
+//!
+//! use backon::BlockingRetryable;
+//! use backon::ConstantBuilder;
+//! use backon::when;
+//!
+//! fn fetch() -> Result<String, Box<dyn Error + Send + Sync>> {
+//!     Err(io::Error::new(io::ErrorKind::TimedOut, "connect timed out").into())
+//! }
+//!
+//! let result = fetch
+//!     .retry(ConstantBuilder::default().with_max_times(1))
+//!     .sleep(|_| {})
+//!     .when(when::report::source_is(|e: &io::Error| {
+//!         e.kind() == io::ErrorKind::TimedOut
+//!     }))
+//!     .notify(|err, _| println!("retrying {err}"))
+//!     .call();
+//!
+//! assert!(result.is_err());
+//! ```
+
+use core::error::Error;
+use core::iter::successors;
+
+/// Iterate over the error and its sources.
+fn chain<'a>(err: &'a (dyn Error + 'static)) -> impl Iterator<Item = &'a (dyn Error + 'static)> {
+    successors(Some(err), |&err: &&'a (dyn Error + 'static)| err.source())
+}
+
+/// Retry the errors with a source of type `S` matching `pred`.
+///
+/// The error itself is checked first, then its sources in order. The type `S` is usually given
+/// by the argument of `pred`, or as `source_is::<S, _>`.
+///
+/// # Examples
+///
+/// ```
+/// use std::io;
+///
+/// use backon::when;
+///
+/// #[derive(Debug)]
+/// struct FetchError(io::Error);
+///
+/// impl std::fmt::Display for FetchError {
+///     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+///         write!(f, "fetch failed")
+///     }
+/// }
+///
+/// impl std::error::Error for FetchError {
+///     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+///         Some(&self.0)
+///     }
+/// }
+///
+/// let mut timed_out = when::source_is(|e: &io::Error| e.kind() == io::ErrorKind::TimedOut);
+///
+/// assert!(timed_out(&FetchError(io::ErrorKind::TimedOut.into())));
+/// assert!(!timed_out(&FetchError(io::ErrorKind::NotFound.into())));
+/// ```
+pub fn source_is<S, E>(mut pred: impl FnMut(&S) -> bool) -> impl FnMut(&E) -> bool
+where
+    S: Error + 'static,
+    E: Error + 'static,
+{
+    move |err| chain(err).any(|err| err.downcast_ref::<S>().is_some_and(&mut pred))
+}
+
+/// Retry the errors with a source matching `pred`.
+///
+/// The error itself is checked first, then its sources in order.
+pub fn any_source<E>(mut pred: impl FnMut(&(dyn Error + 'static)) -> bool) -> impl FnMut(&E) -> bool
+where
+    E: Error + 'static,
+{
+    move |err| chain(err).any(&mut pred)
+}
+
+/// Predicates for the error reports, like `Box<dyn Error>`, `anyhow::Error` or `eyre::Report`.
+///
+/// These errors don't implement [`Error`] themselves, but give access to the error they wrap
+/// with [`Report`], which is where the walk through the sources starts.
+pub mod report {
+    #[cfg(feature = "alloc")]
+    use alloc::boxed::Box;
+    use core::error::Error;
+
+    use super::chain;
+
+    /// Report is implemented by the error reports wrapping an [`Error`].
+    ///
+    /// It's implemented for the boxed errors with the `alloc` feature, for `anyhow::Error` with
+    /// the `anyhow` feature, and for `eyre::Report` with the `eyre` feature. Other reports can
+    /// implement it to use the predicates of this module.
+    pub trait Report {
+        /// Return the error wrapped by the report.
+        fn as_error(&self) -> &(dyn Error + 'static);
+    }
+
+    #[cfg(feature = "alloc")]
+    impl Report for Box<dyn Error> {
+        fn as_error(&self) -> &(dyn Error + 'static) {
+            &**self
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    impl Report for Box<dyn Error + Send> {
+        fn as_error(&self) -> &(dyn Error + 'static) {
+            &**self
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    impl Report for Box<dyn Error + Send + Sync> {
+        fn as_error(&self) -> &(dyn Error + 'static) {
+            &**self
+        }
+    }
+
+    #[cfg(feature = "anyhow")]
+    impl Report for anyhow::Error {
+        fn as_error(&self) -> &(dyn Error + 'static) {
+            &**self
+        }
+    }
+
+    #[cfg(feature = "eyre")]
+    impl Report for eyre::Report {
+        fn as_error(&self) -> &(dyn Error + 'static) {
+            &**self
+        }
+    }
+
+    /// Retry the error reports with a source of type `S` matching `pred`.
+    ///
+    /// This is the same as [`when::source_is`](super::source_is) for the error reports.
+    pub fn source_is<S, E>(mut pred: impl FnMut(&S) -> bool) -> impl FnMut(&E) -> bool
+    where
+        S: Error + 'static,
+        E: Report,
+    {
+        move |err| chain(err.as_error()).any(|err| err.downcast_ref::<S>().is_some_and(&mut pred))
+    }
+
+    /// Retry the error reports with a source matching `pred`.
+    ///
+    /// This is the same as [`when::any_source`](super::any_source) for the error reports.
+    pub fn any_source<E>(
+        mut pred: impl FnMut(&(dyn Error + 'static)) -> bool,
+    ) -> impl FnMut(&E) -> bool
+    where
+        E: Report,
+    {
+        move |err| chain(err.as_error()).any(&mut pred)
+    }
+}
+
+#[cfg(all(test, feature = "alloc"))]
+mod tests {
+    use alloc::boxed::Box;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+    use core::fmt;
+
+    use super::*;
+
+    #[derive(Debug)]
+    struct Timeout;
+
+    impl fmt::Display for Timeout {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "timed out")
+        }
+    }
+
+    impl Error for Timeout {}
+
+    #[derive(Debug)]
+    struct Wrapped<S>(S);
+
+    impl<S> fmt::Display for Wrapped<S> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write!(f, "wrapped")
+        }
+    }
+
+    impl<S: Error + 'static> Error for Wrapped<S> {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn test_source_is() {
+        assert!(source_is(|_: &Timeout| true)(&Timeout));
+        assert!(source_is(|_: &Timeout| true)(&Wrapped(Wrapped(Timeout))));
+        assert!(!source_is(|_: &Timeout| false)(&Wrapped(Wrapped(Timeout))));
+
+        assert!(source_is::<Wrapped<Timeout>, _>(|_| true)(&Wrapped(
+            Wrapped(Timeout)
+        )));
+        assert!(!source_is::<Wrapped<Timeout>, _>(|_| true)(&Timeout));
+    }
+
+    #[test]
+    fn test_any_source() {
+        let mut sources = Vec::new();
+        let mut timed_out = any_source(|err| {
+            sources.push(err.to_string());
+            err.to_string() == "timed out"
+        });
+        assert!(timed_out(&Wrapped(Wrapped(Timeout))));
+        drop(timed_out);
+        assert_eq!(sources, ["wrapped", "wrapped", "timed out"]);
+    }
+
+    #[test]
+    fn test_report() {
+        let err: Box<dyn Error + Send + Sync> = Box::new(Wrapped(Timeout));
+        assert!(report::source_is(|_: &Timeout| true)(&err));
+        assert!(!report::source_is(|_: &Wrapped<Wrapped<Timeout>>| true)(
+            &err
+        ));
+        assert!(report::any_source(|err| err.to_string() == "wrapped")(&err));
+
+        let err: Box<dyn Error> = Box::new(Timeout);
+        assert!(report::source_is(|_: &Timeout| true)(&err));
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn test_report_anyhow() {
+        let err = anyhow::Error::new(Timeout).context("fetch failed");
+        assert!(report::source_is(|_: &Timeout| true)(&err));
+        assert!(!report::source_is(|_: &Wrapped<Timeout>| true)(&err));
+        assert!(report::any_source(|err| err.to_string() == "fetch failed")(
+            &err
+        ));
+    }
+
+    #[cfg(feature = "eyre")]
+    #[test]
+    fn test_report_eyre() {
+        let err = eyre::Report::new(Timeout).wrap_err("fetch failed");
+        assert!(report::source_is(|_: &Timeout| true)(&err));
+        assert!(report::any_source(|err| err.to_string() == "fetch failed")(
+            &err
+        ));
+    }
+}